@@ -0,0 +1,6 @@
+// src-tauri/src/bin/picnexus-cli.rs
+// 命令行工具入口（实现见 cli.rs）
+
+fn main() {
+    std::process::exit(picnexus_lib::cli::run())
+}
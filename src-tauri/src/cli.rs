@@ -0,0 +1,448 @@
+// src-tauri/src/cli.rs
+// 命令行工具 picnexus-cli
+//
+// 不创建 Tauri 应用，直接复用桌面程序的图床模块、用户配置（.settings.dat）和钥匙串中的加密密钥，
+// 供脚本和 CI 上传图片使用。上传结果（链接或 JSON）写入标准输出，日志全部输出到标准错误。
+//
+// 上传所需的共享状态（限流、图片处理、去重缓存）由 `CliHost` 自行持有，在普通的 tokio 运行时中运行，
+// 没有图形环境（如 CI 容器）时也可以直接使用。
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::error::{AppError, IntoAppError};
+use crate::link_format::LinkFormat;
+use crate::queue::store::{now_millis, QueueStore};
+use crate::uploader::cancel::CancelRegistry;
+use crate::uploader::dedup::DedupCache;
+use crate::uploader::host::{SidecarOutput, UploadHost};
+use crate::uploader::limiter::RateLimiter;
+use crate::uploader::orchestrator::{upload_to_services, MultiUploadRequest, DEFAULT_MAX_CONCURRENCY};
+use crate::uploader::processing::ImageProcessor;
+use crate::uploader::registry::UploaderRegistry;
+use crate::uploader::retry::RetryPolicy;
+use crate::{app_context, build_http_client, secure_config};
+
+const USAGE: &str = "\
+用法:
+  picnexus-cli upload <文件>... [选项]   上传文件，输出链接
+  picnexus-cli services                  列出可用的图床
+  picnexus-cli help                      显示帮助
+
+upload 选项:
+  -s, --services <id,id,...>  目标图床（按优先级排列），默认使用桌面程序中启用的图床
+  -f, --format <格式>         url | markdown | html | bbcode | json，默认 url
+  -a, --all                   同时输出备份图床的链接（默认只输出主力图床的链接）
+      --force                 跳过去重缓存，相同内容也重新上传
+      --retry <次数>          单个图床遇到网络错误时最多尝试的次数
+
+退出码: 0 全部成功，1 有文件上传失败，2 参数错误";
+
+/// 输出格式：链接或 JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Links(LinkFormat),
+    Json,
+}
+
+impl OutputFormat {
+    fn parse(value: &str) -> Result<Self, String> {
+        if value == "json" {
+            return Ok(OutputFormat::Json);
+        }
+        LinkFormat::parse(value)
+            .map(OutputFormat::Links)
+            .ok_or_else(|| format!("未知的输出格式: {}", value))
+    }
+}
+
+struct UploadArgs {
+    files: Vec<String>,
+    services: Vec<String>,
+    format: OutputFormat,
+    all: bool,
+    force: bool,
+    retry_attempts: Option<u32>,
+}
+
+enum CliCommand {
+    Upload(UploadArgs),
+    Services,
+    Help,
+}
+
+fn parse_args(args: &[String]) -> Result<CliCommand, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(CliCommand::Help);
+    };
+
+    match command.as_str() {
+        "upload" => parse_upload_args(rest).map(CliCommand::Upload),
+        "services" => Ok(CliCommand::Services),
+        "help" | "-h" | "--help" => Ok(CliCommand::Help),
+        other => Err(format!("未知的命令: {}", other)),
+    }
+}
+
+fn parse_upload_args(args: &[String]) -> Result<UploadArgs, String> {
+    let mut parsed = UploadArgs {
+        files: Vec::new(),
+        services: Vec::new(),
+        format: OutputFormat::Links(LinkFormat::Url),
+        all: false,
+        force: false,
+        retry_attempts: None,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} 需要一个参数", name))
+        };
+
+        match arg.as_str() {
+            "-s" | "--services" => {
+                parsed.services = value(arg)?
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            }
+            "-f" | "--format" => parsed.format = OutputFormat::parse(&value(arg)?)?,
+            "-a" | "--all" => parsed.all = true,
+            "--force" => parsed.force = true,
+            "--retry" => {
+                let attempts = value(arg)?;
+                parsed.retry_attempts = Some(
+                    attempts
+                        .parse()
+                        .map_err(|_| format!("无效的重试次数: {}", attempts))?,
+                );
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("未知的选项: {}", flag));
+            }
+            file => parsed.files.push(file.to_string()),
+        }
+    }
+
+    if parsed.files.is_empty() {
+        return Err("请指定要上传的文件".to_string());
+    }
+    Ok(parsed)
+}
+
+/// 命令行入口，返回进程退出码
+pub fn run() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return 2;
+        }
+    };
+
+    if let CliCommand::Help = command {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let mut out = match redirect_stdout() {
+        Ok(out) => out,
+        Err(e) => {
+            eprintln!("[CLI] 无法重定向标准输出: {}", e);
+            return 1;
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("[CLI] 初始化失败: {}", e);
+            return 1;
+        }
+    };
+
+    let result = runtime.block_on(async {
+        match command {
+            CliCommand::Upload(args) => {
+                let host = CliHost::init().await?;
+                upload(&host, args, &mut out).await
+            }
+            CliCommand::Services => list_services(&mut out).map(|_| true),
+            CliCommand::Help => Ok(true),
+        }
+    });
+    let _ = out.flush();
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("[CLI] {}", e);
+            1
+        }
+    }
+}
+
+/// 命令行的上传运行环境：自行持有桌面程序中由 Tauri 全局状态提供的部分
+#[derive(Clone)]
+struct CliHost(Arc<CliState>);
+
+struct CliState {
+    /// 与桌面程序相同的应用数据目录（.settings.dat、续传记录）
+    data_dir: PathBuf,
+    registry: UploaderRegistry,
+    limiter: RateLimiter,
+    processor: ImageProcessor,
+    dedup: Option<DedupCache>,
+}
+
+impl CliHost {
+    /// 按桌面程序的应用标识定位数据目录（与 Tauri 的 app_data_dir / app_config_dir 一致），
+    /// 打开去重缓存并按用户配置设置限流和图片处理
+    async fn init() -> Result<Self, AppError> {
+        let identifier = app_context().config().identifier.clone();
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| AppError::config("无法获取应用数据目录"))?
+            .join(&identifier);
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| AppError::config("无法获取应用配置目录"))?
+            .join(&identifier);
+
+        // 去重缓存与桌面程序共用 history.db，打开失败时只是不做去重
+        let dedup = match QueueStore::open(&config_dir.join("history.db")).await {
+            Ok(store) => match DedupCache::new(store.pool()).await {
+                Ok(cache) => Some(cache),
+                Err(e) => {
+                    eprintln!("[Dedup] 初始化失败，上传不做去重: {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                eprintln!("[Dedup] 初始化失败，上传不做去重: {}", e);
+                None
+            }
+        };
+
+        // 限流和图片处理按桌面程序的配置生效
+        let limiter = RateLimiter::new();
+        let processor = ImageProcessor::new();
+        match secure_config::load_settings_value(&data_dir, "config") {
+            Ok(user_config) => {
+                let user_config = user_config.unwrap_or_default();
+                limiter.apply_config(&user_config);
+                processor.apply_config(&user_config);
+            }
+            Err(e) => eprintln!("[CLI] 读取用户配置失败，使用默认的限流和图片处理设置: {}", e),
+        }
+
+        Ok(Self(Arc::new(CliState {
+            data_dir,
+            registry: UploaderRegistry::with_builtin(),
+            limiter,
+            processor,
+            dedup,
+        })))
+    }
+}
+
+#[async_trait]
+impl UploadHost for CliHost {
+    /// 没有前端，进度等事件直接丢弃
+    fn emit_event(&self, _event: &str, _payload: serde_json::Value) {}
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.0.limiter)
+    }
+
+    fn dedup_cache(&self) -> Option<&DedupCache> {
+        self.0.dedup.as_ref()
+    }
+
+    fn image_processor(&self) -> Option<&ImageProcessor> {
+        Some(&self.0.processor)
+    }
+
+    fn uploader_registry(&self) -> Option<&UploaderRegistry> {
+        Some(&self.0.registry)
+    }
+
+    /// 命令行不支持按 ID 取消单个上传
+    fn cancel_registry(&self) -> Option<&CancelRegistry> {
+        None
+    }
+
+    fn app_data_dir(&self) -> Result<PathBuf, AppError> {
+        Ok(self.0.data_dir.clone())
+    }
+
+    /// sidecar 与可执行文件位于同一目录（与 Tauri 打包后的布局一致）
+    async fn run_sidecar(&self, name: &str, args: &[&str]) -> Result<SidecarOutput, AppError> {
+        let program = std::env::current_exe()
+            .into_external_err_with("无法获取程序路径")?
+            .with_file_name(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+
+        let output = tokio::process::Command::new(&program)
+            .args(args)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| AppError::external(format!("启动 sidecar 失败 ({}): {}", program.display(), e)))?;
+        println!("[Sidecar] {} 退出，状态: {}", name, output.status);
+
+        Ok(SidecarOutput {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+async fn upload(host: &CliHost, args: UploadArgs, out: &mut impl Write) -> Result<bool, AppError> {
+    let user_config = secure_config::load_user_config(&host.0.data_dir)?;
+
+    let services = if args.services.is_empty() {
+        secure_config::enabled_services(&user_config)
+    } else {
+        args.services.clone()
+    };
+    if services.is_empty() {
+        return Err(AppError::validation(
+            "没有可用的图床，请通过 --services 指定，或在桌面程序中启用图床",
+        ));
+    }
+
+    let configs = secure_config::service_configs(&user_config);
+    let weibo_prefix = secure_config::weibo_link_prefix(&user_config);
+    let retry = args
+        .retry_attempts
+        .map_or_else(RetryPolicy::default, RetryPolicy::with_max_attempts);
+
+    let registry = &host.0.registry;
+    let http_client = build_http_client();
+
+    let mut all_succeeded = true;
+    let mut json_results = Vec::new();
+
+    for (index, file) in args.files.iter().enumerate() {
+        let outcome = match std::fs::canonicalize(file) {
+            Ok(path) => {
+                let request = MultiUploadRequest {
+                    id: format!("cli-{}-{}", now_millis(), index),
+                    file_path: path.to_string_lossy().into_owned(),
+                    services: services.clone(),
+                    configs: configs.clone(),
+                    max_concurrency: DEFAULT_MAX_CONCURRENCY,
+                    retry,
+                    force_fresh: args.force,
+                };
+                upload_to_services(host.clone(), registry, http_client.clone(), request).await
+            }
+            Err(e) => Err(AppError::file_io(format!("无法读取文件 {}: {}", file, e))),
+        };
+
+        if outcome.is_err() {
+            all_succeeded = false;
+        }
+
+        let OutputFormat::Links(link_format) = args.format else {
+            json_results.push(match &outcome {
+                Ok(output) => serde_json::json!({ "file": file, "success": true, "output": output }),
+                Err(e) => serde_json::json!({ "file": file, "success": false, "error": e }),
+            });
+            continue;
+        };
+
+        match outcome {
+            Ok(output) => {
+                for failure in output.partial_failures.iter().flatten() {
+                    eprintln!("[CLI] {} 上传到 {} 失败: {}", file, failure.service_id, failure.error);
+                }
+                for link in output.links(args.all, weibo_prefix.as_deref()) {
+                    writeln!(out, "{}", link_format.format(&link, display_name(file)))
+                        .map_err(|e| AppError::file_io(format!("写入标准输出失败: {}", e)))?;
+                }
+            }
+            Err(e) => eprintln!("[CLI] {} 上传失败: {}", file, e),
+        }
+    }
+
+    if args.format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&json_results)
+            .map_err(|e| AppError::external(format!("序列化结果失败: {}", e)))?;
+        writeln!(out, "{}", json).map_err(|e| AppError::file_io(format!("写入标准输出失败: {}", e)))?;
+    }
+
+    Ok(all_succeeded)
+}
+
+fn list_services(out: &mut impl Write) -> Result<(), AppError> {
+    for info in UploaderRegistry::with_builtin().list() {
+        writeln!(out, "{}\t{}", info.id, info.name)
+            .map_err(|e| AppError::file_io(format!("写入标准输出失败: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn display_name(file: &str) -> &str {
+    Path::new(file)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(file)
+}
+
+/// 把标准输出重定向到标准错误，返回指向原标准输出的文件
+///
+/// 各模块的日志都通过 println! 输出，重定向后 stdout 只包含上传结果，可以直接用于管道
+#[cfg(unix)]
+fn redirect_stdout() -> std::io::Result<std::fs::File> {
+    use std::os::fd::FromRawFd;
+
+    std::io::stdout().flush()?;
+    // SAFETY: 只操作进程自身的标准输入输出描述符，dup 返回的新描述符由 File 接管
+    unsafe {
+        let saved = libc::dup(libc::STDOUT_FILENO);
+        if saved < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let out = std::fs::File::from_raw_fd(saved);
+        if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(out)
+    }
+}
+
+/// 把标准输出重定向到标准错误，返回指向原标准输出的文件
+///
+/// Windows 上标准库每次写入都会重新获取标准句柄，替换 STD_OUTPUT_HANDLE 即可
+#[cfg(windows)]
+fn redirect_stdout() -> std::io::Result<std::fs::File> {
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const STD_ERROR_HANDLE: u32 = -12i32 as u32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> RawHandle;
+        fn SetStdHandle(std_handle: u32, handle: RawHandle) -> i32;
+    }
+
+    std::io::stdout().flush()?;
+    // SAFETY: 只替换进程自身的标准句柄，原标准输出句柄由 File 接管
+    unsafe {
+        let stdout = GetStdHandle(STD_OUTPUT_HANDLE);
+        let stderr = GetStdHandle(STD_ERROR_HANDLE);
+        if SetStdHandle(STD_OUTPUT_HANDLE, stderr) == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(std::fs::File::from_raw_handle(stdout))
+    }
+}
@@ -0,0 +1,274 @@
+// src-tauri/src/clipboard_watcher.rs
+// 剪贴板监听自动上传
+//
+// 开启后（`config.clipboardWatcher`）在后台定期检查剪贴板，出现新图片时自动上传，
+// 并把剪贴板内容替换为用户选择格式的链接，截图后可以直接粘贴链接。
+// 按图片内容的 SHA-256 判断是否为新图片：开启时剪贴板中已有的图片、已经上传过的图片都不会重复上传。
+//
+// 监听在独立线程中运行并一直持有剪贴板对象：Linux（X11）上写入剪贴板的内容由写入方进程提供，
+// 对象释放后内容可能丢失。
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use arboard::{Clipboard, ImageData};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands::clipboard::save_image_to_temp;
+use crate::error::AppError;
+use crate::history::HistoryStore;
+use crate::link_format::LinkFormat;
+use crate::queue::store::now_millis;
+use crate::secure_config;
+use crate::uploader::orchestrator::{upload_to_services, MultiUploadRequest, DEFAULT_MAX_CONCURRENCY};
+use crate::uploader::registry::UploaderRegistry;
+use crate::uploader::retry::RetryPolicy;
+use crate::HttpClient;
+
+/// 默认检查间隔
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+
+/// 检查间隔下限
+const MIN_INTERVAL_MS: u64 = 300;
+
+/// 记录已处理图片哈希的数量上限
+const SEEN_LIMIT: usize = 1024;
+
+/// 剪贴板监听配置（`config.clipboardWatcher`）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardWatchSettings {
+    pub enabled: bool,
+    /// 替换到剪贴板的链接格式
+    pub link_format: LinkFormat,
+    /// 目标图床，为空时使用当前启用的图床
+    pub services: Vec<String>,
+    /// 是否写入上传历史
+    pub record_history: bool,
+    /// 检查间隔（毫秒）
+    pub interval_ms: u64,
+}
+
+impl Default for ClipboardWatchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            link_format: LinkFormat::Url,
+            services: Vec::new(),
+            record_history: true,
+            interval_ms: DEFAULT_INTERVAL_MS,
+        }
+    }
+}
+
+/// `clipboard-watcher://uploaded` 事件载荷
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ClipboardUploadedEvent<'a> {
+    file_path: &'a str,
+    /// 写入剪贴板的文本
+    link: &'a str,
+}
+
+/// 正在运行的监听
+struct RunningWatcher {
+    settings: ClipboardWatchSettings,
+    /// 释放时监听线程退出
+    _stop: mpsc::Sender<()>,
+}
+
+/// 剪贴板监听（作为 Tauri 全局状态注册）
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    running: Mutex<Option<RunningWatcher>>,
+}
+
+impl ClipboardWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 应用用户配置中的 `clipboardWatcher`，格式错误时保持当前状态
+    pub fn apply_config(&self, app: &AppHandle, user_config: &serde_json::Value) {
+        match secure_config::section::<ClipboardWatchSettings>(user_config, "clipboardWatcher") {
+            Ok(settings) => self.apply(app, settings),
+            Err(e) => eprintln!("[ClipboardWatcher] clipboardWatcher 配置格式错误: {}", e),
+        }
+    }
+
+    /// 按配置启动、重启或停止监听
+    fn apply(&self, app: &AppHandle, settings: ClipboardWatchSettings) {
+        let mut running = self.running.lock().unwrap_or_else(|p| p.into_inner());
+        if running.as_ref().is_some_and(|r| r.settings == settings) {
+            return;
+        }
+
+        if running.take().is_some() {
+            println!("[ClipboardWatcher] 已停止监听");
+        }
+
+        if !settings.enabled {
+            return;
+        }
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread_app = app.clone();
+        let thread_settings = settings.clone();
+        let spawned = std::thread::Builder::new()
+            .name("clipboard-watcher".into())
+            .spawn(move || watch(thread_app, thread_settings, stopped));
+
+        match spawned {
+            Ok(_) => {
+                println!("[ClipboardWatcher] 开始监听剪贴板");
+                *running = Some(RunningWatcher {
+                    settings,
+                    _stop: stop,
+                });
+            }
+            Err(e) => eprintln!("[ClipboardWatcher] 启动失败: {}", e),
+        }
+    }
+}
+
+/// 图片内容哈希（尺寸 + 像素数据）
+fn image_hash(image: &ImageData) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update((image.width as u64).to_le_bytes());
+    hasher.update((image.height as u64).to_le_bytes());
+    hasher.update(&image.bytes);
+    hex::encode(hasher.finalize())
+}
+
+/// 监听线程：定期检查剪贴板，直到停止信号的发送端被释放
+fn watch(app: AppHandle, settings: ClipboardWatchSettings, stopped: mpsc::Receiver<()>) {
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            eprintln!("[ClipboardWatcher] 无法访问剪贴板: {}", e);
+            return;
+        }
+    };
+
+    let interval = Duration::from_millis(settings.interval_ms.max(MIN_INTERVAL_MS));
+    let mut seen: HashSet<String> = HashSet::new();
+
+    // 开启监听时剪贴板中已有的图片不上传
+    if let Ok(image) = clipboard.get_image() {
+        seen.insert(image_hash(&image));
+    }
+
+    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        let image = match clipboard.get_image() {
+            Ok(image) => image,
+            Err(arboard::Error::ContentNotAvailable) => continue,
+            Err(e) => {
+                eprintln!("[ClipboardWatcher] 读取剪贴板失败: {}", e);
+                continue;
+            }
+        };
+
+        let hash = image_hash(&image);
+        if seen.contains(&hash) {
+            continue;
+        }
+        if seen.len() >= SEEN_LIMIT {
+            seen.clear();
+        }
+        // 上传失败也记为已处理，避免每次检查都重试同一张图片
+        seen.insert(hash.clone());
+
+        println!(
+            "[ClipboardWatcher] 检测到新图片: {}x{}",
+            image.width, image.height
+        );
+        let result = save_image_to_temp(image).and_then(|file_path| {
+            let link = tauri::async_runtime::block_on(upload(&app, &settings, &file_path))?;
+            Ok((file_path, link))
+        });
+
+        match result {
+            Ok((file_path, link)) => {
+                // 上传期间用户可能复制了其他内容，只在剪贴板仍是这张图片时替换
+                let unchanged = clipboard
+                    .get_image()
+                    .is_ok_and(|current| image_hash(&current) == hash);
+                if !unchanged {
+                    println!("[ClipboardWatcher] 剪贴板内容已变化，不替换链接: {}", link);
+                } else if let Err(e) = clipboard.set_text(link.clone()) {
+                    eprintln!("[ClipboardWatcher] 写入剪贴板失败: {}", e);
+                }
+
+                println!("[ClipboardWatcher] 上传完成: {}", link);
+                let _ = app.emit(
+                    "clipboard-watcher://uploaded",
+                    ClipboardUploadedEvent {
+                        file_path: &file_path,
+                        link: &link,
+                    },
+                );
+            }
+            Err(e) => {
+                eprintln!("[ClipboardWatcher] 上传失败: {}", e);
+                let _ = app.emit("clipboard-watcher://failed", &e);
+            }
+        }
+    }
+
+    println!("[ClipboardWatcher] 监听线程已退出");
+}
+
+/// 上传剪贴板图片，返回按配置格式化后的链接
+async fn upload(app: &AppHandle, settings: &ClipboardWatchSettings, file_path: &str) -> Result<String, AppError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::config(format!("无法获取应用数据目录: {}", e)))?;
+    let user_config = secure_config::load_user_config(&app_data_dir)?;
+
+    let services = if settings.services.is_empty() {
+        secure_config::enabled_services(&user_config)
+    } else {
+        settings.services.clone()
+    };
+
+    let registry = app.state::<UploaderRegistry>();
+    let http_client = app.state::<HttpClient>().0.clone();
+    let request = MultiUploadRequest {
+        id: format!("clipboard-{}-{:08x}", now_millis(), rand::random::<u32>()),
+        file_path: file_path.to_string(),
+        services,
+        configs: secure_config::service_configs(&user_config),
+        max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        retry: RetryPolicy::default(),
+        force_fresh: false,
+    };
+    let output = upload_to_services(app.clone(), &registry, http_client, request).await?;
+
+    if settings.record_history {
+        match app.try_state::<HistoryStore>() {
+            Some(history) => {
+                if let Err(e) = history.record_upload(app, file_path, &output, "clipboard-watcher").await {
+                    eprintln!("[ClipboardWatcher] {}", e);
+                }
+            }
+            None => eprintln!("[ClipboardWatcher] 历史记录不可用，未保存: {}", file_path),
+        }
+    }
+
+    let weibo_prefix = secure_config::weibo_link_prefix(&user_config);
+    let url = output
+        .links(false, weibo_prefix.as_deref())
+        .into_iter()
+        .next()
+        .unwrap_or(output.primary_url);
+    let file_name = std::path::Path::new(file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("image.png");
+    Ok(settings.link_format.format(&url, file_name))
+}
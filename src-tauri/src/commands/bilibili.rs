@@ -1,13 +1,23 @@
 // src-tauri/src/commands/bilibili.rs
 // 哔哩哔哩图床上传命令
 
-use tauri::Window;
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::multipart;
 use regex::Regex;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
+
+/// 文件大小限制：10MB
+const MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
 
 /// 哔哩哔哩上传结果
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,21 +128,40 @@ pub async fn test_bilibili_connection(bilibili_cookie: String) -> Result<String,
 /// 上传图片到哔哩哔哩
 #[tauri::command]
 pub async fn upload_to_bilibili(
-    _window: Window,
-    _id: String,
+    window: Window,
+    id: String,
     file_path: String,
     bilibili_cookie: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<BilibiliUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("bilibili", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("bilibili", file_path, || upload_bilibili_internal(&ctx, file_path, &bilibili_cookie))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// 哔哩哔哩上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_bilibili_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    bilibili_cookie: &str,
 ) -> Result<BilibiliUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
     println!("[Bilibili] 开始上传文件: {}", file_path);
 
     // 1. 提取 SESSDATA 和 csrf
-    let (sessdata, csrf) = extract_bilibili_cookies(&bilibili_cookie)?;
+    let (sessdata, csrf) = extract_bilibili_cookies(bilibili_cookie)?;
 
     // 2. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    progress.step(1, "读取文件...");
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 3. 检查文件大小（哔哩哔哩限制 10MB）
-    const MAX_SIZE: u64 = 10 * 1024 * 1024; // 10MB
     if file_size > MAX_SIZE {
         return Err(AppError::validation(format!(
             "文件大小 ({:.2}MB) 超过哔哩哔哩限制 (10MB)",
@@ -141,32 +170,20 @@ pub async fn upload_to_bilibili(
     }
 
     // 4. 获取文件名和扩展名
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
     // 5. 验证文件类型
-    if !["jpg", "jpeg", "png", "gif", "webp"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF、WebP 格式的图片"));
     }
 
     // 6. 确定 MIME 类型
-    let mime_type = match ext.as_str() {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        _ => "image/png",
-    };
+    let mime_type = file.content_type();
 
     // 7. 构建 multipart form
-    let part = multipart::Part::bytes(buffer)
-        .file_name(file_name.to_string())
+    let part = file.multipart_part(&progress)
+        .file_name(file.upload_file_name())
         .mime_str(mime_type)
         .into_validation_err_with("无法设置 MIME 类型")?;
 
@@ -175,6 +192,7 @@ pub async fn upload_to_bilibili(
         .text("csrf", csrf);
 
     // 8. 发送请求
+    progress.step(2, "正在上传...");
     let client = reqwest::Client::new();
     let response = client
         .post("https://mall.bilibili.com/mall-up-c/common/image")
@@ -187,8 +205,10 @@ pub async fn upload_to_bilibili(
         .send()
         .await
         .into_network_err_with("请求失败")?;
+    let response = reject_transient("哔哩哔哩", response).await?;
 
     // 9. 解析响应
+    progress.step(3, "处理响应...");
     let response_text = response.text().await
         .into_network_err_with("无法读取响应")?;
 
@@ -222,3 +242,47 @@ pub async fn upload_to_bilibili(
         size: file_size,
     })
 }
+
+/// 哔哩哔哩图床配置（对应前端 config.services.bilibili）
+#[derive(Deserialize)]
+struct BilibiliConfig {
+    cookie: String,
+}
+
+/// 哔哩哔哩上传器
+pub struct BilibiliUploader;
+
+#[async_trait::async_trait]
+impl Uploader for BilibiliUploader {
+    fn id(&self) -> &'static str {
+        "bilibili"
+    }
+
+    fn name(&self) -> &'static str {
+        "哔哩哔哩"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: Some(MAX_SIZE),
+            max_gif_size: None,
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: BilibiliConfig = parse_config(self.name(), config)?;
+        if config.cookie.trim().is_empty() {
+            return Err(AppError::config("哔哩哔哩 Cookie 未配置"));
+        }
+
+        let res = upload_bilibili_internal(ctx, file_path, &config.cookie).await?;
+        Ok(UploadOutput::new(self.id(), res.url.clone(), res.url).with_size(res.size))
+    }
+}
@@ -1,12 +1,22 @@
 // src-tauri/src/commands/chaoxing.rs
 // 超星图床上传命令
 
-use tauri::Window;
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::multipart;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
+
+/// 文件大小限制：200MB
+const MAX_SIZE: u64 = 200 * 1024 * 1024;
+
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
 
 /// 超星上传结果
 #[derive(Debug, Serialize, Deserialize)]
@@ -105,11 +115,28 @@ pub async fn test_chaoxing_connection(chaoxing_cookie: String) -> Result<String,
 /// 上传图片到超星图床
 #[tauri::command]
 pub async fn upload_to_chaoxing(
-    _window: Window,
-    _id: String,
+    window: Window,
+    id: String,
     file_path: String,
     chaoxing_cookie: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<ChaoxingUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("chaoxing", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("chaoxing", file_path, || upload_chaoxing_internal(&ctx, file_path, &chaoxing_cookie))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// 超星上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_chaoxing_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    chaoxing_cookie: &str,
 ) -> Result<ChaoxingUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
     println!("[Chaoxing] 开始上传文件: {}", file_path);
 
     // 1. 检查 Cookie
@@ -118,10 +145,12 @@ pub async fn upload_to_chaoxing(
     }
 
     // 2. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    progress.step(1, "读取文件...");
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 3. 检查文件大小（超星限制 200MB）
-    const MAX_SIZE: u64 = 200 * 1024 * 1024; // 200MB
     if file_size > MAX_SIZE {
         return Err(AppError::validation(format!(
             "文件大小 ({:.2}MB) 超过超星限制 (200MB)",
@@ -130,43 +159,31 @@ pub async fn upload_to_chaoxing(
     }
 
     // 4. 获取文件名和扩展名
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
     // 5. 验证文件类型
-    if !["jpg", "jpeg", "png", "gif", "webp", "bmp"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF、WebP、BMP 格式的图片"));
     }
 
     // 6. 确定 MIME 类型
-    let mime_type = match ext.as_str() {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        "bmp" => "image/bmp",
-        _ => "image/png",
-    };
+    let mime_type = file.content_type();
 
     // 7. 构建 multipart form（超星使用 attrFile 作为字段名）
-    let part = multipart::Part::bytes(buffer)
-        .file_name(file_name.to_string())
+    let part = file.multipart_part(&progress)
+        .file_name(file.upload_file_name())
         .mime_str(mime_type)
         .into_validation_err_with("无法设置 MIME 类型")?;
 
     let form = multipart::Form::new().part("attrFile", part);
 
     // 8. 发送请求（超星支持大文件，超时设为 120 秒）
+    progress.step(2, "正在上传...");
     let client = reqwest::Client::new();
     let response = client
         .post("https://notice.chaoxing.com/pc/files/uploadNoticeFile")
-        .header("Cookie", &chaoxing_cookie)
+        .header("Cookie", chaoxing_cookie)
         .header("Referer", "https://notice.chaoxing.com/")
         .header("Origin", "https://notice.chaoxing.com")
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/142.0.0.0 Safari/537.36")
@@ -175,8 +192,10 @@ pub async fn upload_to_chaoxing(
         .send()
         .await
         .into_network_err_with("请求失败")?;
+    let response = reject_transient("超星", response).await?;
 
     // 9. 解析响应
+    progress.step(3, "处理响应...");
     let response_text = response.text().await
         .into_network_err_with("无法读取响应")?;
 
@@ -209,3 +228,47 @@ pub async fn upload_to_chaoxing(
         size: file_size,
     })
 }
+
+/// 超星图床配置（对应前端 config.services.chaoxing）
+#[derive(Deserialize)]
+struct ChaoxingConfig {
+    cookie: String,
+}
+
+/// 超星上传器
+pub struct ChaoxingUploader;
+
+#[async_trait::async_trait]
+impl Uploader for ChaoxingUploader {
+    fn id(&self) -> &'static str {
+        "chaoxing"
+    }
+
+    fn name(&self) -> &'static str {
+        "超星"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: Some(MAX_SIZE),
+            max_gif_size: None,
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: ChaoxingConfig = parse_config(self.name(), config)?;
+        if config.cookie.trim().is_empty() {
+            return Err(AppError::config("超星 Cookie 未配置"));
+        }
+
+        let res = upload_chaoxing_internal(ctx, file_path, &config.cookie).await?;
+        Ok(UploadOutput::new(self.id(), res.url.clone(), res.url).with_size(res.size))
+    }
+}
@@ -2,7 +2,7 @@
 // 剪贴板图片处理命令
 // v2.10: 迁移到 AppError 统一错误类型
 
-use arboard::Clipboard;
+use arboard::{Clipboard, ImageData};
 use image::ImageOutputFormat;
 use std::io::Cursor;
 
@@ -44,6 +44,11 @@ pub fn read_clipboard_image() -> Result<String, AppError> {
         image_data.width, image_data.height
     );
 
+    save_image_to_temp(image_data)
+}
+
+/// 把剪贴板图片编码为 PNG 并保存为临时文件，返回文件路径（剪贴板监听也使用）
+pub(crate) fn save_image_to_temp(image_data: ImageData) -> Result<String, AppError> {
     // 将 RGBA 数据转换为 PNG 格式
     let rgba_image = image::RgbaImage::from_raw(
         image_data.width as u32,
@@ -0,0 +1,51 @@
+// src-tauri/src/commands/duplicates.rs
+// 近似重复检测命令（感知哈希的计算与存储见 src/phash.rs）
+
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::phash::{self, DuplicateCluster, PerceptualHashStore, SimilarItem, DEFAULT_MAX_DISTANCE};
+
+/// 阈值上限（超过一半的位不同时已不算相似）
+const MAX_DISTANCE_LIMIT: u32 = 32;
+
+fn threshold(value: Option<u32>) -> u32 {
+    value.unwrap_or(DEFAULT_MAX_DISTANCE).min(MAX_DISTANCE_LIMIT)
+}
+
+/// 为历史记录计算感知哈希（前端保存历史记录后调用，后台执行）
+#[tauri::command]
+pub fn record_perceptual_hash(app: AppHandle, history_id: String, file_path: String) {
+    phash::record_in_background(&app, &history_id, &file_path);
+}
+
+/// 上传前查找与文件近似的历史记录（缩放、转格式、重新保存过的同一张图片）
+///
+/// `max_distance` 为汉明距离阈值，默认 10；按距离从近到远返回
+#[tauri::command]
+pub async fn find_near_duplicates(
+    file_path: String,
+    max_distance: Option<u32>,
+    store: State<'_, PerceptualHashStore>,
+) -> Result<Vec<SimilarItem>, AppError> {
+    let hash = phash::compute_async(&file_path).await?;
+    let similar = store.find_similar(hash, threshold(max_distance)).await?;
+    println!("[PHash] {} 找到 {} 条近似记录", file_path, similar.len());
+    Ok(similar)
+}
+
+/// 把整个历史记录按相似度分组，返回包含两条以上记录的分组
+///
+/// 只对已有哈希的记录分组；还没有哈希的历史记录（本地文件仍存在时）在后台补算，
+/// 补算完成后再次调用即可包含这些记录
+#[tauri::command]
+pub async fn find_duplicate_clusters(
+    app: AppHandle,
+    max_distance: Option<u32>,
+    store: State<'_, PerceptualHashStore>,
+) -> Result<Vec<DuplicateCluster>, AppError> {
+    phash::backfill_in_background(&app);
+    let clusters = store.clusters(threshold(max_distance)).await?;
+    println!("[PHash] 共 {} 组近似重复", clusters.len());
+    Ok(clusters)
+}
@@ -1,12 +1,18 @@
 // src-tauri/src/commands/github.rs
 // GitHub 图床上传命令
 
-use tauri::{Window, Emitter};
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
-use base64::{Engine as _, engine::general_purpose::STANDARD};
+use futures::StreamExt;
+use tokio_util::bytes::Bytes;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::dedup::target_digest;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 /// GitHub 上传结果
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,12 +24,32 @@ pub struct GithubUploadResult {
     pub remote_path: Option<String>,
 }
 
-/// GitHub 上传请求体
-#[derive(Debug, Serialize)]
-struct GithubUploadRequest {
-    message: String,
-    content: String,
-    branch: String,
+/// 构建 GitHub 上传请求体 `{"message", "branch", "content"}`
+///
+/// Contents API 要求文件内容以 Base64 放在 JSON 中，这里边读文件边编码，
+/// 不需要把文件和编码结果整体放进内存。返回请求体和 Content-Length
+fn build_upload_body(
+    file: &FileBody,
+    message: &str,
+    branch: &str,
+    progress: &ProgressReporter,
+) -> Result<(reqwest::Body, u64), AppError> {
+    let prefix = format!(
+        "{{\"message\":{},\"branch\":{},\"content\":\"",
+        serde_json::to_string(message).into_validation_err_with("序列化请求失败")?,
+        serde_json::to_string(branch).into_validation_err_with("序列化请求失败")?,
+    );
+    let suffix = "\"}";
+
+    let content_length = prefix.len() as u64 + file.base64_len() + suffix.len() as u64;
+    progress.set_total(content_length);
+
+    // Base64 字符不需要 JSON 转义，可以直接拼接
+    let stream = futures::stream::iter([Ok::<_, std::io::Error>(Bytes::from(prefix))])
+        .chain(file.base64_stream())
+        .chain(futures::stream::iter([Ok(Bytes::from_static(suffix.as_bytes()))]));
+
+    Ok((reqwest::Body::wrap_stream(progress.track(stream)), content_length))
 }
 
 /// GitHub 上传响应
@@ -54,21 +80,38 @@ pub async fn upload_to_github(
     repo: String,
     branch: String,
     path: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<GithubUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("github", &file_path).await?;
+    let file_path = prepared.path();
+    let target = target_digest(&[&owner, &repo, &branch, &path]);
+    ctx.run_deduped_at("github", &target, file_path, || upload_github_internal(&ctx, file_path, &github_token, &owner, &repo, &branch, &path))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// GitHub 上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_github_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    github_token: &str,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    path: &str,
 ) -> Result<GithubUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
     println!("[GitHub] 开始上传文件: {}", file_path);
 
-    // 发送进度: 0% - 读取文件
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "读取文件...",
-        "step_index": 1,
-        "total_steps": 3
-    }));
+    // 进度: 读取文件
+    progress.step(1, "读取文件...");
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 验证文件大小（限制 25MB）
     if file_size > MAX_FILE_SIZE {
@@ -79,25 +122,15 @@ pub async fn upload_to_github(
     }
 
     // 3. 获取文件名
-    let file_name = std::path::Path::new(&file_path)
+    let file_name = std::path::Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| AppError::validation("无法获取文件名"))?;
 
-    // 发送进度: 33% - 编码文件
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 33,
-        "total": 100,
-        "step": "编码文件...",
-        "step_index": 2,
-        "total_steps": 3
-    }));
-
-    // 4. Base64 编码文件内容
-    let content = STANDARD.encode(&buffer);
+    // 进度: 准备请求
+    progress.step(2, "准备请求...");
 
-    // 5. 构建远程路径
+    // 4. 构建远程路径
     let remote_path = format!("{}/{}", path.trim_end_matches('/'), file_name);
 
     // 对每个路径段分别编码，避免将 / 编码为 %2F
@@ -112,36 +145,29 @@ pub async fn upload_to_github(
         owner, repo, encoded_path
     );
 
-    let request_body = GithubUploadRequest {
-        message: format!("Upload {} via PicNexus", file_name),
-        content,
-        branch: branch.clone(),
-    };
-
-    // 发送进度: 66% - 正在上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 66,
-        "total": 100,
-        "step": "正在上传...",
-        "step_index": 3,
-        "total_steps": 3
-    }));
-
-    // 6. 发送请求到 GitHub API
+    let message = format!("Upload {} via PicNexus", file_name);
+    let (request_body, content_length) = build_upload_body(&file, &message, branch, &progress)?;
+
+    // 进度: 正在上传
+    progress.step(3, "正在上传...");
+
+    // 5. 发送请求到 GitHub API
     let client = reqwest::Client::new();
     let response = client
         .put(&url)
         .header("Authorization", format!("token {}", github_token))
         .header("User-Agent", "PicNexus")
         .header("Accept", "application/vnd.github.v3+json")
-        .json(&request_body)
+        .header("Content-Type", "application/json")
+        .header("Content-Length", content_length)
+        .body(request_body)
         .timeout(std::time::Duration::from_secs(120))
         .send()
         .await
         .into_network_err_with("上传请求失败")?;
+    let response = reject_transient("GitHub", response).await?;
 
-    // 7. 解析响应
+    // 6. 解析响应
     let status = response.status();
     let response_text = response.text().await
         .into_network_err_with("无法读取响应")?;
@@ -173,3 +199,160 @@ pub async fn upload_to_github(
         remote_path: Some(remote_path),
     })
 }
+
+/// GitHub 图床配置（对应前端 config.services.github）
+///
+/// CDN 加速配置由前端处理，这里只处理自定义域名
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GithubConfig {
+    token: String,
+    owner: String,
+    repo: String,
+    #[serde(default = "default_branch")]
+    branch: String,
+    #[serde(default = "default_path")]
+    path: String,
+    #[serde(default)]
+    custom_domain: Option<String>,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+fn default_path() -> String {
+    "images/".to_string()
+}
+
+/// GitHub 删除请求体
+#[derive(Debug, Serialize)]
+struct GithubDeleteRequest {
+    message: String,
+    sha: String,
+    branch: String,
+}
+
+/// GitHub 上传器
+pub struct GithubUploader;
+
+#[async_trait::async_trait]
+impl Uploader for GithubUploader {
+    fn id(&self) -> &'static str {
+        "github"
+    }
+
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: Some(MAX_FILE_SIZE),
+            max_gif_size: None,
+            accepted_formats: &[],
+            supports_delete: true,
+        }
+    }
+
+    fn dedup_target(&self, config: &serde_json::Value) -> String {
+        let Ok(config) = parse_config::<GithubConfig>(self.name(), config) else {
+            return String::new();
+        };
+        let branch = if config.branch.trim().is_empty() { default_branch() } else { config.branch };
+        target_digest(&[
+            &config.owner,
+            &config.repo,
+            &branch,
+            &config.path,
+            config.custom_domain.as_deref().unwrap_or_default(),
+        ])
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: GithubConfig = parse_config(self.name(), config)?;
+        if config.token.trim().is_empty() || config.owner.trim().is_empty() || config.repo.trim().is_empty() {
+            return Err(AppError::config("GitHub Token、仓库所有者和仓库名称不能为空"));
+        }
+        let branch = if config.branch.trim().is_empty() { default_branch() } else { config.branch.clone() };
+
+        let res = upload_github_internal(
+            ctx, file_path, &config.token, &config.owner, &config.repo, &branch, &config.path,
+        ).await?;
+
+        let remote_path = res.remote_path.clone().unwrap_or_default();
+        let url = match config.custom_domain.as_deref().filter(|d| !d.trim().is_empty()) {
+            Some(domain) if !remote_path.is_empty() => {
+                format!("{}/{}", domain.trim_end_matches('/'), remote_path)
+            }
+            _ => res.url.clone(),
+        };
+        let file_key = res.sha.clone()
+            .or_else(|| res.remote_path.clone())
+            .unwrap_or_else(|| res.url.clone());
+
+        let mut output = UploadOutput::new(self.id(), file_key, url)
+            .with_meta("rawUrl", res.url)
+            .with_meta("branch", branch);
+        if let Some(sha) = res.sha {
+            output = output.with_meta("sha", sha);
+        }
+        if let Some(remote_path) = res.remote_path {
+            output = output.with_meta("remotePath", remote_path);
+        }
+        Ok(output)
+    }
+
+    async fn delete(
+        &self,
+        ctx: &UploadContext,
+        output: &UploadOutput,
+        config: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let config: GithubConfig = parse_config(self.name(), config)?;
+        let sha = output.meta_str("sha")
+            .ok_or_else(|| AppError::validation("缺少 GitHub 文件 sha"))?;
+        let remote_path = output.meta_str("remotePath")
+            .ok_or_else(|| AppError::validation("缺少 GitHub 文件路径"))?;
+        let branch = output.meta_str("branch").map(str::to_string).unwrap_or(config.branch);
+
+        let encoded_path = remote_path
+            .split('/')
+            .map(|segment| urlencoding::encode(segment))
+            .collect::<Vec<_>>()
+            .join("/");
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/contents/{}",
+            config.owner, config.repo, encoded_path
+        );
+
+        let response = ctx.http_client
+            .delete(&url)
+            .header("Authorization", format!("token {}", config.token))
+            .header("User-Agent", "PicNexus")
+            .header("Accept", "application/vnd.github.v3+json")
+            .json(&GithubDeleteRequest {
+                message: format!("Delete {} via PicNexus", remote_path),
+                sha: sha.to_string(),
+                branch,
+            })
+            .timeout(std::time::Duration::from_secs(30))
+            .send()
+            .await
+            .into_network_err_with("删除请求失败")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::upload("GitHub", format!("删除失败 (HTTP {}): {}", status, body)));
+        }
+
+        println!("[GitHub] 删除成功: {}", remote_path);
+        Ok(())
+    }
+}
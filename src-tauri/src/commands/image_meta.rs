@@ -1,13 +1,20 @@
 // src-tauri/src/commands/image_meta.rs
 // 图片元数据提取命令
 // 性能优化：使用 imagesize crate 只读取图片头部字节，避免完整解码
+//
+// 扩展元数据（`extended = true`）额外读取 EXIF（拍摄时间、相机、镜头、方向、色彩空间）
+// 并遍历文件结构（位深、透明通道、ICC 配置、动图帧数和时长）。遍历时只读取各数据块的头部，
+// 像素数据直接跳过，同样不解码图片。默认不读取，布局和历史记录使用的快速路径保持不变。
 
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 
+use exif::{In, Tag, Value};
 use serde::Serialize;
 
 use crate::error::AppError;
+use crate::image_format::ImageType;
 
 /// 图片元数据结构（简化版）
 /// 用于前端 Justified Layout 布局和历史记录存储
@@ -22,8 +29,38 @@ pub struct ImageMetadata {
     pub aspect_ratio: f64,
     /// 文件大小（字节）
     pub file_size: u64,
-    /// 图片格式（jpg, png, webp, gif, bmp 等）
+    /// 图片格式（jpg, png, webp, gif, bmp 等），按文件内容识别
     pub format: String,
+    /// 扩展元数据，只在请求时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended: Option<ExtendedMetadata>,
+}
+
+/// 扩展元数据（EXIF 和技术信息），无法读取的字段为空
+#[derive(Serialize, Default)]
+pub struct ExtendedMetadata {
+    /// 拍摄时间（EXIF DateTimeOriginal，ISO 8601 格式；记录了时区时带偏移）
+    pub captured_at: Option<String>,
+    /// 相机厂商
+    pub camera_make: Option<String>,
+    /// 相机型号
+    pub camera_model: Option<String>,
+    /// 镜头型号
+    pub lens_model: Option<String>,
+    /// EXIF 方向（1-8，1 为正常方向）
+    pub orientation: Option<u16>,
+    /// 色彩空间（srgb、adobe-rgb、uncalibrated、gray、cmyk）
+    pub color_space: Option<String>,
+    /// 是否嵌入了 ICC 配置
+    pub has_icc_profile: bool,
+    /// 每个通道的位深
+    pub bit_depth: Option<u8>,
+    /// 是否有透明通道（包括 PNG tRNS、GIF 透明色）
+    pub has_alpha: bool,
+    /// 帧数（静态图片为 1）
+    pub frame_count: u32,
+    /// 动图播放一遍的总时长（毫秒），静态图片为空
+    pub duration_ms: Option<u64>,
 }
 
 /// 获取图片元数据
@@ -34,12 +71,13 @@ pub struct ImageMetadata {
 ///
 /// # 参数
 /// - `file_path`: 图片文件的绝对路径
+/// - `extended`: 是否读取扩展元数据（EXIF、位深、动图帧数等），默认不读取
 ///
 /// # 返回
 /// - `Ok(ImageMetadata)`: 图片元数据
 /// - `Err(AppError)`: 文件读取或图片解析错误
 #[tauri::command]
-pub fn get_image_metadata(file_path: String) -> Result<ImageMetadata, AppError> {
+pub fn get_image_metadata(file_path: String, extended: Option<bool>) -> Result<ImageMetadata, AppError> {
     let path = Path::new(&file_path);
 
     // 1. 检查文件是否存在
@@ -52,12 +90,16 @@ pub fn get_image_metadata(file_path: String) -> Result<ImageMetadata, AppError>
         .map_err(|e| AppError::file_io(format!("读取文件元数据失败: {}", e)))?
         .len();
 
-    // 3. 从文件扩展名推断格式
-    let format = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.to_lowercase())
-        .unwrap_or_else(|| "unknown".to_string());
+    // 3. 按文件头识别格式，无法识别时回退到文件扩展名
+    let image_type = ImageType::detect(path);
+    let format = match image_type {
+        Some(image_type) => image_type.ext().to_string(),
+        None => path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_else(|| "unknown".to_string()),
+    };
 
     // 4. 使用 imagesize crate 只读取头部字节获取尺寸
     // 这是核心优化：避免完整解码图片
@@ -82,11 +124,354 @@ pub fn get_image_metadata(file_path: String) -> Result<ImageMetadata, AppError>
         1.0
     };
 
+    // 6. 扩展元数据
+    let extended = if extended.unwrap_or(false) {
+        Some(read_extended(path, image_type)?)
+    } else {
+        None
+    };
+
     Ok(ImageMetadata {
         width,
         height,
         aspect_ratio,
         file_size,
         format,
+        extended,
     })
 }
+
+type FileReader = BufReader<File>;
+
+/// 读取扩展元数据：文件结构和 EXIF 各自尽量读取，某一部分损坏不影响其他字段
+fn read_extended(path: &Path, image_type: Option<ImageType>) -> Result<ExtendedMetadata, AppError> {
+    let file = File::open(path).map_err(|e| AppError::file_io(format!("无法打开文件: {}", e)))?;
+    let mut reader = BufReader::new(file);
+    let mut meta = ExtendedMetadata {
+        frame_count: 1,
+        ..Default::default()
+    };
+
+    let _ = match image_type {
+        Some(ImageType::Jpeg) => read_jpeg(&mut reader, &mut meta),
+        Some(ImageType::Png) => read_png(&mut reader, &mut meta),
+        Some(ImageType::Webp) => read_webp(&mut reader, &mut meta),
+        Some(ImageType::Gif) => read_gif(&mut reader, &mut meta),
+        _ => Ok(()),
+    };
+
+    if matches!(
+        image_type,
+        Some(ImageType::Jpeg | ImageType::Png | ImageType::Webp | ImageType::Tiff | ImageType::Heic)
+    ) && reader.seek(SeekFrom::Start(0)).is_ok()
+    {
+        // 没有 EXIF 的图片很常见，读取失败不报错
+        if let Ok(exif) = exif::Reader::new().read_from_container(&mut reader) {
+            apply_exif(&exif, &mut meta);
+        }
+    }
+
+    Ok(meta)
+}
+
+/// EXIF 字段
+fn apply_exif(exif: &exif::Exif, meta: &mut ExtendedMetadata) {
+    let field = |tag: Tag| exif.get_field(tag, In::PRIMARY);
+    let ascii = |tag: Tag| match field(tag).map(|f| &f.value) {
+        Some(Value::Ascii(values)) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim_matches(char::from(0)).trim().to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    };
+    let ascii_bytes = |tag: Tag| match field(tag).map(|f| &f.value) {
+        Some(Value::Ascii(values)) => values.first().cloned(),
+        _ => None,
+    };
+
+    let captured = ascii_bytes(Tag::DateTimeOriginal).or_else(|| ascii_bytes(Tag::DateTime));
+    if let Some(mut date_time) = captured.and_then(|v| exif::DateTime::from_ascii(&v).ok()) {
+        if let Some(offset) = ascii_bytes(Tag::OffsetTimeOriginal).or_else(|| ascii_bytes(Tag::OffsetTime)) {
+            let _ = date_time.parse_offset(&offset);
+        }
+        meta.captured_at = Some(format_date_time(&date_time));
+    }
+
+    meta.camera_make = ascii(Tag::Make);
+    meta.camera_model = ascii(Tag::Model);
+    meta.lens_model = ascii(Tag::LensModel);
+    meta.orientation = field(Tag::Orientation)
+        .and_then(|f| f.value.get_uint(0))
+        .filter(|v| (1..=8).contains(v))
+        .map(|v| v as u16);
+
+    // 文件结构中识别出的灰度、CMYK 优先
+    if meta.color_space.is_none() {
+        meta.color_space = match field(Tag::ColorSpace).and_then(|f| f.value.get_uint(0)) {
+            Some(1) => Some("srgb".to_string()),
+            // Adobe RGB 的照片记为未校准，并在互操作性 IFD 中标记 R03
+            Some(0xFFFF) if ascii(Tag::InteroperabilityIndex).as_deref() == Some("R03") => {
+                Some("adobe-rgb".to_string())
+            }
+            Some(0xFFFF) => Some("uncalibrated".to_string()),
+            _ => None,
+        };
+    }
+}
+
+/// 格式化为 ISO 8601（本地时间，记录了时区时带偏移）
+fn format_date_time(date_time: &exif::DateTime) -> String {
+    let mut text = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date_time.year, date_time.month, date_time.day, date_time.hour, date_time.minute, date_time.second
+    );
+    if let Some(offset) = date_time.offset {
+        let sign = if offset < 0 { '-' } else { '+' };
+        let offset = offset.unsigned_abs();
+        text.push_str(&format!("{}{:02}:{:02}", sign, offset / 60, offset % 60));
+    }
+    text
+}
+
+fn read_u16_be(reader: &mut FileReader) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+/// JPEG：遍历 SOS 之前的标记段，SOF 中读取位深和颜色分量数，APP2 中查找 ICC 配置
+fn read_jpeg(reader: &mut FileReader, meta: &mut ExtendedMetadata) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(2))?;
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        if marker[0] != 0xFF {
+            return Ok(());
+        }
+        match marker[1] {
+            // 填充字节
+            0xFF => {
+                reader.seek_relative(-1)?;
+                continue;
+            }
+            // 没有长度字段的标记
+            0x01 | 0xD0..=0xD8 => continue,
+            0xD9 | 0xDA => return Ok(()),
+            _ => {}
+        }
+
+        let len = read_u16_be(reader)? as i64 - 2;
+        if len < 0 {
+            return Ok(());
+        }
+        let mut consumed = 0;
+        match marker[1] {
+            // SOF0-SOF15（C4 DHT、C8 保留、CC DAC 除外）
+            0xC0..=0xCF if !matches!(marker[1], 0xC4 | 0xC8 | 0xCC) => {
+                let mut sof = [0u8; 6];
+                reader.read_exact(&mut sof)?;
+                consumed = sof.len() as i64;
+                meta.bit_depth = Some(sof[0]);
+                match sof[5] {
+                    1 => meta.color_space = Some("gray".to_string()),
+                    4 => meta.color_space = Some("cmyk".to_string()),
+                    _ => {}
+                }
+            }
+            0xE2 => {
+                let mut tag = [0u8; 12];
+                if len >= tag.len() as i64 {
+                    reader.read_exact(&mut tag)?;
+                    consumed = tag.len() as i64;
+                    meta.has_icc_profile |= &tag == b"ICC_PROFILE\0";
+                }
+            }
+            _ => {}
+        }
+        reader.seek_relative(len - consumed)?;
+    }
+}
+
+/// PNG：遍历数据块，IHDR 中读取位深和颜色类型，acTL / fcTL 中读取 APNG 帧数和帧延迟
+fn read_png(reader: &mut FileReader, meta: &mut ExtendedMetadata) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(8))?;
+    let mut frames = None;
+    let mut duration_ms = 0u64;
+
+    loop {
+        let mut head = [0u8; 8];
+        reader.read_exact(&mut head)?;
+        let len = u32::from_be_bytes([head[0], head[1], head[2], head[3]]) as i64;
+        let mut consumed = 0;
+
+        match &head[4..8] {
+            b"IHDR" => {
+                let mut ihdr = [0u8; 13];
+                reader.read_exact(&mut ihdr)?;
+                consumed = ihdr.len() as i64;
+                meta.bit_depth = Some(ihdr[8]);
+                // 颜色类型：0 灰度，4 灰度 + 透明，6 RGBA
+                meta.has_alpha |= matches!(ihdr[9], 4 | 6);
+                if matches!(ihdr[9], 0 | 4) {
+                    meta.color_space = Some("gray".to_string());
+                }
+            }
+            b"tRNS" => meta.has_alpha = true,
+            b"iCCP" => meta.has_icc_profile = true,
+            b"sRGB" => {
+                meta.color_space.get_or_insert_with(|| "srgb".to_string());
+            }
+            b"acTL" => {
+                let mut actl = [0u8; 8];
+                reader.read_exact(&mut actl)?;
+                consumed = actl.len() as i64;
+                frames = Some(u32::from_be_bytes([actl[0], actl[1], actl[2], actl[3]]));
+            }
+            b"fcTL" => {
+                let mut fctl = [0u8; 26];
+                reader.read_exact(&mut fctl)?;
+                consumed = fctl.len() as i64;
+                let delay_num = u16::from_be_bytes([fctl[20], fctl[21]]) as u64;
+                // 分母为 0 时按 1/100 秒计算
+                let delay_den = match u16::from_be_bytes([fctl[22], fctl[23]]) {
+                    0 => 100,
+                    den => den as u64,
+                };
+                duration_ms += delay_num * 1000 / delay_den;
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+        // 剩余数据 + CRC
+        reader.seek_relative(len - consumed + 4)?;
+    }
+
+    if let Some(frames) = frames.filter(|&f| f > 1) {
+        meta.frame_count = frames;
+        meta.duration_ms = Some(duration_ms);
+    }
+    Ok(())
+}
+
+/// WebP：遍历 RIFF 数据块，VP8X 标志位中读取透明和 ICC，ANMF 中读取每帧时长
+fn read_webp(reader: &mut FileReader, meta: &mut ExtendedMetadata) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(12))?;
+    meta.bit_depth = Some(8);
+    let mut frames = 0u32;
+    let mut duration_ms = 0u64;
+
+    loop {
+        let mut head = [0u8; 8];
+        match reader.read_exact(&mut head) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            result => result?,
+        }
+        let len = u32::from_le_bytes([head[4], head[5], head[6], head[7]]) as i64;
+        let mut consumed = 0;
+
+        match &head[0..4] {
+            b"VP8X" => {
+                let mut flags = [0u8; 1];
+                reader.read_exact(&mut flags)?;
+                consumed = 1;
+                meta.has_icc_profile |= flags[0] & 0x20 != 0;
+                meta.has_alpha |= flags[0] & 0x10 != 0;
+            }
+            b"ICCP" => meta.has_icc_profile = true,
+            b"ALPH" => meta.has_alpha = true,
+            // 无损格式：签名字节之后的 32 位中依次为宽 - 1（14 位）、高 - 1（14 位）和透明标志
+            b"VP8L" => {
+                let mut header = [0u8; 5];
+                reader.read_exact(&mut header)?;
+                consumed = header.len() as i64;
+                let bits = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+                meta.has_alpha |= (bits >> 28) & 1 == 1;
+            }
+            // 动画帧：偏移（6 字节）、尺寸（6 字节）之后是 24 位的帧时长
+            b"ANMF" => {
+                let mut frame = [0u8; 16];
+                reader.read_exact(&mut frame)?;
+                consumed = frame.len() as i64;
+                frames += 1;
+                duration_ms += u32::from_le_bytes([frame[12], frame[13], frame[14], 0]) as u64;
+            }
+            _ => {}
+        }
+        // 数据块按偶数字节对齐
+        reader.seek_relative(len - consumed + (len & 1))?;
+    }
+
+    if frames > 1 {
+        meta.frame_count = frames;
+        meta.duration_ms = Some(duration_ms);
+    }
+    Ok(())
+}
+
+/// GIF：遍历数据块，统计图像描述符的数量，图形控制扩展中读取帧延迟和透明色
+fn read_gif(reader: &mut FileReader, meta: &mut ExtendedMetadata) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(6))?;
+    let mut screen = [0u8; 7];
+    reader.read_exact(&mut screen)?;
+    skip_gif_color_table(reader, screen[4])?;
+    meta.bit_depth = Some(8);
+
+    let mut frames = 0u32;
+    let mut duration_ms = 0u64;
+    loop {
+        let mut intro = [0u8; 1];
+        reader.read_exact(&mut intro)?;
+        match intro[0] {
+            // 扩展块
+            0x21 => {
+                let mut label = [0u8; 1];
+                reader.read_exact(&mut label)?;
+                if label[0] == 0xF9 {
+                    // 块大小、标志、延迟（1/100 秒）、透明色索引、结束符
+                    let mut control = [0u8; 6];
+                    reader.read_exact(&mut control)?;
+                    duration_ms += u16::from_le_bytes([control[2], control[3]]) as u64 * 10;
+                    meta.has_alpha |= control[1] & 0x01 != 0;
+                } else {
+                    skip_gif_sub_blocks(reader)?;
+                }
+            }
+            // 图像描述符
+            0x2C => {
+                let mut descriptor = [0u8; 9];
+                reader.read_exact(&mut descriptor)?;
+                skip_gif_color_table(reader, descriptor[8])?;
+                // LZW 最小码长
+                reader.seek_relative(1)?;
+                skip_gif_sub_blocks(reader)?;
+                frames += 1;
+            }
+            // 0x3B 结束符
+            _ => break,
+        }
+    }
+
+    if frames > 1 {
+        meta.frame_count = frames;
+        meta.duration_ms = Some(duration_ms);
+    }
+    Ok(())
+}
+
+fn skip_gif_color_table(reader: &mut FileReader, flags: u8) -> io::Result<()> {
+    if flags & 0x80 != 0 {
+        reader.seek_relative(3 * (1i64 << ((flags & 0x07) + 1)))?;
+    }
+    Ok(())
+}
+
+fn skip_gif_sub_blocks(reader: &mut FileReader) -> io::Result<()> {
+    loop {
+        let mut size = [0u8; 1];
+        reader.read_exact(&mut size)?;
+        if size[0] == 0 {
+            return Ok(());
+        }
+        reader.seek_relative(size[0] as i64)?;
+    }
+}
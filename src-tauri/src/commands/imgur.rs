@@ -1,12 +1,16 @@
 // src-tauri/src/commands/imgur.rs
 // Imgur 图床上传命令
 
-use tauri::{Window, Emitter};
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::multipart;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 /// Imgur 上传结果
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,6 +38,9 @@ struct ImgurData {
 const MAX_FILE_SIZE_IMAGE: u64 = 20 * 1024 * 1024;
 const MAX_FILE_SIZE_GIF: u64 = 200 * 1024 * 1024;
 
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif", "apng", "tiff", "bmp", "webp"];
+
 /// 上传文件到 Imgur
 #[tauri::command]
 pub async fn upload_to_imgur(
@@ -42,31 +49,38 @@ pub async fn upload_to_imgur(
     file_path: String,
     imgur_client_id: String,
     imgur_client_secret: Option<String>,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<ImgurUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("imgur", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("imgur", file_path, || upload_imgur_internal(&ctx, file_path, &imgur_client_id, imgur_client_secret.clone()))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// Imgur 上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_imgur_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    imgur_client_id: &str,
+    imgur_client_secret: Option<String>,
 ) -> Result<ImgurUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
     println!("[Imgur] 开始上传文件: {}", file_path);
 
-    // 发送进度: 0% - 读取文件
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "读取文件...",
-        "step_index": 1,
-        "total_steps": 3
-    }));
+    // 进度: 读取文件
+    progress.step(1, "读取文件...");
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 获取文件名并验证文件类型
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
     // 3. 验证文件类型
     let is_gif = ext == "gif";
@@ -80,24 +94,17 @@ pub async fn upload_to_imgur(
         )));
     }
 
-    if !["jpg", "jpeg", "png", "gif", "apng", "tiff", "bmp", "webp"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF、WebP、APNG、TIFF、BMP 格式的图片"));
     }
 
-    // 发送进度: 33% - 准备上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 33,
-        "total": 100,
-        "step": "准备上传...",
-        "step_index": 2,
-        "total_steps": 3
-    }));
+    // 进度: 准备上传
+    progress.step(2, "准备上传...");
 
     // 4. 构建 multipart form
-    let part = multipart::Part::bytes(buffer)
-        .file_name(file_name.to_string())
-        .mime_str("image/*")
+    let part = file.multipart_part(&progress)
+        .file_name(file.upload_file_name())
+        .mime_str(file.content_type())
         .into_validation_err_with("无法设置 MIME 类型")?;
 
     let mut form_builder = multipart::Form::new()
@@ -108,15 +115,8 @@ pub async fn upload_to_imgur(
         form_builder = form_builder.text("client_secret", secret);
     }
 
-    // 发送进度: 66% - 正在上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 66,
-        "total": 100,
-        "step": "正在上传...",
-        "step_index": 3,
-        "total_steps": 3
-    }));
+    // 进度: 正在上传
+    progress.step(3, "正在上传...");
 
     // 5. 发送请求到 Imgur API
     let client = reqwest::Client::new();
@@ -128,6 +128,7 @@ pub async fn upload_to_imgur(
         .send()
         .await
         .into_network_err_with("上传请求失败")?;
+    let response = reject_transient("Imgur", response).await?;
 
     // 6. 检查 HTTP 状态码
     let status = response.status();
@@ -137,8 +138,6 @@ pub async fn upload_to_imgur(
         return match status {
             reqwest::StatusCode::UNAUTHORIZED =>
                 Err(AppError::auth("Imgur Client ID 无效")),
-            reqwest::StatusCode::TOO_MANY_REQUESTS =>
-                Err(AppError::upload("Imgur", "API 调用频率超限 (1250次/天)")),
             reqwest::StatusCode::FORBIDDEN =>
                 Err(AppError::auth("Imgur API 访问被拒绝")),
             _ => Err(AppError::upload("Imgur", format!("上传失败 (HTTP {}): {}", status, response_text)))
@@ -169,3 +168,83 @@ pub async fn upload_to_imgur(
         delete_hash: Some(data.deletehash),
     })
 }
+
+/// Imgur 图床配置（对应前端 config.services.imgur）
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImgurConfig {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+/// Imgur 上传器
+pub struct ImgurUploader;
+
+#[async_trait::async_trait]
+impl Uploader for ImgurUploader {
+    fn id(&self) -> &'static str {
+        "imgur"
+    }
+
+    fn name(&self) -> &'static str {
+        "Imgur"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: Some(MAX_FILE_SIZE_IMAGE),
+            max_gif_size: Some(MAX_FILE_SIZE_GIF),
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: true,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: ImgurConfig = parse_config(self.name(), config)?;
+        if config.client_id.trim().is_empty() {
+            return Err(AppError::config("Imgur Client ID 未配置"));
+        }
+        let client_secret = config.client_secret.filter(|s| !s.trim().is_empty());
+
+        let res = upload_imgur_internal(ctx, file_path, &config.client_id, client_secret).await?;
+
+        let mut output = UploadOutput::new(self.id(), res.url.clone(), res.url);
+        if let Some(delete_hash) = res.delete_hash {
+            output = output.with_meta("deleteHash", delete_hash);
+        }
+        Ok(output)
+    }
+
+    async fn delete(
+        &self,
+        ctx: &UploadContext,
+        output: &UploadOutput,
+        config: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let config: ImgurConfig = parse_config(self.name(), config)?;
+        let delete_hash = output.meta_str("deleteHash")
+            .ok_or_else(|| AppError::validation("缺少 Imgur 删除凭证 (deletehash)"))?;
+
+        let response = ctx.http_client
+            .delete(format!("https://api.imgur.com/3/image/{}", delete_hash))
+            .header("Authorization", format!("Client-ID {}", config.client_id))
+            .timeout(std::time::Duration::from_secs(30))
+            .send()
+            .await
+            .into_network_err_with("删除请求失败")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::upload("Imgur", format!("删除失败 (HTTP {})", status)));
+        }
+
+        println!("[Imgur] 删除成功: {}", delete_hash);
+        Ok(())
+    }
+}
@@ -2,12 +2,16 @@
 // 京东图床上传命令
 // v2.10: 迁移到 AppError 统一错误类型
 
-use tauri::{Window, Emitter};
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::multipart;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 /// 京东上传结果
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,6 +37,9 @@ struct JDUploadResponse {
 /// 文件大小限制：15MB
 const MAX_FILE_SIZE: u64 = 15 * 1024 * 1024;
 
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif"];
+
 /// 获取京东 aid 和 pin
 async fn get_aid_info() -> Result<AidInfo, AppError> {
     let url = "https://api.m.jd.com/client.action?functionId=getAidInfo&body=%7B%22aidClientType%22%3A%22comet%22%2C%22aidClientVersion%22%3A%22comet%20-v1.0.0%22%2C%22appId%22%3A%22im.customer%22%2C%22os%22%3A%22comet%22%2C%22entry%22%3A%22jd_web_EnterpriseZC%22%2C%22reqSrc%22%3A%22s_comet%22%2C%22siteId%22%3A-1%2C%22customerAppId%22%3A%22im.customer%22%7D&appid=wh5&client=wh5&clientVersion=1.0.0&loginType=3&callback=jsonp1";
@@ -103,21 +110,32 @@ pub async fn upload_to_jd(
     window: Window,
     id: String,
     file_path: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<JDUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("jd", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("jd", file_path, || upload_jd_internal(&ctx, file_path))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// 京东上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_jd_internal(
+    ctx: &UploadContext,
+    file_path: &str,
 ) -> Result<JDUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 4);
     println!("[JD] 开始上传文件: {}", file_path);
 
-    // 发送进度: 0% - 读取文件
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "读取文件...",
-        "step_index": 1,
-        "total_steps": 4
-    }));
+    // 进度: 读取文件
+    progress.step(1, "读取文件...");
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 验证文件大小（限制 15MB）
     if file_size > MAX_FILE_SIZE {
@@ -128,28 +146,20 @@ pub async fn upload_to_jd(
     }
 
     // 3. 验证文件类型（只允许图片）
-    let file_name = std::path::Path::new(&file_path)
+    let file_name = std::path::Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| AppError::validation("无法获取文件名"))?;
 
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
-    if !["jpg", "jpeg", "png", "gif"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF 格式的图片"));
     }
 
-    // 发送进度: 25% - 获取凭证
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 25,
-        "total": 100,
-        "step": "获取上传凭证...",
-        "step_index": 2,
-        "total_steps": 4
-    }));
+    // 进度: 获取凭证
+    progress.step(2, "获取上传凭证...");
 
     // 4. 获取 aid 和 pin
     println!("[JD] 正在获取 aid 和 pin...");
@@ -157,16 +167,16 @@ pub async fn upload_to_jd(
     println!("[JD] 获取成功 - aid: {}, pin: {}", aid_info.aid, aid_info.pin);
 
     // 5. 构建 multipart form
-    // 将扩展名转为小写（避免服务器不支持大写扩展名）
+    // 扩展名统一为小写并与实际格式一致（避免服务器不支持大写扩展名或按扩展名校验格式）
     let normalized_file_name = if let Some(dot_pos) = file_name.rfind('.') {
         format!("{}.{}", &file_name[..dot_pos], ext)
     } else {
         file_name.to_string()
     };
 
-    let part = multipart::Part::bytes(buffer)
+    let part = file.multipart_part(&progress)
         .file_name(normalized_file_name)
-        .mime_str("image/*")
+        .mime_str(file.content_type())
         .into_validation_err_with("无法设置 MIME 类型")?;
 
     let form = multipart::Form::new()
@@ -176,15 +186,8 @@ pub async fn upload_to_jd(
         .text("clientType", "comet")
         .text("pin", aid_info.pin);
 
-    // 发送进度: 50% - 正在上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 50,
-        "total": 100,
-        "step": "正在上传...",
-        "step_index": 3,
-        "total_steps": 4
-    }));
+    // 进度: 正在上传
+    progress.step(3, "正在上传...");
 
     // 6. 发送请求到京东上传 API
     let client = reqwest::Client::new();
@@ -199,16 +202,10 @@ pub async fn upload_to_jd(
         .send()
         .await
         .into_network_err_with("上传请求失败")?;
+    let response = reject_transient("京东", response).await?;
 
-    // 发送进度: 75% - 处理响应
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 75,
-        "total": 100,
-        "step": "处理响应...",
-        "step_index": 4,
-        "total_steps": 4
-    }));
+    // 进度: 处理响应
+    progress.step(4, "处理响应...");
 
     // 7. 解析响应
     let response_text = response.text().await
@@ -244,3 +241,36 @@ pub async fn upload_to_jd(
         size: file_size,
     })
 }
+
+/// 京东上传器（无需配置）
+pub struct JdUploader;
+
+#[async_trait::async_trait]
+impl Uploader for JdUploader {
+    fn id(&self) -> &'static str {
+        "jd"
+    }
+
+    fn name(&self) -> &'static str {
+        "京东"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: Some(MAX_FILE_SIZE),
+            max_gif_size: None,
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        _config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let res = upload_jd_internal(ctx, file_path).await?;
+        Ok(UploadOutput::new(self.id(), res.url.clone(), res.url).with_size(res.size))
+    }
+}
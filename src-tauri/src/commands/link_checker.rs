@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
 use crate::error::AppError;
+use crate::image_format::ImageType;
 
 /// 最大允许下载的文件大小（50MB）
 const MAX_DOWNLOAD_SIZE: usize = 50 * 1024 * 1024;
@@ -242,6 +243,11 @@ pub async fn download_image_from_url(
     url: String,
     http_client: tauri::State<'_, crate::HttpClient>,
 ) -> Result<String, AppError> {
+    download_to_temp(&http_client.0, &url).await
+}
+
+/// 下载图片到临时目录，返回临时文件路径（本地上传服务接收图片链接时也使用）
+pub(crate) async fn download_to_temp(http_client: &reqwest::Client, url: &str) -> Result<String, AppError> {
     eprintln!("[下载图片] 开始下载: {}", url);
 
     // 首先清理过期的临时文件，防止磁盘空间耗尽
@@ -249,8 +255,7 @@ pub async fn download_image_from_url(
 
     // 发送 GET 请求下载图片
     let response = http_client
-        .0
-        .get(&url)
+        .get(url)
         .timeout(std::time::Duration::from_secs(30)) // 30秒超时
         .send()
         .await
@@ -303,14 +308,27 @@ pub async fn download_image_from_url(
         )));
     }
 
-    eprintln!("[下载图片] ✓ 下载成功，大小: {} bytes", bytes.len());
+    // 按内容识别格式，链接返回的不是图片（如登录页、错误页）时不保存
+    let image_type = ImageType::sniff(&bytes).ok_or_else(|| {
+        eprintln!("[下载图片] ✗ 内容不是可识别的图片格式");
+        AppError::validation("下载的内容不是图片")
+    })?;
+
+    eprintln!(
+        "[下载图片] ✓ 下载成功，格式: {}，大小: {} bytes",
+        image_type.ext(),
+        bytes.len()
+    );
 
-    // 创建临时文件
+    // 创建临时文件（扩展名与实际格式一致）
     let temp_dir = std::env::temp_dir();
+    // 同一秒内可能有多个下载（如本地上传服务一次收到多个链接），加随机后缀避免互相覆盖
     let file_name = format!(
-        "{}{}.jpg",
+        "{}{}_{:08x}.{}",
         TEMP_FILE_PREFIX,
-        chrono::Local::now().timestamp()
+        chrono::Local::now().timestamp(),
+        rand::random::<u32>(),
+        image_type.ext()
     );
     let temp_path = temp_dir.join(file_name);
 
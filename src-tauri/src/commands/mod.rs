@@ -14,8 +14,12 @@ pub mod smms;
 pub mod github;
 pub mod imgur;
 pub mod s3_compatible;
-pub mod utils;
 pub mod link_checker;
 pub mod clipboard;
 pub mod image_meta;
+pub mod thumbnail;
+pub mod duplicates;
+pub mod uploader;
 
+pub mod queue;
+pub mod s3_multipart;
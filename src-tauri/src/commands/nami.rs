@@ -3,17 +3,24 @@
 // 使用火山引擎 TOS 对象存储，需要 TOS4-HMAC-SHA256 签名
 // v2.10: 迁移到 AppError 统一错误类型
 
-use tauri::{Window, Emitter, Manager};
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
 use sha1::{Sha1, Digest as Sha1Digest};
 use sha2::Sha256;
 use hmac::{Hmac, Mac};
 use chrono::Utc;
+use futures::stream::{self, TryStreamExt};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 
 use crate::error::{AppError, IntoAppError};
 use super::nami_token::fetch_nami_token_internal;
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::{exhausted, status_error};
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -22,14 +29,26 @@ const TOS_REGION: &str = "tos-cn-shanghai";
 const TOS_SERVICE: &str = "tos";
 const CDN_BASE: &str = "https://bfns.zhaomi.cn";
 
-#[derive(Debug, Serialize)]
+/// 默认分片大小（MB）
+const DEFAULT_PART_SIZE_MB: u64 = 8;
+/// 分片大小范围（MB）：TOS 要求除最后一片外不小于 5MB
+const MIN_PART_SIZE_MB: u64 = 5;
+const MAX_PART_SIZE_MB: u64 = 512;
+/// TOS 单次上传最多 10000 个分片
+const MAX_PARTS: u64 = 10_000;
+/// 同时上传的分片数
+const PART_CONCURRENCY: usize = 3;
+/// 单个分片的超时时间
+const PART_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NamiUploadResult {
     pub url: String,
     pub size: u64,
     pub instant: bool,  // 是否秒传
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct STSCredentials {
     access_key: String,
     secret_access_key: String,
@@ -46,23 +65,8 @@ struct STSResponse {
 }
 
 /// 计算文件 SHA1 哈希（取前40位hex）
-fn calculate_file_hash(buffer: &[u8]) -> String {
-    let mut hasher = Sha1::new();
-    hasher.update(buffer);
-    let result = hasher.finalize();
-    hex::encode(result)
-}
-
-/// 获取文件 Content-Type
-fn get_content_type(ext: &str) -> &'static str {
-    match ext.to_lowercase().as_str() {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        "bmp" => "image/bmp",
-        _ => "application/octet-stream",
-    }
+async fn calculate_file_hash(file: &FileBody) -> Result<String, AppError> {
+    Ok(hex::encode(file.digest::<Sha1>().await?))
 }
 
 /// TOS4-HMAC-SHA256 签名器
@@ -252,15 +256,13 @@ async fn get_sts_credentials(
         .await
         .into_network_err_with("STS 请求失败")?;
 
-    let status = response.status();
+    if !response.status().is_success() {
+        return Err(status_error("纳米", "STS 请求失败", response).await);
+    }
     let text = response.text().await.into_network_err_with("读取 STS 响应失败")?;
 
     println!("[Nami] STS 响应: {}", text);
 
-    if !status.is_success() {
-        return Err(AppError::upload("纳米", format!("STS 请求失败 (HTTP {}): {}", status, text)));
-    }
-
     let sts_response: STSResponse = serde_json::from_str(&text)
         .map_err(|e| AppError::upload("纳米", format!("解析 STS 响应失败: {}", e)))?;
 
@@ -302,12 +304,10 @@ async fn init_multipart_upload(
     }
 
     let response = request.send().await.into_network_err_with("初始化上传请求失败")?;
-    let status = response.status();
-    let text = response.text().await.into_network_err_with("读取初始化响应失败")?;
-
-    if !status.is_success() {
-        return Err(AppError::upload("纳米", format!("初始化上传失败 (HTTP {}): {}", status, text)));
+    if !response.status().is_success() {
+        return Err(status_error("纳米", "初始化上传失败", response).await);
     }
+    let text = response.text().await.into_network_err_with("读取初始化响应失败")?;
 
     // 解析响应获取 UploadId
     // 先尝试 JSON 格式，再尝试 XML 格式（兼容）
@@ -341,7 +341,8 @@ async fn upload_part(
     file_key: &str,
     upload_id: &str,
     part_number: u32,
-    data: &[u8],
+    body: reqwest::Body,
+    content_length: u64,
 ) -> Result<String, AppError> {
     let signer = TosSigner::new(
         credentials.access_key.clone(),
@@ -366,19 +367,17 @@ async fn upload_part(
     let url = format!("https://{}{}?partNumber={}&uploadId={}", TOS_HOST, format!("/{}", encoded_path), part_number, upload_id);
 
     let mut request = client.put(&url)
-        .header("content-length", data.len().to_string())
-        .body(data.to_vec());
+        .header("content-length", content_length.to_string())
+        .timeout(std::time::Duration::from_secs(PART_TIMEOUT_SECS))
+        .body(body);
 
     for (key, value) in signed_headers {
         request = request.header(&key, &value);
     }
 
     let response = request.send().await.into_network_err_with("上传分片失败")?;
-    let status = response.status();
-
-    if !status.is_success() {
-        let text = response.text().await.unwrap_or_default();
-        return Err(AppError::upload("纳米", format!("上传分片失败 (HTTP {}): {}", status, text)));
+    if !response.status().is_success() {
+        return Err(status_error("纳米", &format!("上传分片 {} 失败", part_number), response).await);
     }
 
     // 获取 ETag
@@ -392,6 +391,15 @@ async fn upload_part(
     Ok(etag)
 }
 
+/// 分片大小（字节）：使用配置值（限制在允许范围内），并保证分片数不超过 MAX_PARTS
+fn part_size_for(file_size: u64, part_size_mb: Option<u64>) -> u64 {
+    const MB: u64 = 1024 * 1024;
+    let configured = part_size_mb
+        .unwrap_or(DEFAULT_PART_SIZE_MB)
+        .clamp(MIN_PART_SIZE_MB, MAX_PART_SIZE_MB) * MB;
+    configured.max(file_size.div_ceil(MAX_PARTS).div_ceil(MB) * MB)
+}
+
 /// 完成分片上传
 async fn complete_multipart_upload(
     client: &Client,
@@ -435,16 +443,100 @@ async fn complete_multipart_upload(
     }
 
     let response = request.send().await.into_network_err_with("完成上传请求失败")?;
+    if !response.status().is_success() {
+        return Err(status_error("纳米", "完成上传失败", response).await);
+    }
+
+    Ok(())
+}
+
+/// 取消分片上传（清理服务端已上传的分片）
+async fn abort_multipart_upload(
+    client: &Client,
+    credentials: &STSCredentials,
+    file_key: &str,
+    upload_id: &str,
+) -> Result<(), AppError> {
+    let signer = TosSigner::new(
+        credentials.access_key.clone(),
+        credentials.secret_access_key.clone(),
+        credentials.session_token.clone(),
+    );
+
+    let uri = format!("/{}", file_key);
+    let query_params = [("uploadId", upload_id)];
+
+    let signed_headers = signer.sign("DELETE", &uri, &query_params)?;
+
+    let encoded_path: String = file_key.split('/').map(|p| urlencoding::encode(p).to_string()).collect::<Vec<_>>().join("/");
+    let url = format!("https://{}{}?uploadId={}", TOS_HOST, format!("/{}", encoded_path), upload_id);
+
+    let mut request = client.delete(&url)
+        .timeout(std::time::Duration::from_secs(15));
+
+    for (key, value) in signed_headers {
+        request = request.header(&key, &value);
+    }
+
+    let response = request.send().await.into_network_err_with("取消分片上传请求失败")?;
     let status = response.status();
 
     if !status.is_success() {
         let text = response.text().await.unwrap_or_default();
-        return Err(AppError::upload("纳米", format!("完成上传失败 (HTTP {}): {}", status, text)));
+        return Err(AppError::upload("纳米", format!("取消分片上传失败 (HTTP {}): {}", status, text)));
     }
 
     Ok(())
 }
 
+/// 分片上传清理守卫
+///
+/// 上传被取消（future 被丢弃）或中途出错时，在后台调用 AbortMultipartUpload，
+/// 避免服务端残留未完成的分片。上传完成后调用 `disarm` 解除。
+struct MultipartAbortGuard {
+    client: Client,
+    credentials: STSCredentials,
+    file_key: String,
+    upload_id: String,
+    armed: bool,
+}
+
+impl MultipartAbortGuard {
+    fn new(client: &Client, credentials: &STSCredentials, file_key: &str, upload_id: &str) -> Self {
+        Self {
+            client: client.clone(),
+            credentials: credentials.clone(),
+            file_key: file_key.to_string(),
+            upload_id: upload_id.to_string(),
+            armed: true,
+        }
+    }
+
+    fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for MultipartAbortGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        let client = self.client.clone();
+        let credentials = self.credentials.clone();
+        let file_key = std::mem::take(&mut self.file_key);
+        let upload_id = std::mem::take(&mut self.upload_id);
+
+        println!("[Nami] 上传未完成，清理分片: {}", upload_id);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = abort_multipart_upload(&client, &credentials, &file_key, &upload_id).await {
+                eprintln!("[Nami] 清理分片失败: {}", e);
+            }
+        });
+    }
+}
+
 /// 上传到纳米图床
 #[tauri::command]
 pub async fn upload_to_nami(
@@ -453,24 +545,40 @@ pub async fn upload_to_nami(
     file_path: String,
     cookie: String,
     auth_token: String,
+    part_size_mb: Option<u64>,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<NamiUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("nami", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("nami", file_path, || upload_nami_internal(&ctx, file_path, &cookie, &auth_token, part_size_mb))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// 纳米上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_nami_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    cookie: &str,
+    auth_token: &str,
+    part_size_mb: Option<u64>,
 ) -> Result<NamiUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 5);
     println!("[Nami] 开始上传文件: {}", file_path);
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 获取文件扩展名
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
     // 3. 计算文件哈希
-    let hash = calculate_file_hash(&buffer);
+    let hash = calculate_file_hash(&file).await?;
     let file_key = format!("web/{}.{}", hash, ext);
     println!("[Nami] 文件哈希: {}, key: {}", hash, file_key);
 
@@ -495,77 +603,75 @@ pub async fn upload_to_nami(
         });
     }
 
-    // 发送步骤1进度：获取动态Headers (0%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "获取动态Headers中...",
-        "step_index": 1,
-        "total_steps": 5
-    }));
+    // 进度步骤1：获取动态Headers
+    progress.step(1, "获取动态Headers中...");
 
     // 6. 获取动态 Headers
     println!("[Nami] 获取动态 Headers...");
-    let dynamic_headers = fetch_nami_token_internal(&window.app_handle(), cookie.clone(), auth_token.clone()).await?;
-
-    // 发送步骤2进度：获取STS凭证 (20%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 20,
-        "total": 100,
-        "step": "获取STS凭证中...",
-        "step_index": 2,
-        "total_steps": 5
-    }));
+    let dynamic_headers = fetch_nami_token_internal(ctx.host.as_ref(), cookie.to_string(), auth_token.to_string()).await?;
+
+    // 进度步骤2：获取STS凭证
+    progress.step(2, "获取STS凭证中...");
 
     // 7. 获取 STS 凭证
     println!("[Nami] 获取 STS 凭证...");
-    let credentials = get_sts_credentials(&client, &file_key, &cookie, &auth_token, &dynamic_headers).await?;
+    let credentials = get_sts_credentials(&client, &file_key, cookie, auth_token, &dynamic_headers).await?;
     println!("[Nami] STS 凭证获取成功");
 
-    // 发送步骤3进度：初始化分片上传 (40%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 40,
-        "total": 100,
-        "step": "初始化分片上传中...",
-        "step_index": 3,
-        "total_steps": 5
-    }));
+    // 进度步骤3：初始化分片上传
+    progress.step(3, "初始化分片上传中...");
 
     // 8. 初始化分片上传
-    let content_type = get_content_type(&ext);
+    let content_type = file.content_type();
     println!("[Nami] 初始化分片上传...");
     let upload_id = init_multipart_upload(&client, &credentials, &file_key, content_type).await?;
+    let mut abort_guard = MultipartAbortGuard::new(&client, &credentials, &file_key, &upload_id);
+
+    // 进度步骤4：上传分片
+    progress.step(4, "上传分片中...");
+
+    // 9. 并行上传分片，进度按已完成的分片计算
+    let part_size = part_size_for(file_size, part_size_mb);
+    let part_count = file_size.div_ceil(part_size).max(1) as u32;
+    println!("[Nami] 共 {} 个分片（每片 {}MB）", part_count, part_size / 1024 / 1024);
+
+    let etags = Mutex::new(BTreeMap::new());
+    stream::iter((1..=part_count).map(Ok::<u32, AppError>))
+        .try_for_each_concurrent(PART_CONCURRENCY, |part_number| {
+            let offset = (part_number as u64 - 1) * part_size;
+            let len = part_size.min(file_size - offset);
+            let (client, credentials, file_key, upload_id) = (&client, &credentials, &file_key, &upload_id);
+            let (file, etags, progress) = (&file, &etags, &progress);
+            async move {
+                // 瞬时错误按上传上下文的重试策略重试，每次尝试都重新打开文件生成请求体。
+                // 重试用尽后返回不可重试的错误，外层的 `run_limited` 不会因为单个分片失败从头上传
+                let etag = ctx
+                    .retry
+                    .run(&format!("纳米分片 {}", part_number), || {
+                        upload_part(client, credentials, file_key, upload_id, part_number, file.untracked_range_body(offset, len), len)
+                    })
+                    .await
+                    .map_err(|e| exhausted("纳米", e))?;
+                let done = {
+                    let mut etags = etags.lock().unwrap_or_else(|p| p.into_inner());
+                    etags.insert(part_number, etag);
+                    etags.len()
+                };
+                progress.advance(len);
+                progress.step(4, &format!("上传分片中 ({}/{})...", done, part_count));
+                Ok(())
+            }
+        })
+        .await?;
+
+    // 进度步骤5：完成上传
+    progress.step(5, "完成上传中...");
 
-    // 发送步骤4进度：上传分片 (60%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 60,
-        "total": 100,
-        "step": "上传分片中...",
-        "step_index": 4,
-        "total_steps": 5
-    }));
-
-    // 9. 上传分片（单分片）
-    println!("[Nami] 上传分片...");
-    let etag = upload_part(&client, &credentials, &file_key, &upload_id, 1, &buffer).await?;
-
-    // 发送步骤5进度：完成上传 (80%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 80,
-        "total": 100,
-        "step": "完成上传中...",
-        "step_index": 5,
-        "total_steps": 5
-    }));
-
-    // 10. 完成上传
+    // 10. 完成上传（分片需按序号排列）
     println!("[Nami] 完成上传...");
-    complete_multipart_upload(&client, &credentials, &file_key, &upload_id, &[(1, etag)]).await?;
+    let parts: Vec<(u32, String)> = etags.into_inner().unwrap_or_else(|p| p.into_inner()).into_iter().collect();
+    complete_multipart_upload(&client, &credentials, &file_key, &upload_id, &parts).await?;
+    abort_guard.disarm();
 
     // 11. 返回结果
     let url = format!("{}/{}", CDN_BASE, file_key);
@@ -628,3 +734,56 @@ pub async fn test_nami_connection(app: tauri::AppHandle, cookie: String, auth_to
         }
     }
 }
+
+/// 纳米图床配置（对应前端 config.services.nami）
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NamiConfig {
+    cookie: String,
+    auth_token: String,
+    /// 分片大小（MB），未设置时使用默认值
+    #[serde(default)]
+    part_size_mb: Option<u64>,
+}
+
+/// 纳米上传器
+pub struct NamiUploader;
+
+#[async_trait::async_trait]
+impl Uploader for NamiUploader {
+    fn id(&self) -> &'static str {
+        "nami"
+    }
+
+    fn name(&self) -> &'static str {
+        "纳米"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: None,
+            max_gif_size: None,
+            accepted_formats: &[],
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: NamiConfig = parse_config(self.name(), config)?;
+        if config.cookie.trim().is_empty() || config.auth_token.trim().is_empty() {
+            return Err(AppError::config("纳米 Cookie 或 Auth-Token 未配置"));
+        }
+
+        let res = upload_nami_internal(ctx, file_path, &config.cookie, &config.auth_token, config.part_size_mb).await?;
+        let file_key = res.url.trim_start_matches(CDN_BASE).trim_start_matches('/').to_string();
+
+        Ok(UploadOutput::new(self.id(), file_key, res.url)
+            .with_size(res.size)
+            .with_meta("instant", res.instant))
+    }
+}
@@ -4,10 +4,9 @@
 // v2.10: 迁移到 AppError 统一错误类型
 
 use serde::{Deserialize, Serialize};
-use tauri_plugin_shell::ShellExt;
-use tauri_plugin_shell::process::CommandEvent;
 
-use crate::error::{AppError, IntoAppError};
+use crate::error::AppError;
+use crate::uploader::host::UploadHost;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NamiDynamicHeaders {
@@ -46,39 +45,17 @@ pub async fn fetch_nami_token(
 
 /// 从纳米页面获取动态 Headers (内部函数)
 pub async fn fetch_nami_token_internal(
-    app: &tauri::AppHandle,
+    host: &dyn UploadHost,
     cookie: String,
     auth_token: String
 ) -> Result<NamiDynamicHeaders, AppError> {
     println!("[NamiToken] ========== 开始获取动态 Headers (Sidecar) ==========");
 
-    let sidecar = app.shell()
-        .sidecar("nami-token-fetcher")
-        .into_external_err_with("创建 sidecar 失败")?;
-
-    let (mut rx, _child) = sidecar
-        .args(["fetch-token", "--cookie", &cookie, "--auth-token", &auth_token])
-        .spawn()
-        .into_external_err_with("启动 sidecar 失败")?;
-
-    let mut output = String::new();
-    let mut stderr_output = String::new();
-
-    while let Some(event) = rx.recv().await {
-        match event {
-            CommandEvent::Stdout(line) => {
-                output.push_str(&String::from_utf8_lossy(&line));
-            }
-            CommandEvent::Stderr(line) => {
-                stderr_output.push_str(&String::from_utf8_lossy(&line));
-                stderr_output.push('\n');
-            }
-            CommandEvent::Terminated(status) => {
-                println!("[NamiToken] Sidecar 退出，状态: {:?}", status);
-            }
-            _ => {}
-        }
-    }
+    let sidecar_output = host
+        .run_sidecar("nami-token-fetcher", &["fetch-token", "--cookie", &cookie, "--auth-token", &auth_token])
+        .await?;
+    let output = sidecar_output.stdout;
+    let stderr_output = sidecar_output.stderr;
 
     // 输出 stderr 日志（包含进度信息）
     if !stderr_output.is_empty() {
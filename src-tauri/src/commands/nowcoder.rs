@@ -2,13 +2,20 @@
 // 牛客图床上传命令
 // v2.10: 迁移到 AppError 统一错误类型
 
-use tauri::Window;
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::multipart;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
+
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif"];
 
 /// 测试牛客 Cookie 是否有效
 #[tauri::command]
@@ -53,7 +60,7 @@ pub async fn test_nowcoder_cookie(nowcoder_cookie: String) -> Result<String, App
     let client = reqwest::Client::new();
     let response = client
         .post(&url)
-        .header("Cookie", &nowcoder_cookie)
+        .header("Cookie", nowcoder_cookie)
         .header("Referer", "https://www.nowcoder.com/creation/write/article")
         .header("Origin", "https://www.nowcoder.com")
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/142.0.0.0 Safari/537.36")
@@ -93,27 +100,46 @@ struct NowcoderApiResponse {
 
 #[tauri::command]
 pub async fn upload_to_nowcoder(
-    _window: Window,
-    _id: String,
+    window: Window,
+    id: String,
     file_path: String,
     nowcoder_cookie: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<NowcoderUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("nowcoder", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("nowcoder", file_path, || upload_nowcoder_internal(&ctx, file_path, &nowcoder_cookie))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// 牛客上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_nowcoder_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    nowcoder_cookie: &str,
 ) -> Result<NowcoderUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
     println!("[Nowcoder] 开始上传文件: {}", file_path);
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    progress.step(1, "读取文件...");
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 验证文件类型（只允许图片）
-    let file_name = std::path::Path::new(&file_path)
+    let file_name = std::path::Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| AppError::validation("无法获取文件名"))?;
 
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
-    if !["jpg", "jpeg", "png", "gif"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF 格式的图片"));
     }
 
@@ -126,26 +152,27 @@ pub async fn upload_to_nowcoder(
     let url = format!("https://www.nowcoder.com/uploadImage?type=1&_={}", timestamp);
 
     // 4. 构建 multipart form
-    // 将扩展名转为小写（避免服务器不支持大写扩展名）
+    // 扩展名统一为小写并与实际格式一致（避免服务器不支持大写扩展名或按扩展名校验格式）
     let normalized_file_name = if let Some(dot_pos) = file_name.rfind('.') {
         format!("{}.{}", &file_name[..dot_pos], ext)
     } else {
         file_name.to_string()
     };
 
-    let part = multipart::Part::bytes(buffer)
+    let part = file.multipart_part(&progress)
         .file_name(normalized_file_name)
-        .mime_str("image/*")
+        .mime_str(file.content_type())
         .into_validation_err_with("无法设置 MIME 类型")?;
 
     let form = multipart::Form::new()
         .part("file", part);
 
     // 5. 发送请求到牛客 API（带必须的 Headers）
+    progress.step(2, "正在上传...");
     let client = reqwest::Client::new();
     let response = client
         .post(&url)
-        .header("Cookie", &nowcoder_cookie)
+        .header("Cookie", nowcoder_cookie)
         .header("Referer", "https://www.nowcoder.com/creation/write/article")
         .header("Origin", "https://www.nowcoder.com")
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/142.0.0.0 Safari/537.36")
@@ -153,8 +180,10 @@ pub async fn upload_to_nowcoder(
         .send()
         .await
         .into_network_err_with("请求失败")?;
+    let response = reject_transient("牛客", response).await?;
 
     // 6. 解析响应
+    progress.step(3, "处理响应...");
     let response_text = response.text().await
         .into_network_err_with("无法读取响应")?;
 
@@ -205,3 +234,47 @@ pub async fn upload_to_nowcoder(
         size: file_size,
     })
 }
+
+/// 牛客图床配置（对应前端 config.services.nowcoder）
+#[derive(Deserialize)]
+struct NowcoderConfig {
+    cookie: String,
+}
+
+/// 牛客上传器
+pub struct NowcoderUploader;
+
+#[async_trait::async_trait]
+impl Uploader for NowcoderUploader {
+    fn id(&self) -> &'static str {
+        "nowcoder"
+    }
+
+    fn name(&self) -> &'static str {
+        "牛客"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: None,
+            max_gif_size: None,
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: NowcoderConfig = parse_config(self.name(), config)?;
+        if config.cookie.trim().is_empty() {
+            return Err(AppError::config("牛客 Cookie 未配置"));
+        }
+
+        let res = upload_nowcoder_internal(ctx, file_path, &config.cookie).await?;
+        Ok(UploadOutput::new(self.id(), res.url.clone(), res.url).with_size(res.size))
+    }
+}
@@ -4,119 +4,243 @@
 // 自动获取 Token，无需手动配置
 // v2.10: 迁移到 AppError 统一错误类型
 
-use tauri::{Window, Emitter, Manager};
-use serde::Serialize;
+use tauri::{Window, Manager};
+use serde::{Deserialize, Serialize};
 use reqwest::Client;
 use std::time::{SystemTime, UNIX_EPOCH, Duration};
 
-use crate::error::{AppError, IntoAppError};
-use super::qiyu_token::fetch_qiyu_token_internal;
-use super::utils::read_file_bytes;
+use crate::error::{AppError, ErrorClass, IntoAppError};
+use super::qiyu_token::{fetch_qiyu_token_internal, QiyuToken};
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::resume::{self, ResumeStore};
+use crate::uploader::retry::{exhausted, status_error};
+use crate::uploader::{Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
-#[derive(Debug, Serialize)]
+/// NOS 分片上传地址（nim 为桶名）
+const NOS_UPLOAD_BASE: &str = "https://cdn-nimup-chunk.qiyukf.net/nim";
+
+/// 每个分片的大小（NOS 单个分片最大 4MB）
+const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Token 剩余有效期不足该时间时不再用于续传
+const TOKEN_EXPIRY_MARGIN_SECS: i64 = 300;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct QiyuUploadResult {
     pub url: String,
     pub size: u64,
 }
 
-// 注意：API 响应格式为 {"requestId": "...", "offset": ..., "context": "...", "callbackRetMsg": "..."}
-// 我们只需检查 HTTP 200 状态码即可判断上传成功，无需解析响应内容
+/// 分片上传响应: {"requestId": "...", "offset": ..., "context": "...", "callbackRetMsg": "..."}
+///
+/// `offset` 为服务端已确认接收的字节数，`context` 需要在后续分片请求中带上
+#[derive(Debug, Deserialize)]
+struct ChunkResponse {
+    offset: u64,
+    #[serde(default)]
+    context: Option<String>,
+}
+
+/// 查询上传进度响应: {"requestId": "...", "offset": ...}
+#[derive(Debug, Deserialize)]
+struct UploadContextResponse {
+    offset: u64,
+}
+
+/// 七鱼续传记录（Token 和 Object 路径绑定，续传时必须沿用同一个 Token）
+#[derive(Debug, Serialize, Deserialize)]
+struct QiyuResumeRecord {
+    token: QiyuToken,
+    context: String,
+    offset: u64,
+    created_at: i64,
+}
+
+impl QiyuResumeRecord {
+    /// Token 仍然有效且记录未过期时才可以续传（`expires` 为秒级时间戳，0 表示未知）
+    fn is_usable(&self) -> bool {
+        let token_valid = self.token.expires == 0
+            || self.token.expires > chrono::Utc::now().timestamp() + TOKEN_EXPIRY_MARGIN_SECS;
+        token_valid && !resume::is_expired(self.created_at)
+    }
+}
 
 #[tauri::command]
 pub async fn upload_to_qiyu(
     window: Window,
     id: String,
     file_path: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<QiyuUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("qiyu", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("qiyu", file_path, || upload_qiyu_internal(&ctx, file_path))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// 七鱼上传（内部函数，供命令和统一上传器共用）
+///
+/// 文件按 CHUNK_SIZE 分片依次上传，每个分片带上前一个分片返回的 `context`。
+/// 网络错误后先向服务端查询已确认的偏移，再从该偏移继续；
+/// 每个分片确认后保存续传记录，上传中断后再次上传同一个文件时沿用原 Token 从断点继续。
+pub(crate) async fn upload_qiyu_internal(
+    ctx: &UploadContext,
+    file_path: &str,
 ) -> Result<QiyuUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 2);
     println!("[Qiyu] 开始上传文件: {}", file_path);
 
-    // 发送步骤1进度：获取上传凭证 (0%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "获取上传凭证中...",
-        "step_index": 1,
-        "total_steps": 2
-    }));
-
-    // 1. 自动获取新的 Token（每次上传都获取新的，确保 Object 路径唯一）
-    println!("[Qiyu] 正在获取上传凭证...");
-    let token_info = fetch_qiyu_token_internal(&window.app_handle()).await?;
-    let qiyu_token = &token_info.token;
-    let object_path = &token_info.object_path;
-    println!("[Qiyu] Token 获取成功，Object 路径: {}", object_path);
-
-    // 3. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    // 1. 读取文件
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
-    // 4. 验证文件类型（只允许图片）
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 2. 验证文件类型（只允许图片，按文件内容识别格式，扩展名不可靠）
+    let ext = file.ext();
+    if !matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "gif" | "webp") {
+        return Err(AppError::validation("只支持 JPG、PNG、GIF、WebP 格式的图片"));
+    }
 
     // 获取 Content-Type
-    let content_type = match ext.as_str() {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        _ => return Err(AppError::validation("只支持 JPG、PNG、GIF、WebP 格式的图片")),
-    };
+    let content_type = file.content_type();
 
-    // 5. 构建上传 URL
-    let upload_url = format!(
-        "https://cdn-nimup-chunk.qiyukf.net/nim/{}?offset=0&complete=true&version=1.0",
-        urlencoding::encode(&object_path)
-    );
-    println!("[Qiyu] 上传 URL: {}", upload_url);
-
-    // 发送步骤2进度：上传文件 (50%)
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 50,
-        "total": 100,
-        "step": "上传文件中...",
-        "step_index": 2,
-        "total_steps": 2
-    }));
-
-    // 6. 发送上传请求（直接 POST 二进制数据）
     // 注意：使用标准 TLS 验证，确保通信安全
     let client = Client::builder()
         .timeout(Duration::from_secs(45))
         .build()
         .into_network_err_with("创建 HTTP 客户端失败")?;
 
-    let response = client
-        .post(&upload_url)
-        .header("Content-Type", content_type)
-        .header("x-nos-token", qiyu_token.as_str())
-        .body(buffer)
-        .send()
-        .await
-        .into_network_err_with("上传请求失败")?;
+    // 进度步骤1：获取上传凭证
+    progress.step(1, "获取上传凭证中...");
 
-    // 7. 检查响应状态
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        return Err(AppError::upload("七鱼", format!("上传失败 (HTTP {}): {}", status, body)));
-    }
+    // 3. 查找续传记录，向服务端确认已接收的偏移
+    let store = ResumeStore::new(ctx.host.as_ref())?;
+    let record_key = ResumeStore::key(&["qiyu", &file.fingerprint()]);
 
-    // 8. 记录响应（仅用于调试，不解析 JSON）
-    // API 响应格式: {"requestId": "...", "offset": ..., "context": "...", "callbackRetMsg": "..."}
-    // HTTP 200 即视为成功
-    let response_text = response.text().await
-        .into_network_err_with("无法读取响应")?;
-    println!("[Qiyu] API 响应: {}", response_text);
+    let mut record = match store.load::<QiyuResumeRecord>(&record_key) {
+        Some(record) if record.is_usable() => {
+            match query_offset(&client, &record.token, &record.context).await {
+                Ok(offset) if offset <= file_size => {
+                    println!("[Qiyu] 从偏移 {} 继续上传: {}", offset, record.token.object_path);
+                    Some(QiyuResumeRecord { offset, ..record })
+                }
+                // 网络问题时保留记录，下次再尝试
+                Err(e @ AppError::Network { .. }) => return Err(e),
+                _ => {
+                    println!("[Qiyu] 续传记录已失效，重新上传");
+                    store.remove(&record_key);
+                    None
+                }
+            }
+        }
+        Some(_) => {
+            println!("[Qiyu] 续传记录已过期，重新上传");
+            store.remove(&record_key);
+            None
+        }
+        None => None,
+    };
 
-    // 9. 构建 CDN URL (使用当前时间戳作为 createTime)
+    // 4. 没有可续传的记录时获取新的 Token（每次都获取新的，确保 Object 路径唯一）
+    let token_info = match &record {
+        Some(record) => record.token.clone(),
+        None => {
+            println!("[Qiyu] 正在获取上传凭证...");
+            let token = fetch_qiyu_token_internal(ctx.host.as_ref()).await?;
+            println!("[Qiyu] Token 获取成功，Object 路径: {}", token.object_path);
+            token
+        }
+    };
+    let object_path = &token_info.object_path;
+
+    // 进度步骤2：上传文件
+    progress.step(2, "上传文件中...");
+
+    // 5. 分片上传
+    let mut offset = record.as_ref().map_or(0, |r| r.offset);
+    let mut context = record.as_ref().map(|r| r.context.clone());
+    progress.advance(offset);
+
+    // 分片按上传上下文的重试策略重试；重试用尽后的错误不再交给外层 `run_limited` 整体重试
+    let retry = ctx.retry;
+    let mut attempt = 1;
+    loop {
+        let len = CHUNK_SIZE.min(file_size.saturating_sub(offset));
+        let complete = offset + len >= file_size;
+
+        match upload_chunk(&client, &token_info, content_type, &file, offset, len, context.as_deref()).await {
+            Ok(response) => {
+                // 服务端没有确认新的数据时不能继续，否则会在同一偏移上无限循环
+                if !complete && (response.offset <= offset || response.offset > file_size) {
+                    store.remove(&record_key);
+                    return Err(AppError::upload(
+                        "七鱼",
+                        format!("服务端确认的偏移异常: 发送 {}+{}，确认 {}", offset, len, response.offset),
+                    ));
+                }
+                attempt = 1;
+                progress.advance(response.offset.saturating_sub(offset));
+                offset = response.offset;
+                if response.context.is_some() {
+                    context = response.context;
+                }
+
+                if complete {
+                    break;
+                }
+
+                // 保存续传记录
+                if let Some(context) = &context {
+                    let saved = record.get_or_insert_with(|| QiyuResumeRecord {
+                        token: token_info.clone(),
+                        context: String::new(),
+                        offset: 0,
+                        created_at: chrono::Utc::now().timestamp_millis(),
+                    });
+                    saved.context = context.clone();
+                    saved.offset = offset;
+                    if let Err(e) = store.save(&record_key, &*saved) {
+                        eprintln!("[Qiyu] 保存续传记录失败: {}", e);
+                    }
+                }
+            }
+            Err(e) if e.class() == ErrorClass::Transient && attempt < retry.max_attempts => {
+                let delay = retry.delay_for(attempt, &e);
+                println!("[Qiyu] 偏移 {} 上传失败（第 {} 次），{}ms 后重试: {}", offset, attempt, delay.as_millis(), e);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+
+                // 以服务端确认的偏移为准（请求可能已被接收但响应丢失）
+                if let Some(context) = &context {
+                    if let Ok(confirmed) = query_offset(&client, &token_info, context).await {
+                        if confirmed != offset && confirmed <= file_size {
+                            println!("[Qiyu] 服务端已确认偏移: {}", confirmed);
+                            if confirmed > offset {
+                                progress.advance(confirmed - offset);
+                            } else {
+                                progress.rewind(offset - confirmed);
+                            }
+                            offset = confirmed;
+                        }
+                    }
+                }
+            }
+            // 瞬时错误重试次数用尽：保留续传记录，下次上传同一文件时继续
+            Err(e) if e.class() == ErrorClass::Transient => return Err(exhausted("七鱼", e)),
+            Err(e) => {
+                store.remove(&record_key);
+                return Err(e);
+            }
+        }
+    }
+    store.remove(&record_key);
+
+    // 6. 构建 CDN URL (使用当前时间戳作为 createTime)
     let create_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .into_external_err_with("无法获取时间戳")?
@@ -138,3 +262,110 @@ pub async fn upload_to_qiyu(
         size: file_size,
     })
 }
+
+/// 上传 `[offset, offset + len)` 区间的分片，到达文件末尾时自动标记 `complete=true`
+///
+/// 网络错误、服务端临时故障返回 `AppError::Network`（可以重试），其他失败返回上传错误
+async fn upload_chunk(
+    client: &Client,
+    token: &QiyuToken,
+    content_type: &str,
+    file: &FileBody,
+    offset: u64,
+    len: u64,
+    context: Option<&str>,
+) -> Result<ChunkResponse, AppError> {
+    let complete = offset + len >= file.size();
+    let mut upload_url = format!(
+        "{}/{}?offset={}&complete={}&version=1.0",
+        NOS_UPLOAD_BASE,
+        urlencoding::encode(&token.object_path),
+        offset,
+        complete
+    );
+    if let Some(context) = context {
+        upload_url.push_str(&format!("&context={}", urlencoding::encode(context)));
+    }
+
+    let response = client
+        .post(&upload_url)
+        .header("Content-Type", content_type)
+        .header("x-nos-token", token.token.as_str())
+        .header("Content-Length", len)
+        .body(file.untracked_range_body(offset, len))
+        .send()
+        .await
+        .into_network_err_with("上传请求失败")?;
+
+    if !response.status().is_success() {
+        return Err(status_error("七鱼", "上传分片失败", response).await);
+    }
+
+    let response_text = response.text().await
+        .into_network_err_with("无法读取响应")?;
+    println!("[Qiyu] API 响应: {}", response_text);
+
+    serde_json::from_str(&response_text)
+        .map_err(|e| AppError::upload("七鱼", format!("解析上传响应失败: {} - 原始响应: {}", e, response_text)))
+}
+
+/// 向服务端查询已接收的字节数
+async fn query_offset(client: &Client, token: &QiyuToken, context: &str) -> Result<u64, AppError> {
+    let url = format!(
+        "{}/{}?uploadContext&context={}&version=1.0",
+        NOS_UPLOAD_BASE,
+        urlencoding::encode(&token.object_path),
+        urlencoding::encode(context)
+    );
+
+    let response = client
+        .get(&url)
+        .header("x-nos-token", token.token.as_str())
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await
+        .into_network_err_with("查询上传进度失败")?;
+
+    if !response.status().is_success() {
+        return Err(status_error("七鱼", "查询上传进度失败", response).await);
+    }
+
+    let parsed: UploadContextResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::upload("七鱼", format!("解析上传进度失败: {}", e)))?;
+    Ok(parsed.offset)
+}
+
+/// 七鱼上传器（自动获取 Token，无需配置）
+pub struct QiyuUploader;
+
+#[async_trait::async_trait]
+impl Uploader for QiyuUploader {
+    fn id(&self) -> &'static str {
+        "qiyu"
+    }
+
+    fn name(&self) -> &'static str {
+        "七鱼"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: None,
+            max_gif_size: None,
+            accepted_formats: &["jpg", "jpeg", "png", "gif", "webp"],
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        _config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let res = upload_qiyu_internal(ctx, file_path).await?;
+        Ok(UploadOutput::new(self.id(), res.url.clone(), res.url).with_size(res.size))
+    }
+}
@@ -9,6 +9,7 @@ use tauri_plugin_shell::process::CommandEvent;
 use tokio::time::{timeout, Duration};
 
 use crate::error::{AppError, IntoAppError};
+use crate::uploader::host::UploadHost;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct QiyuToken {
@@ -128,44 +129,15 @@ pub async fn fetch_qiyu_token(app: tauri::AppHandle) -> Result<QiyuToken, AppErr
 }
 
 /// 内部函数：从七鱼页面获取新的上传 Token
-pub async fn fetch_qiyu_token_internal(app: &tauri::AppHandle) -> Result<QiyuToken, AppError> {
+pub async fn fetch_qiyu_token_internal(host: &dyn UploadHost) -> Result<QiyuToken, AppError> {
     println!("[QiyuToken] ========== 开始获取 Token (Sidecar) ==========");
 
-    let sidecar = app.shell()
-        .sidecar("qiyu-token-fetcher")
-        .into_external_err_with("创建 sidecar 失败")?;
-
-    let (mut rx, _child) = sidecar
-        .args(["fetch-token"])
-        .spawn()
-        .into_external_err_with("启动 sidecar 失败")?;
-
-    let mut output = String::new();
-    let mut stderr_output = String::new();
-
     // 添加 45 秒超时控制
-    let result = timeout(Duration::from_secs(45), async {
-        while let Some(event) = rx.recv().await {
-            match event {
-                CommandEvent::Stdout(line) => {
-                    output.push_str(&String::from_utf8_lossy(&line));
-                }
-                CommandEvent::Stderr(line) => {
-                    stderr_output.push_str(&String::from_utf8_lossy(&line));
-                    stderr_output.push('\n');
-                }
-                CommandEvent::Terminated(status) => {
-                    println!("[QiyuToken] Sidecar 退出，状态: {:?}", status);
-                }
-                _ => {}
-            }
-        }
-    }).await;
-
-    // 检查是否超时
-    if result.is_err() {
-        return Err(AppError::network("获取 Token 超时（45秒），请检查网络连接或稍后重试"));
-    }
+    let sidecar_output = timeout(Duration::from_secs(45), host.run_sidecar("qiyu-token-fetcher", &["fetch-token"]))
+        .await
+        .map_err(|_| AppError::network("获取 Token 超时（45秒），请检查网络连接或稍后重试"))??;
+    let output = sidecar_output.stdout;
+    let stderr_output = sidecar_output.stderr;
 
     // 输出 stderr 日志（包含进度信息）
     if !stderr_output.is_empty() {
@@ -0,0 +1,60 @@
+// src-tauri/src/commands/queue.rs
+// 持久化上传队列命令
+
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::queue::{UploadJob, UploadQueue};
+
+/// 添加上传任务到持久化队列
+///
+/// - `services`: 按优先级排列的图床列表
+/// - `max_attempts`: 最大尝试次数，默认 `DEFAULT_MAX_ATTEMPTS`
+///
+/// 任务状态变化时发送 `queue://updated`，完成时发送 `queue://job-completed`
+#[tauri::command]
+pub async fn enqueue_upload(
+    app: AppHandle,
+    file_path: String,
+    services: Vec<String>,
+    max_attempts: Option<u32>,
+    queue: State<'_, UploadQueue>,
+) -> Result<UploadJob, AppError> {
+    queue.enqueue(&app, file_path, services, max_attempts).await
+}
+
+/// 列出队列中的所有任务（按创建时间倒序）
+#[tauri::command]
+pub async fn list_upload_jobs(queue: State<'_, UploadQueue>) -> Result<Vec<UploadJob>, AppError> {
+    queue.list().await
+}
+
+/// 暂停任务
+#[tauri::command]
+pub async fn pause_upload_job(
+    app: AppHandle,
+    id: String,
+    queue: State<'_, UploadQueue>,
+) -> Result<UploadJob, AppError> {
+    queue.pause(&app, &id).await
+}
+
+/// 恢复已暂停或已失败的任务
+#[tauri::command]
+pub async fn resume_upload_job(
+    app: AppHandle,
+    id: String,
+    queue: State<'_, UploadQueue>,
+) -> Result<UploadJob, AppError> {
+    queue.resume(&app, &id).await
+}
+
+/// 删除任务
+#[tauri::command]
+pub async fn remove_upload_job(
+    app: AppHandle,
+    id: String,
+    queue: State<'_, UploadQueue>,
+) -> Result<(), AppError> {
+    queue.remove(&app, &id).await
+}
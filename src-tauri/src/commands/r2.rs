@@ -2,16 +2,16 @@
 // Cloudflare R2 上传命令
 // v2.10: 迁移到 AppError 统一错误类型
 
-use tauri::{Window, Emitter};
+use tauri::{Window, Manager};
 use serde::{Serialize, Deserialize};
-use std::path::Path;
-use aws_sdk_s3::{Client, Config, primitives::ByteStream};
+use aws_sdk_s3::{Client, Config};
 use aws_sdk_s3::config::{Credentials, Region};
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
-use tokio::time::{timeout, Duration};
 
-use crate::error::{AppError, IntoAppError};
+use crate::error::AppError;
+use crate::uploader::body::FileBody;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::UploadContext;
+use super::s3_multipart::{upload_object, S3Object};
 
 #[derive(Serialize, Deserialize)]
 pub struct R2UploadResult {
@@ -19,13 +19,6 @@ pub struct R2UploadResult {
     size: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProgressPayload {
-    id: String,
-    progress: u64,
-    total: u64,
-}
-
 /// 上传文件到 Cloudflare R2
 ///
 /// # 参数
@@ -50,24 +43,21 @@ pub async fn upload_to_r2(
 ) -> Result<R2UploadResult, AppError> {
     println!("[R2] 开始上传: {} -> {}", file_path, key);
 
-    // 1. 检查文件是否存在
-    let path = Path::new(&file_path);
-    if !path.exists() {
-        return Err(AppError::file_io(format!("文件不存在: {}", file_path)));
-    }
+    // 注册取消令牌，cancel_upload(id) 可中断上传
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new());
 
-    // 2. 获取文件大小
-    let file_size = tokio::fs::metadata(&path)
-        .await
-        .into_file_io_err_with("读取文件元数据失败")?
-        .len();
+    // 1. 打开文件（只读取元数据，内容在上传时按块读取）
+    let file = FileBody::open(&file_path).await?;
+    let file_size = file.size();
 
     println!("[R2] 文件大小: {} bytes", file_size);
 
-    // 3. 发送初始进度
-    emit_progress(&window, &id, 0, file_size);
+    // 2. 发送初始进度
+    let progress = ProgressReporter::new(&ctx, 2);
+    progress.set_total(file_size);
+    progress.step(1, "准备上传...");
 
-    // 4. 构建 S3 客户端
+    // 3. 构建 S3 客户端
     let endpoint = format!("https://{}.r2.cloudflarestorage.com", account_id);
     println!("[R2] 端点: {}", endpoint);
 
@@ -87,80 +77,66 @@ pub async fn upload_to_r2(
 
     let client = Client::from_conf(config);
 
-    // 5. 检测 MIME 类型
-    let content_type = mime_guess::from_path(&path)
-        .first_or_octet_stream()
-        .to_string();
+    // 4. 检测 MIME 类型（优先按文件内容识别）
+    let content_type = match file.image_type() {
+        Some(image_type) => image_type.mime().to_string(),
+        None => mime_guess::from_path(&file_path)
+            .first_or_octet_stream()
+            .to_string(),
+    };
 
     println!("[R2] Content-Type: {}", content_type);
 
-    // 6. 读取文件
-    let mut file = File::open(&path)
-        .await
-        .into_file_io_err_with("打开文件失败")?;
-
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)
-        .await
-        .into_file_io_err_with("读取文件失败")?;
+    progress.step(2, "正在上传...");
 
-    // 发送 50% 进度（文件已读取）
-    emit_progress(&window, &id, file_size / 2, file_size);
-
-    // 7. 创建 ByteStream
-    let body = ByteStream::from(buffer);
-
-    // 8. 上传到 R2（设置 2 分钟超时）
+    // 5. 上传到 R2（大文件自动分片上传，支持断点续传）
     println!("[R2] 开始上传到存储桶: {}", bucket_name);
 
-    let upload_timeout = Duration::from_secs(120);
-
-    let result = timeout(upload_timeout, async {
-        client
-            .put_object()
-            .bucket(&bucket_name)
-            .key(&key)
-            .body(body)
-            .content_type(&content_type)
-            .send()
-            .await
-    })
-    .await
-    .map_err(|_| AppError::storage("R2 上传超时: 网络连接不稳定或文件过大，请稍后重试"))?
-    .map_err(|e| {
-        let error_msg = format!("R2 上传失败: {}", e);
-        println!("[R2] 错误: {}", error_msg);
-
-        // 转换为更友好的错误提示
-        if error_msg.contains("NoSuchBucket") {
-            return AppError::storage(format!("存储桶不存在: {}", bucket_name));
-        } else if error_msg.contains("AccessDenied") || error_msg.contains("InvalidAccessKeyId") {
-            return AppError::auth("R2 认证失败: 请检查 Account ID、Access Key ID 和 Secret Access Key");
-        } else if error_msg.contains("SignatureDoesNotMatch") {
-            return AppError::auth("R2 签名错误: 请检查 Secret Access Key 是否正确");
-        } else if error_msg.contains("timeout") {
-            return AppError::storage("R2 上传超时: 网络连接不稳定，请重试");
-        }
-
-        AppError::storage(error_msg)
-    })?;
+    let object = S3Object {
+        client: &client,
+        endpoint: &endpoint,
+        bucket: &bucket_name,
+        key: &key,
+        content_type: Some(&content_type),
+        service_name: "R2",
+    };
+
+    // 对象键由调用方决定且结果不含链接，旧命令不经过去重缓存
+    // 取消时丢弃 future：PutObject 不会留下残留对象，分片上传在后台中止并删除续传记录（见 s3_multipart.rs）
+    // 网络错误重试时分片上传从续传记录继续，已发送字节重新统计
+    let e_tag = ctx
+        .run_limited("r2", || {
+            progress.reset();
+            upload_object(&ctx, &object, &file, &progress)
+        })
+        .await
+        .map_err(|e| {
+            if e.is_cancelled() {
+                return e;
+            }
+
+            let error_msg = e.to_string();
+            println!("[R2] 错误: {}", error_msg);
+
+            // 转换为更友好的错误提示
+            if error_msg.contains("NoSuchBucket") {
+                return AppError::storage(format!("存储桶不存在: {}", bucket_name));
+            } else if error_msg.contains("AccessDenied") || error_msg.contains("InvalidAccessKeyId") {
+                return AppError::auth("R2 认证失败: 请检查 Account ID、Access Key ID 和 Secret Access Key");
+            } else if error_msg.contains("SignatureDoesNotMatch") {
+                return AppError::auth("R2 签名错误: 请检查 Secret Access Key 是否正确");
+            }
+
+            e
+        })?;
 
     // ✅ 修复: 删除此处的100%事件发送
     // 前端会在收到Ok结果时自动设置100%
 
-    println!("[R2] 上传成功！ETag: {:?}", result.e_tag());
+    println!("[R2] 上传成功！ETag: {:?}", e_tag);
 
     Ok(R2UploadResult {
-        e_tag: result.e_tag().map(|s| s.to_string()),
+        e_tag,
         size: file_size,
     })
 }
-
-/// 辅助函数：发送进度事件
-fn emit_progress(window: &Window, id: &str, progress: u64, total: u64) {
-    let _ = window.emit("upload://progress", ProgressPayload {
-        id: id.to_string(),
-        progress,
-        total,
-    });
-}
@@ -2,7 +2,7 @@
 // S3 兼容存储通用上传模块
 // 支持腾讯云 COS、阿里云 OSS、七牛云、又拍云
 
-use tauri::{Window, Emitter};
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use aws_sdk_s3::{Client, Config};
 use aws_sdk_s3::config::{Credentials, Region};
@@ -10,7 +10,14 @@ use aws_sdk_s3::primitives::ByteStream;
 use tokio::time::{timeout, Duration};
 
 use crate::error::AppError;
-use super::utils::read_file_bytes;
+use crate::image_format::{self, ImageType};
+use super::s3_multipart::{s3_error, upload_object, S3Object};
+use crate::uploader::body::FileBody;
+use crate::uploader::dedup::target_digest;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::RetryPolicy;
+use crate::uploader::{file_name_and_ext, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 // ==================== 常量 ====================
 
@@ -65,62 +72,91 @@ pub async fn upload_to_s3_compatible(
     bucket: String,
     key: String,
     public_domain: String,
+    service_id: Option<String>,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<S3UploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let target = S3Target {
+        endpoint,
+        access_key,
+        secret_key,
+        region,
+        bucket,
+        public_domain,
+    };
+    // 限流按具体的图床（如腾讯云、阿里云）区分，未指定时共用 "s3" 的预算
+    let service_id = service_id.as_deref().unwrap_or("s3");
+    let prepared = ctx.prepare_file(service_id, &file_path).await?;
+    let file_path = prepared.path();
+    // 格式转换后对象 Key 的扩展名随之变化
+    let key = prepared.adjust_key(&key);
+    let dir = key.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+    let dedup_target = target.dedup_target(dir);
+    ctx.run_deduped_at(service_id, &dedup_target, file_path, || upload_s3_internal(&ctx, file_path, &target, key.clone()))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// S3 上传目标（端点、凭证、存储桶和公开域名）
+pub(crate) struct S3Target {
+    pub endpoint: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub region: String,
+    pub bucket: String,
+    pub public_domain: String,
+}
+
+impl S3Target {
+    /// 去重目标：同一内容上传到不同的端点、存储桶、目录或公开域名时不复用上次的结果
+    ///
+    /// `dir` 为对象 Key 的目录部分，末尾有无 `/` 视为同一目录（命令传入 Key 的前缀，统一上传器传入配置的 path）
+    fn dedup_target(&self, dir: &str) -> String {
+        target_digest(&[&self.endpoint, &self.bucket, dir.trim_end_matches('/'), &self.public_domain])
+    }
+}
+
+/// S3 兼容上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_s3_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    target: &S3Target,
+    key: String,
 ) -> Result<S3UploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
+    let S3Target { endpoint, access_key, secret_key, region, bucket, public_domain } = target;
     println!("[S3兼容] 开始上传文件: {}", file_path);
 
-    // 发送进度: 0% - 读取文件
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "读取文件...",
-        "step_index": 1,
-        "total_steps": 3
-    }));
+    // 进度: 读取文件
+    progress.step(1, "读取文件...");
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     println!("[S3兼容] 文件大小: {} bytes", file_size);
 
-    // 发送进度: 33% - 创建客户端
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 33,
-        "total": 100,
-        "step": "创建客户端...",
-        "step_index": 2,
-        "total_steps": 3
-    }));
+    // 进度: 创建客户端
+    progress.step(2, "创建客户端...");
 
     // 2. 创建 S3 客户端
-    let client = create_s3_client(&endpoint, &access_key, &secret_key, &region);
-
-    // 发送进度: 66% - 正在上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 66,
-        "total": 100,
-        "step": "正在上传...",
-        "step_index": 3,
-        "total_steps": 3
-    }));
-
-    // 3. 上传文件（带超时保护）
-    let body = ByteStream::from(buffer);
-
-    timeout(
-        Duration::from_secs(S3_OPERATION_TIMEOUT_SECS * 2),  // 上传操作给予更长超时
-        client
-            .put_object()
-            .bucket(&bucket)
-            .key(&key)
-            .body(body)
-            .send()
-    )
-    .await
-    .map_err(|_| AppError::upload("S3兼容", format!("上传超时 ({}秒)", S3_OPERATION_TIMEOUT_SECS * 2)))?
-    .map_err(|e| AppError::upload("S3兼容", format!("上传失败: {}", e)))?;
+    let client = create_s3_client(endpoint, access_key, secret_key, region);
+
+    // 进度: 正在上传
+    progress.step(3, "正在上传...");
+
+    // 3. 上传文件（大文件自动分片上传，支持断点续传）
+    let object = S3Object {
+        client: &client,
+        endpoint,
+        bucket,
+        key: &key,
+        content_type: Some(file.content_type()),
+        service_name: "S3兼容",
+    };
+    upload_object(ctx, &object, &file, &progress).await?;
 
     println!("[S3兼容] 上传成功 - Key: {}", key);
 
@@ -334,18 +370,9 @@ impl S3TestConfig {
     }
 }
 
-/// 判断错误是否可重试（网络瞬时错误）
-fn is_retriable_error(error_msg: &str) -> bool {
-    error_msg.contains("dispatch failure")
-        || error_msg.contains("connection reset")
-        || error_msg.contains("connection closed")
-        || error_msg.contains("timeout")
-        || error_msg.contains("hyper")
-}
-
 /// 测试 S3 兼容存储连接
 /// 根据 service_id 自动构建对应的 endpoint 进行验证
-/// 包含重试机制（最多 3 次，指数退避）以应对网络瞬时错误
+/// 网络瞬时错误按统一重试策略重试（最多 3 次，带抖动的指数退避）
 #[tauri::command]
 pub async fn test_s3_connection(
     service_id: String,
@@ -375,82 +402,52 @@ pub async fn test_s3_connection(
 
     println!("[S3测试] Endpoint: {}, Region: {}, Bucket: {}", endpoint, region, bucket);
 
-    // 重试配置
-    const MAX_RETRIES: u32 = 3;
+    // 瞬时错误（连接失败、超时、5xx）按统一重试策略重试，每次尝试创建新的客户端（避免复用失败的连接）
     let test_timeout = Duration::from_secs(15);
-    let mut last_error: Option<AppError> = None;
-
-    for attempt in 1..=MAX_RETRIES {
-        // 每次尝试创建新的客户端（避免复用失败的连接）
-        let client = create_s3_client(&endpoint, &access_key, &secret_key, &region);
+    let retry = RetryPolicy {
+        base_delay: Duration::from_millis(200),
+        ..RetryPolicy::default()
+    };
+    let (endpoint, access_key, secret_key, region, bucket) = (&endpoint, &access_key, &secret_key, &region, &bucket);
+    let service_name = get_service_name(&service_id);
 
-        let result = timeout(test_timeout, async {
-            client
-                .list_objects_v2()
-                .bucket(&bucket)
-                .max_keys(1)
-                .send()
+    let result = retry
+        .run("S3测试", || async move {
+            let client = create_s3_client(endpoint, access_key, secret_key, region);
+            timeout(test_timeout, client.list_objects_v2().bucket(bucket).max_keys(1).send())
                 .await
+                .map_err(|_| AppError::network("连接超时，请检查网络或配置"))?
+                .map_err(|e| s3_error(service_name, "连接失败", e))
         })
         .await;
 
-        match result {
-            Ok(Ok(response)) => {
-                let object_count = response.contents().len();
-                if attempt > 1 {
-                    println!("[S3测试] ✓ {} 连接成功（第 {} 次尝试），存储桶内有 {} 个对象",
-                        service_id, attempt, object_count);
-                } else {
-                    println!("[S3测试] ✓ {} 连接成功，存储桶内有 {} 个对象",
-                        service_id, object_count);
-                }
-                return Ok(format!("{} 连接成功！", get_service_name(&service_id)));
-            }
-            Ok(Err(e)) => {
-                let error_msg = e.to_string();
-                println!("[S3测试] 第 {} 次尝试失败: {}", attempt, error_msg);
-
-                // 不可重试的错误（配置错误、认证错误等）直接返回
-                if error_msg.contains("NoSuchBucket") {
-                    return Err(AppError::storage(format!("存储桶不存在: {}", bucket)));
-                }
-                if error_msg.contains("AccessDenied") || error_msg.contains("InvalidAccessKeyId") {
-                    return Err(AppError::auth("认证失败: 请检查 Access Key 和 Secret Key"));
-                }
-                if error_msg.contains("SignatureDoesNotMatch") {
-                    return Err(AppError::auth("签名错误: 请检查 Secret Key 是否正确"));
-                }
-                if error_msg.contains("InvalidBucketName") {
-                    return Err(AppError::config(format!("无效的存储桶名称: {}", bucket)));
-                }
-
-                // 可重试的错误
-                if is_retriable_error(&error_msg) && attempt < MAX_RETRIES {
-                    let delay = Duration::from_millis(100 * 2u64.pow(attempt - 1));
-                    println!("[S3测试] 将在 {:?} 后重试...", delay);
-                    tokio::time::sleep(delay).await;
-                    last_error = Some(AppError::storage(format!("连接失败: {}", error_msg)));
-                    continue;
-                }
-
-                return Err(AppError::storage(format!("连接失败: {}", error_msg)));
-            }
-            Err(_) => {
-                println!("[S3测试] 第 {} 次尝试超时", attempt);
-                if attempt < MAX_RETRIES {
-                    let delay = Duration::from_millis(100 * 2u64.pow(attempt - 1));
-                    println!("[S3测试] 将在 {:?} 后重试...", delay);
-                    tokio::time::sleep(delay).await;
-                    last_error = Some(AppError::storage("连接超时，请检查网络或配置"));
-                    continue;
-                }
-                return Err(AppError::storage("连接超时，请检查网络或配置"));
+    match result {
+        Ok(response) => {
+            println!(
+                "[S3测试] ✓ {} 连接成功，存储桶内有 {} 个对象",
+                service_id,
+                response.contents().len()
+            );
+            Ok(format!("{} 连接成功！", service_name))
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            println!("[S3测试] 连接失败: {}", error_msg);
+
+            // 转换为更友好的错误提示
+            if error_msg.contains("NoSuchBucket") {
+                Err(AppError::storage(format!("存储桶不存在: {}", bucket)))
+            } else if error_msg.contains("AccessDenied") || error_msg.contains("InvalidAccessKeyId") {
+                Err(AppError::auth("认证失败: 请检查 Access Key 和 Secret Key"))
+            } else if error_msg.contains("SignatureDoesNotMatch") {
+                Err(AppError::auth("签名错误: 请检查 Secret Key 是否正确"))
+            } else if error_msg.contains("InvalidBucketName") {
+                Err(AppError::config(format!("无效的存储桶名称: {}", bucket)))
+            } else {
+                Err(e)
             }
         }
     }
-
-    // 所有重试都失败
-    Err(last_error.unwrap_or_else(|| AppError::storage("连接失败")))
 }
 
 /// 根据服务类型构建 S3 endpoint
@@ -573,7 +570,7 @@ pub async fn create_s3_folder(
         Duration::from_secs(S3_OPERATION_TIMEOUT_SECS),
         client
             .put_object()
-            .bucket(&bucket)
+            .bucket(bucket)
             .key(&key)
             .body(body)
             .send()
@@ -584,3 +581,204 @@ pub async fn create_s3_folder(
 
     Ok(format!("成功创建文件夹: {}", key))
 }
+
+// ==================== 统一上传器 ====================
+
+/// S3 兼容存储上传器
+///
+/// R2、腾讯云、阿里云、七牛云、又拍云共用同一实现，
+/// 区别仅在于配置字段名和端点的拼接方式（与前端各 S3 上传器保持一致）
+pub struct S3CompatibleUploader {
+    service_id: &'static str,
+    name: &'static str,
+}
+
+impl S3CompatibleUploader {
+    pub fn r2() -> Self {
+        Self { service_id: "r2", name: "Cloudflare R2" }
+    }
+
+    pub fn tencent() -> Self {
+        Self { service_id: "tencent", name: "腾讯云 COS" }
+    }
+
+    pub fn aliyun() -> Self {
+        Self { service_id: "aliyun", name: "阿里云 OSS" }
+    }
+
+    pub fn qiniu() -> Self {
+        Self { service_id: "qiniu", name: "七牛云" }
+    }
+
+    pub fn upyun() -> Self {
+        Self { service_id: "upyun", name: "又拍云" }
+    }
+
+    /// 从前端配置解析上传目标和存储路径前缀
+    fn resolve(&self, config: &serde_json::Value) -> Result<(S3Target, String), AppError> {
+        let field = |name: &str| -> String {
+            config.get(name)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        let required = |name: &str| -> Result<String, AppError> {
+            let value = field(name);
+            if value.is_empty() {
+                Err(AppError::config(format!("{} 配置缺少 {}", self.name, name)))
+            } else {
+                Ok(value)
+            }
+        };
+        let path_or_default = || {
+            let path = field("path");
+            if path.is_empty() { "images/".to_string() } else { path }
+        };
+
+        let (target, path) = match self.service_id {
+            "r2" => (
+                S3Target {
+                    endpoint: format!("https://{}.r2.cloudflarestorage.com", required("accountId")?),
+                    access_key: required("accessKeyId")?,
+                    secret_key: required("secretAccessKey")?,
+                    region: "auto".to_string(),
+                    bucket: required("bucketName")?,
+                    public_domain: field("publicDomain"),
+                },
+                field("path"),
+            ),
+            "tencent" => {
+                let region = required("region")?;
+                (
+                    S3Target {
+                        endpoint: format!("https://cos.{}.myqcloud.com", region),
+                        access_key: required("secretId")?,
+                        secret_key: required("secretKey")?,
+                        region,
+                        bucket: required("bucket")?,
+                        public_domain: field("publicDomain"),
+                    },
+                    path_or_default(),
+                )
+            }
+            "aliyun" => {
+                let region = required("region")?;
+                (
+                    S3Target {
+                        endpoint: format!("https://oss-{}.aliyuncs.com", region),
+                        access_key: required("accessKeyId")?,
+                        secret_key: required("accessKeySecret")?,
+                        region,
+                        bucket: required("bucket")?,
+                        public_domain: field("publicDomain"),
+                    },
+                    path_or_default(),
+                )
+            }
+            "qiniu" => {
+                let region = Some(field("region"))
+                    .filter(|r| !r.is_empty())
+                    .unwrap_or_else(|| "cn-east-1".to_string());
+                (
+                    S3Target {
+                        endpoint: format!("https://s3-{}.qiniucs.com", region),
+                        access_key: required("accessKey")?,
+                        secret_key: required("secretKey")?,
+                        region,
+                        bucket: required("bucket")?,
+                        public_domain: field("publicDomain"),
+                    },
+                    path_or_default(),
+                )
+            }
+            "upyun" => (
+                S3Target {
+                    endpoint: "https://s3.api.upyun.com".to_string(),
+                    access_key: required("operator")?,
+                    secret_key: required("password")?,
+                    region: "upyun".to_string(),
+                    bucket: required("bucket")?,
+                    public_domain: field("publicDomain"),
+                },
+                path_or_default(),
+            ),
+            other => return Err(AppError::config(format!("不支持的服务类型: {}", other))),
+        };
+
+        // 确保 path 以 / 结尾（如果非空）
+        let path = if path.is_empty() || path.ends_with('/') { path } else { format!("{}/", path) };
+        Ok((target, path))
+    }
+}
+
+#[async_trait::async_trait]
+impl Uploader for S3CompatibleUploader {
+    fn id(&self) -> &'static str {
+        self.service_id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: None,
+            max_gif_size: None,
+            accepted_formats: &[],
+            supports_delete: true,
+        }
+    }
+
+    fn dedup_target(&self, config: &serde_json::Value) -> String {
+        match self.resolve(config) {
+            Ok((target, path)) => target.dedup_target(&path),
+            Err(_) => String::new(),
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let (target, path) = self.resolve(config)?;
+        let (file_name, _) = file_name_and_ext(file_path)?;
+        // 扩展名与实际格式不符时修正，避免访问时按错误的类型解析
+        let file_name = match ImageType::detect(file_path) {
+            Some(image_type) => image_format::corrected_file_name(&file_name, image_type),
+            None => file_name,
+        };
+        let key = format!("{}{}", path, file_name);
+
+        let res = upload_s3_internal(ctx, file_path, &target, key).await?;
+        Ok(UploadOutput::new(self.service_id, res.key.clone(), res.url)
+            .with_meta("key", res.key))
+    }
+
+    async fn delete(
+        &self,
+        _ctx: &UploadContext,
+        output: &UploadOutput,
+        config: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let (target, _) = self.resolve(config)?;
+        let client = create_s3_client(&target.endpoint, &target.access_key, &target.secret_key, &target.region);
+
+        timeout(
+            Duration::from_secs(S3_OPERATION_TIMEOUT_SECS),
+            client
+                .delete_object()
+                .bucket(&target.bucket)
+                .key(&output.file_key)
+                .send()
+        )
+        .await
+        .map_err(|_| AppError::storage(format!("删除对象超时 ({}秒)", S3_OPERATION_TIMEOUT_SECS)))?
+        .map_err(|e| AppError::storage(format!("删除对象失败: {}", e)))?;
+
+        println!("[S3兼容] 删除成功 - Key: {}", output.file_key);
+        Ok(())
+    }
+}
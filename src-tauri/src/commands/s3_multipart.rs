@@ -0,0 +1,523 @@
+// src-tauri/src/commands/s3_multipart.rs
+// S3 分片上传（R2 与 S3 兼容存储共用）
+//
+// - 小文件仍使用单次 PutObject
+// - 超过 MULTIPART_THRESHOLD 的文件使用分片上传，多个分片并行发送，每个分片单独超时
+// - 每完成一个分片就把 ETag 写入本地续传记录，中断后再次上传同一文件时从已完成的分片继续
+// - 出现不可恢复的错误或用户取消时中止分片上传（已上传的分片在服务端按存储计费），网络中断时保留续传记录
+// - 同一对象上创建时间超过续传记录有效期的未完成上传视为孤儿，开始新上传前中止
+//   （较新的上传可能属于另一台设备或另一个进程，仍在进行中，不能中止）
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+use futures::stream::{self, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::time::{timeout, Duration};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::AppError;
+use crate::uploader::body::FileBody;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::resume::{self, ResumeStore, RESUME_RECORD_TTL};
+use crate::uploader::UploadContext;
+
+/// 超过该大小使用分片上传
+pub(crate) const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// 最小分片大小（S3 要求除最后一片外不小于 5MB）
+const MIN_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// S3 单次上传最多 10000 个分片
+const MAX_PARTS: u64 = 10_000;
+
+/// 同时上传的分片数
+const PART_CONCURRENCY: usize = 4;
+
+/// 单次 PutObject 的超时时间
+const PUT_OBJECT_TIMEOUT_SECS: u64 = 120;
+
+/// 单个分片的超时时间
+const PART_TIMEOUT_SECS: u64 = 120;
+
+/// 其他分片上传相关请求（创建、列出、完成、中止）的超时时间
+const CONTROL_TIMEOUT_SECS: u64 = 30;
+
+/// 上传目标
+pub(crate) struct S3Object<'a> {
+    pub client: &'a Client,
+    /// 用于区分续传记录的端点标识（如 endpoint URL）
+    pub endpoint: &'a str,
+    pub bucket: &'a str,
+    pub key: &'a str,
+    pub content_type: Option<&'a str>,
+    /// 错误信息中显示的服务名
+    pub service_name: &'static str,
+}
+
+/// 分片上传续传记录
+#[derive(Debug, Serialize, Deserialize)]
+struct MultipartRecord {
+    upload_id: String,
+    part_size: u64,
+    /// 分片号 -> ETag
+    parts: BTreeMap<i32, String>,
+    created_at: i64,
+}
+
+/// 上传对象，按文件大小自动选择 PutObject 或分片上传，返回 ETag
+pub(crate) async fn upload_object(
+    ctx: &UploadContext,
+    target: &S3Object<'_>,
+    file: &FileBody,
+    progress: &ProgressReporter,
+) -> Result<Option<String>, AppError> {
+    if file.size() <= MULTIPART_THRESHOLD {
+        return put_object(target, file, progress).await;
+    }
+    upload_multipart(ctx, target, file, progress).await
+}
+
+/// 单次 PutObject
+async fn put_object(
+    target: &S3Object<'_>,
+    file: &FileBody,
+    progress: &ProgressReporter,
+) -> Result<Option<String>, AppError> {
+    let mut request = target
+        .client
+        .put_object()
+        .bucket(target.bucket)
+        .key(target.key)
+        .content_length(file.size() as i64)  // 流式请求体无法推断长度，必须显式设置
+        .body(file.byte_stream(progress));
+    if let Some(content_type) = target.content_type {
+        request = request.content_type(content_type);
+    }
+
+    // PutObject 是单次请求，取消时丢弃 future 即可，服务端不会留下残留对象
+    let output = timeout(Duration::from_secs(PUT_OBJECT_TIMEOUT_SECS), request.send())
+        .await
+        .map_err(|_| {
+            AppError::network(format!(
+                "{} 上传超时 ({}秒): 网络连接不稳定或文件过大，请稍后重试",
+                target.service_name, PUT_OBJECT_TIMEOUT_SECS
+            ))
+        })?
+        .map_err(|e| s3_error(target.service_name, "上传失败", e))?;
+
+    Ok(output.e_tag().map(|s| s.to_string()))
+}
+
+/// 分片大小：保证分片数不超过 MAX_PARTS，按 1MB 对齐
+fn part_size_for(file_size: u64) -> u64 {
+    const MB: u64 = 1024 * 1024;
+    let min_for_count = file_size.div_ceil(MAX_PARTS);
+    min_for_count.max(MIN_PART_SIZE).div_ceil(MB) * MB
+}
+
+/// 分片上传（支持断点续传）
+async fn upload_multipart(
+    ctx: &UploadContext,
+    target: &S3Object<'_>,
+    file: &FileBody,
+    progress: &ProgressReporter,
+) -> Result<Option<String>, AppError> {
+    let store = ResumeStore::new(ctx.host.as_ref())?;
+    let record_key = ResumeStore::key(&["s3", target.endpoint, target.bucket, target.key, &file.fingerprint()]);
+
+    // 1. 尝试恢复之前的上传
+    let resumed = match store.load::<MultipartRecord>(&record_key) {
+        Some(record) if resume::is_expired(record.created_at) => {
+            println!("[S3分片] 续传记录已过期，中止旧的分片上传");
+            let _ = abort_upload(target, &record.upload_id).await;
+            store.remove(&record_key);
+            None
+        }
+        Some(record) => match list_uploaded_parts(target, &record.upload_id).await {
+            Ok(parts) => {
+                println!(
+                    "[S3分片] 恢复分片上传 {}，服务端已有 {} 个分片",
+                    record.upload_id,
+                    parts.len()
+                );
+                // 以服务端实际存在的分片为准
+                Some(MultipartRecord { parts, ..record })
+            }
+            // 网络问题时保留记录，下次再尝试恢复
+            Err(e @ AppError::Network { .. }) => return Err(e),
+            Err(e) => {
+                println!("[S3分片] 无法恢复分片上传（{}），重新开始", e);
+                store.remove(&record_key);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // 2. 没有可恢复的上传时，先清理该对象上的孤儿上传，再创建新上传
+    let record = match resumed {
+        Some(record) => record,
+        None => create_upload(target, file, &store, &record_key).await?,
+    };
+
+    let upload_id = record.upload_id.clone();
+    let mut abort_guard = CancelAbortGuard::new(ctx, target, &upload_id, &store, &record_key);
+    let part_size = record.part_size;
+    let part_count = file.size().div_ceil(part_size) as i32;
+
+    // 已完成的分片直接计入进度
+    let pending: Vec<i32> = (1..=part_count).filter(|n| !record.parts.contains_key(n)).collect();
+    let done_bytes: u64 = (1..=part_count)
+        .filter(|n| record.parts.contains_key(n))
+        .map(|n| part_range(file.size(), part_size, n).1)
+        .sum();
+    if done_bytes > 0 {
+        progress.advance(done_bytes);
+    }
+    println!(
+        "[S3分片] 共 {} 个分片（每片 {}MB），待上传 {} 个",
+        part_count,
+        part_size / 1024 / 1024,
+        pending.len()
+    );
+
+    // 3. 并行上传剩余分片，每完成一个就保存续传记录
+    let record = Mutex::new(record);
+    let result = stream::iter(pending.into_iter().map(Ok::<i32, AppError>))
+        .try_for_each_concurrent(PART_CONCURRENCY, |part_number| {
+            let (offset, len) = part_range(file.size(), part_size, part_number);
+            let upload_id = upload_id.as_str();
+            let record = &record;
+            let store = &store;
+            let record_key = record_key.as_str();
+            async move {
+                let etag = upload_part(target, file, upload_id, part_number, offset, len, progress).await?;
+                let mut record = record.lock().unwrap_or_else(|p| p.into_inner());
+                record.parts.insert(part_number, etag);
+                if let Err(e) = store.save(record_key, &*record) {
+                    eprintln!("[S3分片] 保存续传记录失败: {}", e);
+                }
+                Ok::<(), AppError>(())
+            }
+        })
+        .await;
+
+    if let Err(e) = result {
+        // 网络类错误保留续传记录以便下次继续；其他错误（认证、存储桶不存在等）重试也不会成功，直接中止
+        if !matches!(e, AppError::Network { .. }) {
+            eprintln!("[S3分片] 不可恢复的错误，中止分片上传: {}", e);
+            abort_guard.disarm();
+            let _ = abort_upload(target, &upload_id).await;
+            store.remove(&record_key);
+        }
+        return Err(e);
+    }
+
+    // 4. 完成上传
+    let parts: Vec<CompletedPart> = {
+        let record = record.lock().unwrap_or_else(|p| p.into_inner());
+        record
+            .parts
+            .iter()
+            .map(|(n, etag)| CompletedPart::builder().part_number(*n).e_tag(etag).build())
+            .collect()
+    };
+
+    let completed = timeout(
+        Duration::from_secs(CONTROL_TIMEOUT_SECS),
+        target
+            .client
+            .complete_multipart_upload()
+            .bucket(target.bucket)
+            .key(target.key)
+            .upload_id(&upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send(),
+    )
+    .await
+    .map_err(|_| AppError::network(format!("{} 完成分片上传超时", target.service_name)))?;
+
+    match completed {
+        Ok(output) => {
+            abort_guard.disarm();
+            store.remove(&record_key);
+            println!("[S3分片] 分片上传完成: {}", target.key);
+            Ok(output.e_tag().map(|s| s.to_string()))
+        }
+        Err(e) => {
+            let err = s3_error(target.service_name, "完成分片上传失败", e);
+            if !matches!(err, AppError::Network { .. }) {
+                abort_guard.disarm();
+                let _ = abort_upload(target, &upload_id).await;
+                store.remove(&record_key);
+            }
+            Err(err)
+        }
+    }
+}
+
+/// 用户取消时中止分片上传并删除续传记录
+///
+/// 取消会直接丢弃上传 future，无法在正常流程中清理，因此与纳米图床的 `MultipartAbortGuard` 一样在 drop 时
+/// 后台发送中止请求。只在取消令牌已触发时生效：网络中断等错误返回时保留续传记录，下次上传继续
+struct CancelAbortGuard {
+    client: Client,
+    bucket: String,
+    key: String,
+    service_name: &'static str,
+    upload_id: String,
+    cancel: CancellationToken,
+    store: ResumeStore,
+    record_key: String,
+    armed: bool,
+}
+
+impl CancelAbortGuard {
+    fn new(ctx: &UploadContext, target: &S3Object<'_>, upload_id: &str, store: &ResumeStore, record_key: &str) -> Self {
+        Self {
+            client: target.client.clone(),
+            bucket: target.bucket.to_string(),
+            key: target.key.to_string(),
+            service_name: target.service_name,
+            upload_id: upload_id.to_string(),
+            cancel: ctx.cancel.clone(),
+            store: store.clone(),
+            record_key: record_key.to_string(),
+            armed: true,
+        }
+    }
+
+    /// 上传已完成或已在正常流程中中止
+    fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for CancelAbortGuard {
+    fn drop(&mut self) {
+        if !self.armed || !self.cancel.is_cancelled() {
+            return;
+        }
+
+        self.store.remove(&self.record_key);
+        let client = self.client.clone();
+        let bucket = std::mem::take(&mut self.bucket);
+        let key = std::mem::take(&mut self.key);
+        let upload_id = std::mem::take(&mut self.upload_id);
+        let service_name = self.service_name;
+
+        println!("[S3分片] 上传已取消，中止分片上传: {}", upload_id);
+        tauri::async_runtime::spawn(async move {
+            let target = S3Object {
+                client: &client,
+                endpoint: "",
+                bucket: &bucket,
+                key: &key,
+                content_type: None,
+                service_name,
+            };
+            if let Err(e) = abort_upload(&target, &upload_id).await {
+                eprintln!("[S3分片] 中止分片上传失败: {}", e);
+            }
+        });
+    }
+}
+
+/// 创建新的分片上传并保存续传记录
+async fn create_upload(
+    target: &S3Object<'_>,
+    file: &FileBody,
+    store: &ResumeStore,
+    record_key: &str,
+) -> Result<MultipartRecord, AppError> {
+    abort_orphaned_uploads(target).await;
+
+    let mut request = target.client.create_multipart_upload().bucket(target.bucket).key(target.key);
+    if let Some(content_type) = target.content_type {
+        request = request.content_type(content_type);
+    }
+    let output = timeout(Duration::from_secs(CONTROL_TIMEOUT_SECS), request.send())
+        .await
+        .map_err(|_| AppError::network(format!("{} 创建分片上传超时", target.service_name)))?
+        .map_err(|e| s3_error(target.service_name, "创建分片上传失败", e))?;
+    let upload_id = output
+        .upload_id()
+        .ok_or_else(|| AppError::upload(target.service_name, "创建分片上传失败：未返回 UploadId"))?
+        .to_string();
+
+    println!("[S3分片] 创建分片上传: {}", upload_id);
+    let new_record = MultipartRecord {
+        upload_id,
+        part_size: part_size_for(file.size()),
+        parts: BTreeMap::new(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    store.save(record_key, &new_record)?;
+    Ok(new_record)
+}
+
+/// 第 n 个分片（从 1 开始）的偏移和长度
+fn part_range(file_size: u64, part_size: u64, part_number: i32) -> (u64, u64) {
+    let offset = (part_number as u64 - 1) * part_size;
+    (offset, part_size.min(file_size - offset))
+}
+
+async fn upload_part(
+    target: &S3Object<'_>,
+    file: &FileBody,
+    upload_id: &str,
+    part_number: i32,
+    offset: u64,
+    len: u64,
+    progress: &ProgressReporter,
+) -> Result<String, AppError> {
+    let request = target
+        .client
+        .upload_part()
+        .bucket(target.bucket)
+        .key(target.key)
+        .upload_id(upload_id)
+        .part_number(part_number)
+        .content_length(len as i64)
+        .body(file.range_byte_stream(offset, len, progress));
+
+    let output = timeout(Duration::from_secs(PART_TIMEOUT_SECS), request.send())
+        .await
+        .map_err(|_| AppError::network(format!("{} 分片 {} 上传超时", target.service_name, part_number)))?
+        .map_err(|e| s3_error(target.service_name, &format!("分片 {} 上传失败", part_number), e))?;
+
+    output
+        .e_tag()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AppError::upload(target.service_name, format!("分片 {} 未返回 ETag", part_number)))
+}
+
+/// 列出服务端已上传的分片（分片号 -> ETag）
+async fn list_uploaded_parts(target: &S3Object<'_>, upload_id: &str) -> Result<BTreeMap<i32, String>, AppError> {
+    let mut parts = BTreeMap::new();
+    let mut marker: Option<String> = None;
+
+    loop {
+        let output = timeout(
+            Duration::from_secs(CONTROL_TIMEOUT_SECS),
+            target
+                .client
+                .list_parts()
+                .bucket(target.bucket)
+                .key(target.key)
+                .upload_id(upload_id)
+                .set_part_number_marker(marker.clone())
+                .send(),
+        )
+        .await
+        .map_err(|_| AppError::network("列出分片超时"))?
+        .map_err(|e| s3_error(target.service_name, "列出分片失败", e))?;
+
+        for part in output.parts() {
+            if let (Some(n), Some(etag)) = (part.part_number(), part.e_tag()) {
+                parts.insert(n, etag.to_string());
+            }
+        }
+
+        if output.is_truncated() != Some(true) {
+            break;
+        }
+        marker = output.next_part_number_marker().map(|s| s.to_string());
+        if marker.is_none() {
+            break;
+        }
+    }
+
+    Ok(parts)
+}
+
+async fn abort_upload(target: &S3Object<'_>, upload_id: &str) -> Result<(), AppError> {
+    timeout(
+        Duration::from_secs(CONTROL_TIMEOUT_SECS),
+        target
+            .client
+            .abort_multipart_upload()
+            .bucket(target.bucket)
+            .key(target.key)
+            .upload_id(upload_id)
+            .send(),
+    )
+    .await
+    .map_err(|_| AppError::network("中止分片上传超时"))?
+    .map_err(|e| s3_error(target.service_name, "中止分片上传失败", e))?;
+    println!("[S3分片] 已中止分片上传: {}", upload_id);
+    Ok(())
+}
+
+/// 中止同一对象上已超过续传记录有效期的未完成分片上传
+///
+/// 这些上传不可能再被任何续传记录恢复；有效期内的上传可能正由其他设备或进程进行，保持不动。
+/// 部分 S3 兼容服务不支持 ListMultipartUploads，失败时忽略
+async fn abort_orphaned_uploads(target: &S3Object<'_>) {
+    let listed = timeout(
+        Duration::from_secs(CONTROL_TIMEOUT_SECS),
+        target
+            .client
+            .list_multipart_uploads()
+            .bucket(target.bucket)
+            .prefix(target.key)
+            .send(),
+    )
+    .await;
+
+    let Ok(Ok(output)) = listed else {
+        return;
+    };
+
+    let cutoff = chrono::Utc::now().timestamp_millis() - RESUME_RECORD_TTL.as_millis() as i64;
+    for upload in output.uploads() {
+        if upload.key() != Some(target.key) {
+            continue;
+        }
+        // 没有创建时间时无法判断是否仍在进行，同样保留
+        let stale = upload
+            .initiated()
+            .and_then(|initiated| initiated.to_millis().ok())
+            .is_some_and(|initiated| initiated < cutoff);
+        if !stale {
+            continue;
+        }
+        if let Some(upload_id) = upload.upload_id() {
+            println!("[S3分片] 发现孤儿分片上传 {}，中止", upload_id);
+            let _ = abort_upload(target, upload_id).await;
+        }
+    }
+}
+
+/// 将 SDK 错误转换为 AppError
+///
+/// 请求未到达服务端（超时、连接失败）或服务端临时故障时为网络错误，可以续传；服务端明确拒绝时为上传错误
+pub(crate) fn s3_error<E>(service_name: &str, context: &str, error: SdkError<E, HttpResponse>) -> AppError
+where
+    E: std::error::Error + 'static,
+{
+    let message = format!("{}: {}", context, DisplayErrorContext(&error));
+    let server_error = error.raw_response().is_some_and(|r| r.status().is_server_error());
+    match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            AppError::network(format!("{} {}", service_name, message))
+        }
+        // 5xx / SlowDown 等服务端临时故障，同样保留续传记录
+        _ if server_error => AppError::network(format!("{} {}", service_name, message)),
+        _ => {
+            if message.contains("AccessDenied")
+                || message.contains("InvalidAccessKeyId")
+                || message.contains("SignatureDoesNotMatch")
+            {
+                AppError::auth(format!("{} 认证失败: {}", service_name, message))
+            } else {
+                AppError::upload(service_name, message)
+            }
+        }
+    }
+}
@@ -1,12 +1,16 @@
 // src-tauri/src/commands/smms.rs
 // SM.MS 图床上传命令
 
-use tauri::{Window, Emitter};
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::multipart;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::reject_transient;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 /// SM.MS 上传结果
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +43,9 @@ struct SmmsData {
 /// 文件大小限制：5MB（SM.MS 免费用户限制）
 const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
 
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "webp"];
+
 /// 上传文件到 SM.MS
 #[tauri::command]
 pub async fn upload_to_smms(
@@ -46,21 +53,33 @@ pub async fn upload_to_smms(
     id: String,
     file_path: String,
     smms_token: String,
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<SmmsUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("smms", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("smms", file_path, || upload_smms_internal(&ctx, file_path, &smms_token))
+        .await
+        .map(|result| prepared.attach(result))
+}
+
+/// SM.MS 上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_smms_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    smms_token: &str,
 ) -> Result<SmmsUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 3);
     println!("[SM.MS] 开始上传文件: {}", file_path);
 
-    // 发送进度: 0% - 读取文件
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 0,
-        "total": 100,
-        "step": "读取文件...",
-        "step_index": 1,
-        "total_steps": 3
-    }));
+    // 进度: 读取文件
+    progress.step(1, "读取文件...");
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(&file_path).await?;
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 验证文件大小（限制 5MB）
     if file_size > MAX_FILE_SIZE {
@@ -71,46 +90,26 @@ pub async fn upload_to_smms(
     }
 
     // 3. 验证文件类型（只允许图片）
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
-    if !["jpg", "jpeg", "png", "gif", "bmp", "webp"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF、BMP、WebP 格式的图片"));
     }
 
-    // 发送进度: 33% - 准备上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 33,
-        "total": 100,
-        "step": "准备上传...",
-        "step_index": 2,
-        "total_steps": 3
-    }));
+    // 进度: 准备上传
+    progress.step(2, "准备上传...");
 
     // 4. 构建 multipart form
-    let part = multipart::Part::bytes(buffer)
-        .file_name(file_name.to_string())
-        .mime_str("image/*")
+    let part = file.multipart_part(&progress)
+        .file_name(file.upload_file_name())
+        .mime_str(file.content_type())
         .into_validation_err_with("无法设置 MIME 类型")?;
 
     let form = multipart::Form::new().part("smfile", part);
 
-    // 发送进度: 66% - 正在上传
-    let _ = window.emit("upload://progress", serde_json::json!({
-        "id": id,
-        "progress": 66,
-        "total": 100,
-        "step": "正在上传...",
-        "step_index": 3,
-        "total_steps": 3
-    }));
+    // 进度: 正在上传
+    progress.step(3, "正在上传...");
 
     // 5. 发送请求到 SM.MS API
     let client = reqwest::Client::new();
@@ -122,6 +121,7 @@ pub async fn upload_to_smms(
         .send()
         .await
         .into_network_err_with("上传请求失败")?;
+    let response = reject_transient("SM.MS", response).await?;
 
     // 6. 检查 HTTP 状态码
     let status = response.status();
@@ -131,8 +131,6 @@ pub async fn upload_to_smms(
         return match status {
             reqwest::StatusCode::UNAUTHORIZED =>
                 Err(AppError::auth("SM.MS Token 无效或已过期")),
-            reqwest::StatusCode::TOO_MANY_REQUESTS =>
-                Err(AppError::upload("SM.MS", "API 调用频率超限，请稍后重试")),
             reqwest::StatusCode::PAYLOAD_TOO_LARGE =>
                 Err(AppError::validation("文件大小超过限制 (5MB)")),
             _ => Err(AppError::upload("SM.MS", format!("上传失败 (HTTP {}): {}", status, response_text)))
@@ -165,3 +163,83 @@ pub async fn upload_to_smms(
         hash: data.hash,
     })
 }
+
+/// SM.MS 图床配置（对应前端 config.services.smms）
+#[derive(Deserialize)]
+struct SmmsConfig {
+    token: String,
+}
+
+/// SM.MS 上传器
+pub struct SmmsUploader;
+
+#[async_trait::async_trait]
+impl Uploader for SmmsUploader {
+    fn id(&self) -> &'static str {
+        "smms"
+    }
+
+    fn name(&self) -> &'static str {
+        "SM.MS"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: Some(MAX_FILE_SIZE),
+            max_gif_size: None,
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: true,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: SmmsConfig = parse_config(self.name(), config)?;
+        if config.token.trim().is_empty() {
+            return Err(AppError::config("SM.MS Token 未配置"));
+        }
+
+        let res = upload_smms_internal(ctx, file_path, &config.token).await?;
+        let file_key = res.hash.clone().unwrap_or_else(|| res.url.clone());
+
+        let mut output = UploadOutput::new(self.id(), file_key, res.url);
+        if let Some(hash) = res.hash {
+            output = output.with_meta("hash", hash);
+        }
+        if let Some(delete_url) = res.delete {
+            output = output.with_meta("deleteUrl", delete_url);
+        }
+        Ok(output)
+    }
+
+    async fn delete(
+        &self,
+        ctx: &UploadContext,
+        output: &UploadOutput,
+        config: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let config: SmmsConfig = parse_config(self.name(), config)?;
+        let hash = output.meta_str("hash")
+            .ok_or_else(|| AppError::validation("缺少 SM.MS 删除凭证 (hash)"))?;
+
+        let response = ctx.http_client
+            .get(format!("https://sm.ms/api/v2/delete/{}", hash))
+            .header("Authorization", config.token)
+            .timeout(std::time::Duration::from_secs(30))
+            .send()
+            .await
+            .into_network_err_with("删除请求失败")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::upload("SM.MS", format!("删除失败 (HTTP {})", status)));
+        }
+
+        println!("[SM.MS] 删除成功: {}", hash);
+        Ok(())
+    }
+}
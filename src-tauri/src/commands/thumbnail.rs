@@ -0,0 +1,22 @@
+// src-tauri/src/commands/thumbnail.rs
+// 缩略图缓存命令（缓存实现和 thumb:// 协议见 src/thumbnail.rs）
+
+use tauri::{AppHandle, State};
+
+use crate::thumbnail::{self, ThumbnailCache};
+
+/// 用本地文件为已上传的图片生成缩略图（前端保存历史记录后调用，后台执行）
+///
+/// `urls` 为各图床返回的链接，缩略图按链接分别缓存
+#[tauri::command]
+pub fn generate_upload_thumbnails(app: AppHandle, file_path: String, urls: Vec<String>) {
+    thumbnail::generate_for_upload(&app, &file_path, urls);
+}
+
+/// 清空缩略图缓存，返回删除的文件数
+#[tauri::command]
+pub fn clear_thumbnail_cache(cache: State<'_, ThumbnailCache>) -> usize {
+    let removed = cache.clear();
+    println!("[Thumbnail] 已清空缩略图缓存: {} 个文件", removed);
+    removed
+}
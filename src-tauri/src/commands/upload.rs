@@ -1,16 +1,13 @@
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
 use crate::error::AppError;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use reqwest::header;
-use std::path::Path;
 use quick_xml::events::Event;
 use quick_xml::Reader;
-use tauri::{Window, Emitter};
-use futures::StreamExt;
-use std::sync::{Arc, Mutex};
+use tauri::{Window, Manager};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct UploadResponse {
     pub pid: String,
     pub width: i32,
@@ -18,20 +15,6 @@ pub struct UploadResponse {
     pub size: i32,
 }
 
-/// 定义进度事件的载荷
-#[derive(Serialize, Clone)]
-struct ProgressPayload {
-    id: String,
-    progress: u64,
-    total: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    step: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    step_index: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    total_steps: Option<u32>,
-}
-
 /// 使用 quick-xml 进行健壮的 XML 解析
 /// 能够处理格式变化（如空格、换行符等）
 fn parse_weibo_response(xml: &str) -> Result<UploadResponse, AppError> {
@@ -163,6 +146,9 @@ fn find_xml_tag_content(xml: &str, tag: &str) -> Option<String> {
 
 // HttpClient 在 main.rs 中定义，这里直接使用
 use crate::HttpClient;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 #[tauri::command]
 pub async fn upload_file_stream(
@@ -170,12 +156,27 @@ pub async fn upload_file_stream(
     id: String,
     file_path: String,
     weibo_cookie: String,
+    force_upload: Option<bool>,
     http_client: tauri::State<'_, HttpClient>
-) -> Result<UploadResponse, AppError> {
+) -> Result<WithProcessing<UploadResponse>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, http_client.0.clone())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("weibo", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("weibo", file_path, || upload_weibo_internal(&ctx, file_path, &weibo_cookie))
+        .await
+        .map(|result| prepared.attach(result))
+}
 
+/// 微博流式上传（内部函数，供命令和统一上传器共用）
+pub(crate) async fn upload_weibo_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    weibo_cookie: &str,
+) -> Result<UploadResponse, AppError> {
     // 安全验证：防止路径遍历攻击
     // 使用 canonicalize 解析真实路径，防止通过 ../ 或符号链接访问未授权文件
-    let canonical_path = std::fs::canonicalize(&file_path)
+    let canonical_path = std::fs::canonicalize(file_path)
         .map_err(|e| AppError::file_io(format!("无法解析文件路径: {}", e)))?;
 
     // 验证是普通文件而不是目录或特殊文件
@@ -183,84 +184,24 @@ pub async fn upload_file_stream(
         return Err(AppError::file_io("指定的路径不是有效的文件"));
     }
 
-    // 发送步骤1进度：读取文件 (0%)
-    let _ = window.emit("upload://progress", ProgressPayload {
-        id: id.clone(),
-        progress: 0,
-        total: 100,
-        step: Some("读取文件...".to_string()),
-        step_index: Some(1),
-        total_steps: Some(3),
-    });
-
-    let path = Path::new(&file_path);
-    // Unused variable file_name
-    let _file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("image.jpg");
+    let progress = ProgressReporter::new(ctx, 3);
+    progress.step(1, "读取文件...");
 
     // 使用规范化后的路径打开文件
     let file = File::open(&canonical_path).await?;
     let metadata = file.metadata().await?;
     let total_len = metadata.len();
+    progress.set_total(total_len);
 
-    // 使用 FramedRead 读取文件流
+    // 使用 FramedRead 读取文件流，每块数据发出时累加已发送字节
     let stream = FramedRead::new(file, BytesCodec::new());
-    
-    // 关键优化：通过 map 包装流，在此处注入进度监控
-    let uploaded = Arc::new(Mutex::new(0u64));
-    let uploaded_clone = Arc::clone(&uploaded);
-    let window_clone = window.clone();
-    let id_clone = id.clone();
-    let total_len_clone = total_len;
-    
-    let progress_stream = stream.map(move |chunk: Result<tokio_util::bytes::BytesMut, std::io::Error>| {
-        if let Ok(bytes) = &chunk {
-            // 安全处理 Mutex lock，避免 panic
-            // 使用 unwrap_or_else 恢复被污染的 Mutex（进度计数器不影响业务正确性）
-            let mut uploaded_guard = match uploaded_clone.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => {
-                    // Mutex 被污染（之前有 panic），尝试恢复
-                    // 对于进度计数器，恢复是安全的，因为它不影响上传结果
-                    eprintln!("[上传] 警告: Mutex 锁被污染，尝试恢复进度计数器");
-                    poisoned.into_inner()
-                }
-            };
-
-            *uploaded_guard += bytes.len() as u64;
-            let current_progress = *uploaded_guard;
-            drop(uploaded_guard); // 尽早释放锁
-
-            // ✅ 修复: 限制进度最高99%，防止在业务验证前就显示100%
-            let safe_progress = if current_progress >= total_len_clone {
-                // 数据已发送完毕，但服务器尚未响应，保持在99%
-                if total_len_clone > 0 {
-                    total_len_clone.saturating_sub(total_len_clone / 100).max(1)
-                } else {
-                    0
-                }
-            } else {
-                current_progress
-            };
-
-            // 发送进度事件到前端(带步骤信息)
-            let _ = window_clone.emit("upload://progress", ProgressPayload {
-                id: id_clone.clone(),
-                progress: safe_progress,
-                total: total_len_clone,
-                step: Some("正在上传...".to_string()),
-                step_index: Some(2),
-                total_steps: Some(3),
-            });
-        }
-        chunk
-    });
-
-    let body = reqwest::Body::wrap_stream(progress_stream);
+    let body = reqwest::Body::wrap_stream(progress.track(stream));
+    progress.step(2, "正在上传...");
 
     let url = "https://picupload.weibo.com/interface/pic_upload.php?s=xml&ori=1&data=1&rotate=0&wm=&app=miniblog&mime=image/jpeg";
 
     // 使用全局 HTTP 客户端（带连接池配置），而不是创建新客户端
-    let res = http_client.0.post(url)
+    let res = ctx.http_client.post(url)
         .header(header::COOKIE, weibo_cookie)
         .header(header::CONTENT_LENGTH, total_len) // 必须显式设置长度，否则流式上传可能无法计算总长
         .header(header::CONTENT_TYPE, "application/octet-stream")
@@ -273,15 +214,7 @@ pub async fn upload_file_stream(
 
     let text = res.text().await?;
 
-    // 发送步骤3进度：处理响应 (95%)
-    let _ = window.emit("upload://progress", ProgressPayload {
-        id: id.clone(),
-        progress: 95,
-        total: 100,
-        step: Some("处理响应...".to_string()),
-        step_index: Some(3),
-        total_steps: Some(3),
-    });
+    progress.step(3, "处理响应...");
 
     // ✅ 修复: 删除此处的100%事件发送
     // 只有parse_weibo_response成功返回后，前端才会在收到Ok结果时设置100%
@@ -372,3 +305,52 @@ pub async fn test_weibo_connection(
     }
 }
 
+
+/// 微博图床配置（对应前端 config.services.weibo）
+#[derive(serde::Deserialize)]
+struct WeiboConfig {
+    cookie: String,
+}
+
+/// 微博上传器
+pub struct WeiboUploader;
+
+#[async_trait::async_trait]
+impl Uploader for WeiboUploader {
+    fn id(&self) -> &'static str {
+        "weibo"
+    }
+
+    fn name(&self) -> &'static str {
+        "新浪微博"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: None,
+            max_gif_size: None,
+            accepted_formats: &[],
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: WeiboConfig = parse_config(self.name(), config)?;
+        if config.cookie.trim().is_empty() {
+            return Err(AppError::config("微博 Cookie 未配置"));
+        }
+
+        let res = upload_weibo_internal(ctx, file_path, &config.cookie).await?;
+        let url = format!("https://tvax1.sinaimg.cn/large/{}.jpg", res.pid);
+
+        Ok(UploadOutput::new(self.id(), res.pid, url)
+            .with_size(res.size.max(0) as u64)
+            .with_meta("width", res.width)
+            .with_meta("height", res.height))
+    }
+}
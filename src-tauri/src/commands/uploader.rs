@@ -0,0 +1,154 @@
+// src-tauri/src/commands/uploader.rs
+// 通用上传命令：按服务 ID 分发到注册表中的上传器
+
+use std::collections::BTreeMap;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+use crate::uploader::cancel::CancelRegistry;
+use crate::uploader::dedup::DedupCache;
+use crate::uploader::limiter::{RateLimiter, ServiceBudget};
+use crate::uploader::orchestrator::{
+    upload_to_services, MultiUploadOutput, MultiUploadRequest, DEFAULT_MAX_CONCURRENCY,
+};
+use crate::uploader::registry::{UploaderInfo, UploaderRegistry};
+use crate::uploader::retry::RetryPolicy;
+use crate::uploader::{run_upload, UploadContext, UploadOutput};
+use crate::HttpClient;
+
+/// 通用上传命令
+///
+/// - `service_id`: 图床标识（与前端 ServiceType 一致）
+/// - `config`: 对应图床的配置（前端 `config.services[serviceId]`）
+/// - `retry_attempts`: 瞬时错误时最多尝试次数，默认 `retry::DEFAULT_MAX_ATTEMPTS`
+/// - `force_upload`: 跳过去重缓存，相同内容也重新上传
+#[tauri::command]
+pub async fn upload(
+    app: AppHandle,
+    id: String,
+    service_id: String,
+    file_path: String,
+    config: serde_json::Value,
+    retry_attempts: Option<u32>,
+    force_upload: Option<bool>,
+    registry: State<'_, UploaderRegistry>,
+    http_client: State<'_, HttpClient>,
+) -> Result<UploadOutput, AppError> {
+    let uploader = registry.get(&service_id)?;
+    let ctx = UploadContext::new(app, id, http_client.0.clone())
+        .with_retry(retry_attempts.map_or_else(RetryPolicy::default, RetryPolicy::with_max_attempts))
+        .with_force_fresh(force_upload.unwrap_or(false));
+    run_upload(uploader.as_ref(), &ctx, &file_path, &config).await
+}
+
+/// 多图床并行上传命令
+///
+/// - `services`: 按优先级排列的图床列表，第一个成功的作为主力图床
+/// - `configs`: 以服务 ID 为键的配置表（前端 `config.services`）
+/// - `max_concurrency`: 同时进行的上传数上限，默认 `DEFAULT_MAX_CONCURRENCY`
+/// - `retry_attempts`: 单个图床瞬时错误时最多尝试次数，默认 `retry::DEFAULT_MAX_ATTEMPTS`
+/// - `force_upload`: 跳过去重缓存，相同内容也重新上传
+///
+/// 每个图床完成时发送 `upload://service-result`，全部结束后发送 `upload://multi-complete`，
+/// 窗口隐藏到托盘时整个流程依然可以完成
+#[tauri::command]
+pub async fn upload_multi(
+    app: AppHandle,
+    id: String,
+    file_path: String,
+    services: Vec<String>,
+    configs: serde_json::Value,
+    max_concurrency: Option<usize>,
+    retry_attempts: Option<u32>,
+    force_upload: Option<bool>,
+    registry: State<'_, UploaderRegistry>,
+    http_client: State<'_, HttpClient>,
+) -> Result<MultiUploadOutput, AppError> {
+    let request = MultiUploadRequest {
+        id,
+        file_path,
+        services,
+        configs,
+        max_concurrency: max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY),
+        retry: retry_attempts.map_or_else(RetryPolicy::default, RetryPolicy::with_max_attempts),
+        force_fresh: force_upload.unwrap_or(false),
+    };
+    upload_to_services(app, &registry, http_client.0.clone(), request).await
+}
+
+/// 列出所有已注册的图床及其能力
+#[tauri::command]
+pub fn list_uploaders(registry: State<'_, UploaderRegistry>) -> Vec<UploaderInfo> {
+    registry.list()
+}
+
+/// 获取各图床当前生效的限流预算（用户配置覆盖内置默认值）
+#[tauri::command]
+pub fn get_rate_limits(
+    registry: State<'_, UploaderRegistry>,
+    limiter: State<'_, RateLimiter>,
+) -> BTreeMap<&'static str, ServiceBudget> {
+    registry
+        .list()
+        .into_iter()
+        .map(|info| (info.id, limiter.budget(info.id)))
+        .collect()
+}
+
+/// 删除已上传的文件
+///
+/// `output` 为上传时返回的结果（需要其中的 fileKey / metadata 作为删除凭证）
+#[tauri::command]
+pub async fn delete_upload(
+    app: AppHandle,
+    output: UploadOutput,
+    config: serde_json::Value,
+    registry: State<'_, UploaderRegistry>,
+    http_client: State<'_, HttpClient>,
+) -> Result<(), AppError> {
+    let uploader = registry.get(&output.service_id)?;
+    if !uploader.capabilities().supports_delete {
+        return Err(AppError::validation(format!("{} 不支持删除操作", uploader.name())));
+    }
+
+    let ctx = UploadContext::new(app.clone(), format!("delete-{}", output.file_key), http_client.0.clone());
+    uploader.delete(&ctx, &output, &config).await?;
+
+    // 文件已删除，去重缓存中的链接不再可用
+    if let Some(cache) = app.try_state::<DedupCache>() {
+        if let Err(e) = cache.forget_url(&output.service_id, &output.url).await {
+            eprintln!("[Dedup] {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// 查询文件内容此前在各图床的链接（服务 ID → URL），未上传过时返回空表
+#[tauri::command]
+pub async fn find_uploaded_copies(
+    file_path: String,
+    cache: State<'_, DedupCache>,
+) -> Result<BTreeMap<String, String>, AppError> {
+    let hash = cache.file_hash(&file_path).await?;
+    cache.urls_for(&hash).await
+}
+
+/// 清空去重缓存，返回删除的记录数
+#[tauri::command]
+pub async fn clear_upload_cache(cache: State<'_, DedupCache>) -> Result<u64, AppError> {
+    let removed = cache.clear().await?;
+    println!("[Dedup] 已清空去重缓存: {} 条记录", removed);
+    Ok(removed)
+}
+
+/// 取消上传
+///
+/// `id` 为上传命令传入的任务 ID；多图床上传也可以用 `{id}:{serviceId}` 单独取消某个图床。
+/// 返回是否找到了正在进行的任务
+#[tauri::command]
+pub fn cancel_upload(id: String, cancel_registry: State<'_, CancelRegistry>) -> bool {
+    let found = cancel_registry.cancel(&id);
+    println!("[Uploader] 取消上传 {}: {}", id, if found { "已发送取消信号" } else { "任务不存在或已结束" });
+    found
+}
@@ -10,7 +10,7 @@
 // 5. 轮询图片状态直到处理完成
 // 6. URL 标准化为 https://picx.zhimg.com/v2-{hash}.webp
 
-use tauri::Window;
+use tauri::{Window, Manager};
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
 use std::time::Duration;
@@ -21,12 +21,16 @@ use base64::{Engine, engine::general_purpose::STANDARD};
 use regex::Regex;
 
 use crate::error::{AppError, IntoAppError};
-use super::utils::read_file_bytes;
+use crate::uploader::body::FileBody;
+use crate::uploader::processing::WithProcessing;
+use crate::uploader::progress::ProgressReporter;
+use crate::uploader::retry::{reject_transient, status_error};
+use crate::uploader::{parse_config, Uploader, UploaderCapabilities, UploadContext, UploadOutput};
 
 type HmacSha1 = Hmac<Sha1>;
 
-/// 最大重试次数
-const MAX_UPLOAD_RETRIES: u32 = 3;
+/// 支持的图片格式
+const ACCEPTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ZhihuUploadResult {
@@ -117,82 +121,65 @@ fn normalize_image_url(url: &str) -> String {
     }
 }
 
-/// 获取 MIME 类型
-fn get_mime_type(ext: &str) -> &'static str {
-    match ext {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        _ => "image/jpeg",
-    }
-}
-
 #[tauri::command]
 pub async fn upload_to_zhihu(
-    _window: Window,
-    _id: String,
+    window: Window,
+    id: String,
     file_path: String,
     zhihu_cookie: String,
-) -> Result<ZhihuUploadResult, AppError> {
-    let mut last_error: Option<AppError> = None;
-
-    for attempt in 0..=MAX_UPLOAD_RETRIES {
-        if attempt > 0 {
-            let delay = attempt * 2;  // 2, 4, 6 秒
-            println!("[Zhihu] 第 {} 次重试，等待 {} 秒...", attempt, delay);
-            tokio::time::sleep(Duration::from_secs(delay as u64)).await;
-        }
-
-        match upload_to_zhihu_inner(&file_path, &zhihu_cookie).await {
-            Ok(result) => return Ok(result),
-            Err(e) => {
-                // 只对"图片处理超时"错误进行重试
-                let error_str = format!("{}", e);
-                if error_str.contains("图片处理超时") && attempt < MAX_UPLOAD_RETRIES {
-                    println!("[Zhihu] 上传超时，准备重试...");
-                    last_error = Some(e);
-                    continue;
-                }
-                return Err(e);
-            }
-        }
-    }
+    force_upload: Option<bool>,
+) -> Result<WithProcessing<ZhihuUploadResult>, AppError> {
+    let ctx = UploadContext::new(window.app_handle().clone(), id, reqwest::Client::new())
+        .with_force_fresh(force_upload.unwrap_or(false));
+    let prepared = ctx.prepare_file("zhihu", &file_path).await?;
+    let file_path = prepared.path();
+    ctx.run_deduped("zhihu", file_path, || upload_zhihu_internal(&ctx, file_path, &zhihu_cookie))
+        .await
+        .map(|result| prepared.attach(result))
+}
 
-    Err(last_error.unwrap_or_else(|| AppError::upload("知乎", format!("上传失败，已重试 {} 次", MAX_UPLOAD_RETRIES))))
+/// 知乎上传（供命令和统一上传器共用）
+///
+/// 图片处理超时、网络错误等瞬时错误由调用方的重试策略（`UploadContext::run_limited`）重试
+pub(crate) async fn upload_zhihu_internal(
+    ctx: &UploadContext,
+    file_path: &str,
+    zhihu_cookie: &str,
+) -> Result<ZhihuUploadResult, AppError> {
+    let progress = ProgressReporter::new(ctx, 4);
+    upload_to_zhihu_inner(file_path, zhihu_cookie, &progress).await
 }
 
 /// 内部上传函数
 async fn upload_to_zhihu_inner(
     file_path: &str,
     zhihu_cookie: &str,
+    progress: &ProgressReporter,
 ) -> Result<ZhihuUploadResult, AppError> {
     println!("[Zhihu] 开始上传文件: {}", file_path);
 
     // 1. 读取文件
-    let (buffer, file_size) = read_file_bytes(file_path).await?;
+    progress.step(1, "读取文件...");
+    let file = FileBody::open(file_path).await?;
+    let file_size = file.size();
+    progress.set_total(file_size);
 
     // 2. 验证文件类型（只允许图片）
-    let file_name = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
-
-    let ext = file_name.split('.').last()
-        .ok_or_else(|| AppError::validation("无法获取文件扩展名"))?
-        .to_lowercase();
+    // 按文件内容识别格式，扩展名不可靠（如另存为 .jpg 的 WebP）
+    let ext = file.ext();
 
-    if !["jpg", "jpeg", "png", "gif", "webp"].contains(&ext.as_str()) {
+    if !ACCEPTED_FORMATS.contains(&ext.as_str()) {
         return Err(AppError::validation("只支持 JPG、PNG、GIF、WebP 格式的图片"));
     }
 
-    let content_type = get_mime_type(&ext);
+    let content_type = file.content_type();
 
     // 3. 计算图片 MD5
-    let image_hash = calculate_md5(&buffer);
+    let image_hash = hex::encode(file.digest::<Md5>().await?);
     println!("[Zhihu] 图片 MD5: {}", image_hash);
 
     // 4. 获取上传凭证
+    progress.step(2, "获取上传凭证...");
     let client = Client::builder()
         .timeout(Duration::from_secs(30))
         .build()
@@ -212,6 +199,7 @@ async fn upload_to_zhihu_inner(
         .send()
         .await
         .into_network_err_with("获取上传凭证失败")?;
+    let credentials_response = reject_transient("知乎", credentials_response).await?;
 
     let credentials_text = credentials_response.text().await
         .into_network_err_with("读取凭证响应失败")?;
@@ -239,6 +227,7 @@ async fn upload_to_zhihu_inner(
         println!("[Zhihu] 开始上传到 OSS: {}", object_key);
 
         // 5.1 上传到 OSS
+        progress.step(3, "正在上传...");
         let date = get_rfc2822_date();
         let signature = calculate_oss_signature(
             &upload_token.access_key,
@@ -258,15 +247,14 @@ async fn upload_to_zhihu_inner(
             .header("Authorization", &authorization)
             .header("x-oss-date", &date)
             .header("x-oss-security-token", &upload_token.access_token)
-            .body(buffer.clone())
+            .header("Content-Length", file_size)
+            .body(file.body(&progress))
             .send()
             .await
             .into_network_err_with("OSS 上传失败")?;
 
-        let oss_status = oss_response.status();
-        if !oss_status.is_success() {
-            let oss_error = oss_response.text().await.unwrap_or_default();
-            return Err(AppError::upload("知乎", format!("OSS 上传失败 ({}): {}", oss_status, oss_error)));
+        if !oss_response.status().is_success() {
+            return Err(status_error("知乎", "OSS 上传失败", oss_response).await);
         }
 
         println!("[Zhihu] OSS 上传成功");
@@ -293,6 +281,7 @@ async fn upload_to_zhihu_inner(
         }
 
         println!("[Zhihu] 开始轮询图片状态...");
+        progress.step(4, "等待图片处理...");
 
         // 5.3 轮询图片状态
         poll_image_status(&client, &zhihu_cookie, &image_id, 30).await?
@@ -348,7 +337,8 @@ async fn poll_image_status(
         }
     }
 
-    Err(AppError::upload("知乎", "图片处理超时"))
+    // 服务端处理较慢时重新上传通常可以成功，作为瞬时错误交给重试策略
+    Err(AppError::network("知乎图片处理超时"))
 }
 
 /// 测试知乎 Cookie 连接
@@ -402,3 +392,47 @@ pub async fn test_zhihu_connection(zhihu_cookie: String) -> Result<String, AppEr
         Err(AppError::upload("知乎", format!("测试失败 (HTTP {}): {}", status, response_text)))
     }
 }
+
+/// 知乎图床配置（对应前端 config.services.zhihu）
+#[derive(Deserialize)]
+struct ZhihuConfig {
+    cookie: String,
+}
+
+/// 知乎上传器
+pub struct ZhihuUploader;
+
+#[async_trait::async_trait]
+impl Uploader for ZhihuUploader {
+    fn id(&self) -> &'static str {
+        "zhihu"
+    }
+
+    fn name(&self) -> &'static str {
+        "知乎"
+    }
+
+    fn capabilities(&self) -> UploaderCapabilities {
+        UploaderCapabilities {
+            max_file_size: None,
+            max_gif_size: None,
+            accepted_formats: ACCEPTED_FORMATS,
+            supports_delete: false,
+        }
+    }
+
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError> {
+        let config: ZhihuConfig = parse_config(self.name(), config)?;
+        if config.cookie.trim().is_empty() {
+            return Err(AppError::config("知乎 Cookie 未配置"));
+        }
+
+        let res = upload_zhihu_internal(ctx, file_path, &config.cookie).await?;
+        Ok(UploadOutput::new(self.id(), res.url.clone(), res.url).with_size(res.size))
+    }
+}
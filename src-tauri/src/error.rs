@@ -2,6 +2,8 @@
 // 统一应用错误类型
 // v2.10: 扩展错误类型覆盖所有服务
 
+use std::time::Duration;
+
 use serde::Serialize;
 
 /// 应用统一错误类型
@@ -11,9 +13,14 @@ use serde::Serialize;
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", content = "data")]
 pub enum AppError {
-    /// 网络错误：连接失败、超时等
+    /// 网络错误：连接失败、超时、服务端临时故障（5xx / 429）等，可以重试
     #[serde(rename = "NETWORK")]
-    Network { message: String },
+    Network {
+        message: String,
+        /// 服务端通过 Retry-After 要求的等待时间
+        #[serde(skip)]
+        retry_after: Option<Duration>,
+    },
 
     /// 认证错误：Cookie 过期、Token 无效等
     #[serde(rename = "AUTH")]
@@ -54,6 +61,10 @@ pub enum AppError {
     /// R2/S3 存储错误
     #[serde(rename = "STORAGE")]
     Storage { message: String },
+
+    /// 上传被用户取消
+    #[serde(rename = "CANCELLED")]
+    Cancelled { message: String },
 }
 
 // ==================== From trait 实现 ====================
@@ -61,17 +72,11 @@ pub enum AppError {
 impl From<reqwest::Error> for AppError {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
-            AppError::Network {
-                message: "请求超时".to_string(),
-            }
+            AppError::network("请求超时")
         } else if err.is_connect() {
-            AppError::Network {
-                message: "连接失败".to_string(),
-            }
+            AppError::network("连接失败")
         } else {
-            AppError::Network {
-                message: err.to_string(),
-            }
+            AppError::network(err.to_string())
         }
     }
 }
@@ -94,15 +99,13 @@ impl From<serde_json::Error> for AppError {
 
 impl From<String> for AppError {
     fn from(message: String) -> Self {
-        AppError::Network { message }
+        AppError::network(message)
     }
 }
 
 impl From<&str> for AppError {
     fn from(message: &str) -> Self {
-        AppError::Network {
-            message: message.to_string(),
-        }
+        AppError::network(message)
     }
 }
 
@@ -111,7 +114,7 @@ impl From<&str> for AppError {
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Network { message } => write!(f, "网络错误: {}", message),
+            Self::Network { message, .. } => write!(f, "网络错误: {}", message),
             Self::Auth { message } => write!(f, "认证错误: {}", message),
             Self::FileIo { message } => write!(f, "文件错误: {}", message),
             Self::Upload {
@@ -123,6 +126,7 @@ impl std::fmt::Display for AppError {
             Self::Validation { message } => write!(f, "验证错误: {}", message),
             Self::WebDAV { message } => write!(f, "WebDAV 错误: {}", message),
             Self::Storage { message } => write!(f, "存储错误: {}", message),
+            Self::Cancelled { message } => write!(f, "已取消: {}", message),
         }
     }
 }
@@ -136,6 +140,15 @@ impl AppError {
     pub fn network(message: impl Into<String>) -> Self {
         AppError::Network {
             message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    /// 创建带 Retry-After 等待时间的网络错误（限流、服务暂不可用）
+    pub fn network_retry_after(message: impl Into<String>, retry_after: Option<Duration>) -> Self {
+        AppError::Network {
+            message: message.into(),
+            retry_after,
         }
     }
 
@@ -216,6 +229,48 @@ impl AppError {
             message: message.into(),
         }
     }
+
+    /// 创建取消错误
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        AppError::Cancelled {
+            message: message.into(),
+        }
+    }
+
+    /// 是否为用户取消（取消不应被重试或计入失败统计）
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, AppError::Cancelled { .. })
+    }
+
+    /// 错误分类，决定是否值得重试
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            AppError::Network { .. } => ErrorClass::Transient,
+            AppError::Auth { .. } => ErrorClass::Auth,
+            _ => ErrorClass::Permanent,
+        }
+    }
+
+    /// 服务端要求的重试等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AppError::Network { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+// ==================== 错误分类 ====================
+
+/// 错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// 瞬时错误（网络中断、超时、5xx、限流），稍后重试可能成功
+    Transient,
+    /// 认证错误（Cookie / Token 失效），需要用户重新配置，重试无意义
+    Auth,
+    /// 永久错误（格式不支持、文件过大、配置错误、用户取消等）
+    Permanent,
 }
 
 // ==================== Result 扩展 trait ====================
@@ -0,0 +1,160 @@
+// src-tauri/src/history.rs
+// 后端写入上传历史
+//
+// 历史记录由前端 HistoryDatabase.ts 管理（history.db 的 history_items 表）。
+// 监听文件夹等后台上传不经过前端，由这里按相同的表结构写入，
+// 写入后广播与前端相同的 `cache-event`（history-updated），已打开的历史页面会自动刷新。
+
+use std::path::Path;
+
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::image_meta::get_image_metadata;
+use crate::error::{AppError, IntoAppError};
+use crate::phash;
+use crate::queue::store::now_millis;
+use crate::thumbnail;
+use crate::uploader::orchestrator::{MultiUploadOutput, ServiceUploadResult};
+
+/// 与前端 HistoryDatabase.ts 的建表语句保持一致（前端尚未打开过数据库时由这里创建）
+const CREATE_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS history_items (
+    id TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    local_file_name TEXT NOT NULL,
+    local_file_name_lower TEXT NOT NULL,
+    file_path TEXT,
+    primary_service TEXT NOT NULL,
+    results TEXT NOT NULL,
+    generated_link TEXT NOT NULL,
+    link_check_status TEXT,
+    link_check_summary TEXT,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    aspect_ratio REAL NOT NULL,
+    file_size INTEGER NOT NULL,
+    format TEXT NOT NULL,
+    color_type TEXT NOT NULL,
+    has_alpha INTEGER NOT NULL
+)
+"#;
+
+/// 前端跨窗口缓存事件名（src/events/cacheEvents.ts）
+const CACHE_EVENT_NAME: &str = "cache-event";
+
+/// `cache-event` 事件载荷
+#[derive(Serialize, Clone)]
+struct CacheEvent<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    timestamp: i64,
+    data: HistoryEventData<'a>,
+}
+
+#[derive(Serialize, Clone)]
+struct HistoryEventData<'a> {
+    ids: [&'a str; 1],
+    source: &'static str,
+}
+
+/// 历史记录存储（作为 Tauri 全局状态注册，与上传队列共用 history.db）
+pub struct HistoryStore {
+    pool: SqlitePool,
+}
+
+impl HistoryStore {
+    pub async fn new(pool: SqlitePool) -> Result<Self, AppError> {
+        sqlx::query(CREATE_TABLE_SQL)
+            .execute(&pool)
+            .await
+            .into_storage_err_with("创建历史记录表失败")?;
+        Ok(Self { pool })
+    }
+
+    /// 写入一条多图床上传的历史记录（只保存成功的结果，与前端 `saveHistoryItem` 一致），返回记录 ID
+    ///
+    /// `source` 标记写入来源，随 history-updated 事件发给前端
+    pub async fn record_upload(
+        &self,
+        app: &AppHandle,
+        file_path: &str,
+        output: &MultiUploadOutput,
+        source: &'static str,
+    ) -> Result<String, AppError> {
+        let id = random_uuid();
+        let file_name = Path::new(file_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("未知文件")
+            .to_string();
+
+        let results: Vec<&ServiceUploadResult> = output
+            .results
+            .iter()
+            .filter(|r| r.status == "success")
+            .collect();
+        let urls: Vec<String> = results
+            .iter()
+            .filter_map(|r| r.result.as_ref().map(|o| o.url.clone()))
+            .collect();
+        let results = serde_json::to_string(&results).into_storage_err_with("序列化上传结果失败")?;
+
+        // 元数据读取失败时使用与前端相同的默认值
+        let metadata = get_image_metadata(file_path.to_string(), None).ok();
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO history_items (\
+                id, timestamp, local_file_name, local_file_name_lower, file_path, \
+                primary_service, results, generated_link, link_check_status, link_check_summary, \
+                width, height, aspect_ratio, file_size, format, color_type, has_alpha\
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, NULL, NULL, ?, ?, ?, ?, ?, 'unknown', 0)",
+        )
+        .bind(&id)
+        .bind(now_millis())
+        .bind(&file_name)
+        .bind(file_name.to_lowercase())
+        .bind(file_path)
+        .bind(&output.primary_service)
+        .bind(results)
+        .bind(&output.primary_url)
+        .bind(metadata.as_ref().map_or(0, |m| m.width as i64))
+        .bind(metadata.as_ref().map_or(0, |m| m.height as i64))
+        .bind(metadata.as_ref().map_or(1.0, |m| m.aspect_ratio))
+        .bind(metadata.as_ref().map_or(0, |m| m.file_size as i64))
+        .bind(metadata.as_ref().map_or("unknown", |m| m.format.as_str()))
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("写入历史记录失败")?;
+
+        println!("[History] 已保存: {} ({})", file_name, source);
+        thumbnail::generate_for_upload(app, file_path, urls);
+        phash::record_in_background(app, &id, file_path);
+        let _ = app.emit(
+            CACHE_EVENT_NAME,
+            CacheEvent {
+                kind: "history-updated",
+                timestamp: now_millis(),
+                data: HistoryEventData { ids: [&id], source },
+            },
+        );
+        Ok(id)
+    }
+}
+
+/// 生成 UUID v4 字符串（与前端 `crypto.randomUUID()` 格式相同）
+fn random_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
@@ -0,0 +1,144 @@
+// src-tauri/src/image_format.rs
+// 按文件内容（魔数）识别图片格式
+//
+// 文件扩展名并不可靠：浏览器另存的 WebP 常被命名为 .jpg，截图工具导出的 PNG 也可能被改名。
+// 上传时的 Content-Type、扩展名修正以及格式校验都以这里识别的结果为准，无法识别时才回退到扩展名。
+
+use std::io::Read;
+use std::path::Path;
+
+/// 识别格式需要读取的文件头长度
+pub const HEADER_LEN: usize = 32;
+
+/// 图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageType {
+    Jpeg,
+    Png,
+    Gif,
+    Webp,
+    Bmp,
+    Tiff,
+    Ico,
+    Avif,
+    Heic,
+}
+
+impl ImageType {
+    /// 按文件头识别格式
+    pub fn sniff(header: &[u8]) -> Option<Self> {
+        match header {
+            [0xFF, 0xD8, 0xFF, ..] => Some(Self::Jpeg),
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(Self::Png),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(Self::Gif),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(Self::Webp),
+            [b'B', b'M', ..] => Some(Self::Bmp),
+            [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => Some(Self::Tiff),
+            [0x00, 0x00, 0x01, 0x00, ..] => Some(Self::Ico),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => Self::sniff_ftyp(header),
+            _ => None,
+        }
+    }
+
+    /// ISO BMFF 容器（AVIF / HEIC）：主品牌与兼容品牌中任一匹配即可
+    fn sniff_ftyp(header: &[u8]) -> Option<Self> {
+        let box_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let end = box_len.min(header.len());
+        // 主品牌（8..12）之后是 4 字节的次版本号，再之后是兼容品牌列表
+        let brands = header
+            .get(8..12)
+            .into_iter()
+            .chain(header.get(16..end).into_iter().flat_map(|b| b.chunks_exact(4)));
+
+        let mut heic = false;
+        for brand in brands {
+            match brand {
+                b"avif" | b"avis" => return Some(Self::Avif),
+                b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" | b"mif1" | b"msf1" => heic = true,
+                _ => {}
+            }
+        }
+        heic.then_some(Self::Heic)
+    }
+
+    /// 读取文件头识别格式；文件无法读取或格式无法识别时返回 None
+    pub fn detect(path: impl AsRef<Path>) -> Option<Self> {
+        let file = std::fs::File::open(path).ok()?;
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        file.take(HEADER_LEN as u64).read_to_end(&mut header).ok()?;
+        Self::sniff(&header)
+    }
+
+    /// 按扩展名推断格式（不区分大小写）
+    pub fn from_ext(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "jpg" | "jpeg" | "jpe" | "jfif" => Some(Self::Jpeg),
+            "png" | "apng" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "webp" => Some(Self::Webp),
+            "bmp" => Some(Self::Bmp),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "ico" => Some(Self::Ico),
+            "avif" => Some(Self::Avif),
+            "heic" | "heif" => Some(Self::Heic),
+            _ => None,
+        }
+    }
+
+    /// 标准扩展名（小写）
+    pub fn ext(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+            Self::Bmp => "bmp",
+            Self::Tiff => "tiff",
+            Self::Ico => "ico",
+            Self::Avif => "avif",
+            Self::Heic => "heic",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+            Self::Bmp => "image/bmp",
+            Self::Tiff => "image/tiff",
+            Self::Ico => "image/x-icon",
+            Self::Avif => "image/avif",
+            Self::Heic => "image/heic",
+        }
+    }
+
+    /// 扩展名是否与该格式相符（如 .jpeg 与 JPEG 相符，不需要修正）
+    pub fn matches_ext(self, ext: &str) -> bool {
+        Self::from_ext(ext) == Some(self)
+    }
+}
+
+/// 扩展名与实际格式不符时返回修正后的文件名，相符时原样返回
+pub fn corrected_file_name(file_name: &str, image_type: ImageType) -> String {
+    let (stem, ext) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+    if image_type.matches_ext(ext) {
+        file_name.to_string()
+    } else {
+        format!("{}.{}", stem, image_type.ext())
+    }
+}
+
+/// 文件格式对应的扩展名（小写）：优先按内容识别，无法识别时使用文件扩展名
+pub fn detect_ext(path: impl AsRef<Path>) -> String {
+    let path = path.as_ref();
+    match ImageType::detect(path) {
+        Some(image_type) => image_type.ext().to_string(),
+        None => path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,36 @@
+// src-tauri/src/link_format.rs
+// 链接格式（与前端历史记录、云存储页面复制链接的格式一致）
+
+use serde::Deserialize;
+
+/// 复制链接的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkFormat {
+    #[default]
+    Url,
+    Markdown,
+    Html,
+    BBCode,
+}
+
+impl LinkFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "url" => Some(LinkFormat::Url),
+            "markdown" | "md" => Some(LinkFormat::Markdown),
+            "html" => Some(LinkFormat::Html),
+            "bbcode" => Some(LinkFormat::BBCode),
+            _ => None,
+        }
+    }
+
+    pub fn format(&self, url: &str, file_name: &str) -> String {
+        match self {
+            LinkFormat::Url => url.to_string(),
+            LinkFormat::Markdown => format!("![{}]({})", file_name, url),
+            LinkFormat::Html => format!("<img src=\"{}\" alt=\"{}\" />", url, file_name),
+            LinkFormat::BBCode => format!("[img]{}[/img]", url),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+// src-tauri/src/phash.rs
+// 感知哈希（dHash）近似重复检测
+//
+// 内容去重（uploader/dedup.rs）只能识别完全相同的文件，同一张截图缩放、转格式或重新保存后就无法命中。
+// 这里为每次上传计算 64 位差值哈希（dHash）：缩小到 9×8 灰度图，比较每行相邻像素的明暗。
+// 缩放、重新压缩和轻微调色基本不改变哈希，两张图片哈希的汉明距离越小越相似。
+//
+// 哈希保存在 `history_phash` 表（与历史记录共用 history.db，按历史记录 ID 关联，
+// 不改动前端维护的 history_items 表结构）。无法计算的图片（本地文件已删除、格式不支持）记为 NULL，不再重复尝试。
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use image::DynamicImage;
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use tauri::{AppHandle, Manager};
+
+use crate::error::{AppError, IntoAppError};
+use crate::queue::store::now_millis;
+use crate::uploader::processing;
+
+/// 默认的相似阈值（64 位哈希中不同的位数）
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+const CREATE_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS history_phash (
+    history_id TEXT PRIMARY KEY,
+    phash TEXT,
+    created_at INTEGER NOT NULL
+)
+"#;
+
+/// 计算图片的 dHash（按 EXIF 方向校正后计算，旋转保存的同一张照片哈希相同）
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.thumbnail_exact(9, 8).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// 读取并解码本地图片，计算 dHash（耗时操作，需在阻塞线程中调用）
+pub fn compute(file_path: &str) -> Result<u64, AppError> {
+    let path = Path::new(file_path);
+    let image = image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?
+        .decode()
+        .map_err(|e| AppError::validation(format!("图片解码失败: {}", e)))?;
+    let image = processing::apply_orientation(image, processing::read_orientation(path));
+    Ok(dhash(&image))
+}
+
+/// 在阻塞线程中计算 dHash
+pub async fn compute_async(file_path: &str) -> Result<u64, AppError> {
+    let path = file_path.to_string();
+    tokio::task::spawn_blocking(move || compute(&path))
+        .await
+        .map_err(|e| AppError::external(format!("计算感知哈希失败: {}", e)))?
+}
+
+/// 两个哈希的汉明距离
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+fn to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+fn parse_hex(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex, 16).ok()
+}
+
+/// 与给定图片相似的历史记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarItem {
+    pub history_id: String,
+    pub file_name: String,
+    pub file_path: Option<String>,
+    pub primary_service: String,
+    /// 主力图床链接
+    pub url: String,
+    pub timestamp: i64,
+    /// 十六进制的 dHash
+    pub phash: String,
+    /// 汉明距离（0 表示哈希完全相同）
+    pub distance: u32,
+}
+
+/// 一组相互近似的历史记录（按上传时间排序，`distance` 为与最早一条的距离）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCluster {
+    pub items: Vec<SimilarItem>,
+}
+
+/// 已计算哈希的历史记录
+struct HashedItem {
+    hash: u64,
+    item: SimilarItem,
+}
+
+/// 感知哈希存储（作为 Tauri 全局状态注册，与历史记录共用 history.db）
+pub struct PerceptualHashStore {
+    pool: SqlitePool,
+    /// 是否有补算任务在后台运行（同一时间只跑一个）
+    backfilling: AtomicBool,
+}
+
+impl PerceptualHashStore {
+    pub async fn new(pool: SqlitePool) -> Result<Self, AppError> {
+        sqlx::query(CREATE_TABLE_SQL)
+            .execute(&pool)
+            .await
+            .into_storage_err_with("创建感知哈希表失败")?;
+        Ok(Self { pool, backfilling: AtomicBool::new(false) })
+    }
+
+    /// 记录历史记录的哈希（None 表示无法计算）
+    pub async fn record(&self, history_id: &str, hash: Option<u64>) -> Result<(), AppError> {
+        sqlx::query("INSERT OR REPLACE INTO history_phash (history_id, phash, created_at) VALUES (?, ?, ?)")
+            .bind(history_id)
+            .bind(hash.map(to_hex))
+            .bind(now_millis())
+            .execute(&self.pool)
+            .await
+            .into_storage_err_with("写入感知哈希失败")?;
+        Ok(())
+    }
+
+    /// 计算本地文件的哈希并记录
+    pub async fn record_file(&self, history_id: &str, file_path: &str) -> Result<Option<u64>, AppError> {
+        let hash = match compute_async(file_path).await {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                eprintln!("[PHash] 无法计算 {}: {}", file_path, e);
+                None
+            }
+        };
+        self.record(history_id, hash).await?;
+        Ok(hash)
+    }
+
+    /// 为还没有哈希的历史记录（功能加入前上传的、后台计算未完成的）补算，返回成功计算的数量
+    pub async fn backfill(&self) -> Result<usize, AppError> {
+        let rows = sqlx::query(
+            "SELECT h.id, h.file_path FROM history_items h \
+             LEFT JOIN history_phash p ON p.history_id = h.id \
+             WHERE p.history_id IS NULL",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .into_storage_err_with("读取历史记录失败")?;
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        println!("[PHash] 补算 {} 条历史记录的感知哈希", rows.len());
+        let mut computed = 0;
+        for row in &rows {
+            let id: String = row.get("id");
+            let file_path: Option<String> = row.get("file_path");
+            let hash = match file_path.filter(|p| Path::new(p).is_file()) {
+                Some(path) => self.record_file(&id, &path).await?,
+                None => {
+                    self.record(&id, None).await?;
+                    None
+                }
+            };
+            computed += usize::from(hash.is_some());
+        }
+        Ok(computed)
+    }
+
+    /// 删除已不在历史记录中的哈希
+    async fn prune(&self) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM history_phash WHERE history_id NOT IN (SELECT id FROM history_items)")
+            .execute(&self.pool)
+            .await
+            .into_storage_err_with("清理感知哈希失败")?;
+        Ok(result.rows_affected())
+    }
+
+    /// 所有已计算哈希的历史记录
+    async fn hashed_items(&self) -> Result<Vec<HashedItem>, AppError> {
+        let rows = sqlx::query(
+            "SELECT h.id, h.local_file_name, h.file_path, h.primary_service, h.generated_link, h.timestamp, p.phash \
+             FROM history_items h JOIN history_phash p ON p.history_id = h.id \
+             WHERE p.phash IS NOT NULL \
+             ORDER BY h.timestamp",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .into_storage_err_with("读取感知哈希失败")?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let phash: String = row.get("phash");
+                Some(HashedItem {
+                    hash: parse_hex(&phash)?,
+                    item: SimilarItem {
+                        history_id: row.get("id"),
+                        file_name: row.get("local_file_name"),
+                        file_path: row.get("file_path"),
+                        primary_service: row.get("primary_service"),
+                        url: row.get("generated_link"),
+                        timestamp: row.get("timestamp"),
+                        phash,
+                        distance: 0,
+                    },
+                })
+            })
+            .collect())
+    }
+
+    /// 查找与给定哈希相似的历史记录，按距离从近到远排序（距离相同时较新的在前）
+    pub async fn find_similar(&self, hash: u64, max_distance: u32) -> Result<Vec<SimilarItem>, AppError> {
+        let mut similar: Vec<SimilarItem> = self
+            .hashed_items()
+            .await?
+            .into_iter()
+            .filter_map(|HashedItem { hash: other, mut item }| {
+                item.distance = distance(hash, other);
+                (item.distance <= max_distance).then_some(item)
+            })
+            .collect();
+        similar.sort_by(|a, b| a.distance.cmp(&b.distance).then(b.timestamp.cmp(&a.timestamp)));
+        Ok(similar)
+    }
+
+    /// 把整个历史记录按相似度分组，只返回包含两条以上记录的分组（按分组大小从大到小排序）
+    ///
+    /// 距离不超过阈值的两条记录归入同一组（传递合并：A 与 B 相似、B 与 C 相似时三者同组）
+    pub async fn clusters(&self, max_distance: u32) -> Result<Vec<DuplicateCluster>, AppError> {
+        let pruned = self.prune().await?;
+        if pruned > 0 {
+            println!("[PHash] 已清理 {} 条已删除历史记录的哈希", pruned);
+        }
+        let items = self.hashed_items().await?;
+
+        tokio::task::spawn_blocking(move || cluster_items(items, max_distance))
+            .await
+            .map_err(|e| AppError::external(format!("近似重复分组失败: {}", e)))
+    }
+}
+
+/// 并查集分组（两两比较，数千条记录时耗时在毫秒级）
+fn cluster_items(items: Vec<HashedItem>, max_distance: u32) -> Vec<DuplicateCluster> {
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut parent: Vec<usize> = (0..items.len()).collect();
+    for i in 0..items.len() {
+        for j in i + 1..items.len() {
+            if distance(items[i].hash, items[j].hash) <= max_distance {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                if a != b {
+                    // 以较早的记录为根，分组顺序与上传时间一致
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..items.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+
+    let mut clusters: Vec<DuplicateCluster> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            // items 按上传时间排序，第一个即最早的记录
+            let first = items[members[0]].hash;
+            let cluster = members
+                .iter()
+                .map(|&i| SimilarItem {
+                    distance: distance(first, items[i].hash),
+                    ..items[i].item.clone()
+                })
+                .collect();
+            DuplicateCluster { items: cluster }
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.items
+            .len()
+            .cmp(&a.items.len())
+            .then(b.items[0].timestamp.cmp(&a.items[0].timestamp))
+    });
+    clusters
+}
+
+/// 上传完成后在后台计算并记录哈希（失败只记录日志）
+pub fn record_in_background(app: &AppHandle, history_id: &str, file_path: &str) {
+    let app = app.clone();
+    let history_id = history_id.to_string();
+    let file_path = file_path.to_string();
+    tauri::async_runtime::spawn(async move {
+        let Some(store) = app.try_state::<PerceptualHashStore>() else {
+            return;
+        };
+        if let Err(e) = store.record_file(&history_id, &file_path).await {
+            eprintln!("[PHash] {}", e);
+        }
+    });
+}
+
+/// 在后台为缺少哈希的历史记录补算（解码大量图片耗时较长，不阻塞调用方）
+///
+/// 已有补算任务在运行时直接返回
+pub fn backfill_in_background(app: &AppHandle) {
+    let Some(store) = app.try_state::<PerceptualHashStore>() else {
+        return;
+    };
+    if store.backfilling.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let store = app.state::<PerceptualHashStore>();
+        match store.backfill().await {
+            Ok(0) => {}
+            Ok(computed) => println!("[PHash] 已补算 {} 条感知哈希", computed),
+            Err(e) => eprintln!("[PHash] 补算失败: {}", e),
+        }
+        store.backfilling.store(false, Ordering::Release);
+    });
+}
@@ -0,0 +1,475 @@
+// src-tauri/src/picgo_server.rs
+// PicGo 兼容的本地上传服务
+//
+// Typora、Obsidian 插件、VS Code 扩展等通过 `POST http://127.0.0.1:36677/upload` 调用 PicGo 上传图片，
+// 开启后 PicNexus 可以直接替代 PicGo：按用户启用的图床（`enabledServices`）上传，返回主力图床的链接。
+//
+// 请求格式（与 PicGo Server 一致）：
+// - JSON `{"list": ["/path/to/a.png", "https://..."]}`：上传本地文件或网络图片
+// - multipart/form-data：上传请求中携带的文件
+// - 空请求体或空列表：上传剪贴板中的图片
+// 响应：`{"success": true, "result": [url, ...]}`，失败时 `{"success": false, "message": "..."}`
+//
+// 安全：只监听 127.0.0.1；拒绝来自网页的请求（带 http/https Origin）和非本机 Host（防 DNS 重绑定）；
+// 配置了 token 时要求 `?key=<token>` 或 `Authorization: Bearer <token>`。
+// 配置项为 `config.picgoServer`，默认关闭，保存配置后立即生效。
+
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use hyper::body::HttpBody;
+use hyper::header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, HOST, ORIGIN};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use crate::commands::clipboard::read_clipboard_image;
+use crate::commands::link_checker::download_to_temp;
+use crate::error::AppError;
+use crate::image_format::{self, ImageType};
+use crate::queue::store::now_millis;
+use crate::secure_config;
+use crate::uploader::orchestrator::{upload_to_services, MultiUploadRequest, DEFAULT_MAX_CONCURRENCY};
+use crate::uploader::registry::UploaderRegistry;
+use crate::uploader::retry::RetryPolicy;
+use crate::HttpClient;
+
+/// PicGo Server 的默认端口
+pub const DEFAULT_PORT: u16 = 36677;
+
+/// 请求体大小上限（100MB）
+const MAX_BODY_SIZE: usize = 100 * 1024 * 1024;
+
+/// 本地上传服务配置（`config.picgoServer`）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PicGoServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// 访问令牌，为空时不校验
+    pub token: Option<String>,
+}
+
+impl Default for PicGoServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: None,
+        }
+    }
+}
+
+/// 正在运行的服务
+struct RunningServer {
+    port: u16,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// 本地上传服务（作为 Tauri 全局状态注册）
+#[derive(Default)]
+pub struct PicGoServer {
+    running: Mutex<Option<RunningServer>>,
+    /// 访问令牌，与请求处理共享，修改令牌不需要重启服务
+    token: Arc<Mutex<Option<String>>>,
+}
+
+impl PicGoServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 应用用户配置中的 `picgoServer`，格式错误时保持当前状态
+    pub fn apply_config(&self, app: &AppHandle, user_config: &serde_json::Value) {
+        match secure_config::section::<PicGoServerSettings>(user_config, "picgoServer") {
+            Ok(settings) => self.apply(app, settings),
+            Err(e) => eprintln!("[PicGoServer] picgoServer 配置格式错误: {}", e),
+        }
+    }
+
+    /// 按配置启动、重启或停止服务
+    fn apply(&self, app: &AppHandle, settings: PicGoServerSettings) {
+        *self.token.lock().unwrap_or_else(|p| p.into_inner()) =
+            settings.token.filter(|token| !token.is_empty());
+
+        let mut running = self.running.lock().unwrap_or_else(|p| p.into_inner());
+        if settings.enabled && running.as_ref().is_some_and(|r| r.port == settings.port) {
+            return;
+        }
+
+        if let Some(server) = running.take() {
+            let _ = server.shutdown.send(());
+            println!("[PicGoServer] 正在停止 127.0.0.1:{}", server.port);
+        }
+
+        if !settings.enabled {
+            return;
+        }
+
+        match start(app.clone(), settings.port, self.token.clone()) {
+            Ok(shutdown) => {
+                println!("[PicGoServer] 已启动: http://127.0.0.1:{}/upload", settings.port);
+                *running = Some(RunningServer {
+                    port: settings.port,
+                    shutdown,
+                });
+            }
+            // 端口被占用（如 PicGo 本身正在运行）时只记录日志，下次保存配置时重试
+            Err(e) => eprintln!("[PicGoServer] 启动失败: {}", e),
+        }
+    }
+}
+
+/// 绑定端口并在后台运行服务，返回关闭信号
+fn start(
+    app: AppHandle,
+    port: u16,
+    token: Arc<Mutex<Option<String>>>,
+) -> Result<oneshot::Sender<()>, AppError> {
+    // 在当前线程同步绑定，端口冲突可以立即报告
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+        .map_err(|e| AppError::external(format!("无法监听 127.0.0.1:{}: {}", port, e)))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| AppError::external(format!("无法监听 127.0.0.1:{}: {}", port, e)))?;
+
+    let (shutdown, signal) = oneshot::channel::<()>();
+
+    tauri::async_runtime::spawn(async move {
+        let builder = match Server::from_tcp(listener) {
+            Ok(builder) => builder,
+            Err(e) => {
+                eprintln!("[PicGoServer] 启动失败: {}", e);
+                return;
+            }
+        };
+
+        let make_service = make_service_fn(move |_| {
+            let app = app.clone();
+            let token = token.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| handle(app.clone(), token.clone(), req)))
+            }
+        });
+
+        let server = builder.serve(make_service).with_graceful_shutdown(async {
+            let _ = signal.await;
+        });
+        if let Err(e) = server.await {
+            eprintln!("[PicGoServer] 服务异常退出: {}", e);
+        }
+        println!("[PicGoServer] 已停止 127.0.0.1:{}", port);
+    });
+
+    Ok(shutdown)
+}
+
+/// 请求失败：HTTP 状态码和错误
+type Rejection = (StatusCode, AppError);
+
+async fn handle(
+    app: AppHandle,
+    token: Arc<Mutex<Option<String>>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let token = token.lock().unwrap_or_else(|p| p.into_inner()).clone();
+
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+
+    let result = match authorize(&req, token.as_deref()) {
+        Err(rejection) => Err(rejection),
+        Ok(()) => match (&method, path.as_str()) {
+            (&Method::POST, "/upload") => upload(&app, req).await,
+            (&Method::POST, "/heartbeat") => Ok(serde_json::json!("alive")),
+            _ => Err((StatusCode::NOT_FOUND, AppError::validation("未知的接口"))),
+        },
+    };
+
+    let (status, body) = match result {
+        Ok(result) => (StatusCode::OK, serde_json::json!({ "success": true, "result": result })),
+        Err((status, e)) => {
+            eprintln!("[PicGoServer] 请求失败: {}", e);
+            (status, serde_json::json!({ "success": false, "message": e.to_string() }))
+        }
+    };
+
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json; charset=utf-8"));
+    Ok(response)
+}
+
+/// 校验请求来源和访问令牌
+fn authorize(req: &Request<Body>, token: Option<&str>) -> Result<(), Rejection> {
+    let header = |name: HeaderName| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    // 浏览器中的网页也能向 127.0.0.1 发请求，编辑器插件和命令行工具不会带网页的 Origin
+    if let Some(origin) = header(ORIGIN) {
+        if origin.starts_with("http://") || origin.starts_with("https://") {
+            return Err((StatusCode::FORBIDDEN, AppError::auth("不接受来自网页的上传请求")));
+        }
+    }
+
+    // 防 DNS 重绑定：Host 必须是本机地址
+    if let Some(host) = header(HOST) {
+        let hostname = match host.rsplit_once(':') {
+            Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+            _ => host,
+        };
+        if !matches!(hostname, "127.0.0.1" | "localhost" | "[::1]") {
+            return Err((StatusCode::FORBIDDEN, AppError::auth(format!("不接受的 Host: {}", host))));
+        }
+    }
+
+    let Some(token) = token else {
+        return Ok(());
+    };
+
+    let from_query = req.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("key="))
+            .map(|key| urlencoding::decode(key).map(|k| k.into_owned()).unwrap_or_default())
+    });
+    let from_header = header(AUTHORIZATION)
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|key| key.trim().to_string());
+
+    if from_query.as_deref() == Some(token) || from_header.as_deref() == Some(token) {
+        Ok(())
+    } else {
+        Err((StatusCode::UNAUTHORIZED, AppError::auth("访问令牌无效")))
+    }
+}
+
+/// 请求中上传的临时文件，处理结束后删除
+#[derive(Default)]
+struct TempFiles(Vec<PathBuf>);
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// `POST /upload`：准备文件并逐个上传到启用的图床，返回主力图床的链接
+async fn upload(app: &AppHandle, req: Request<Body>) -> Result<serde_json::Value, Rejection> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let body = read_body(req.into_body()).await?;
+
+    let http_client = app.state::<HttpClient>().0.clone();
+    let mut temp_files = TempFiles::default();
+    let files = prepare_files(&content_type, &body, &http_client, &mut temp_files)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let failed = |e: AppError| (StatusCode::OK, e);
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| failed(AppError::config(format!("无法获取应用数据目录: {}", e))))?;
+    let user_config = secure_config::load_user_config(&app_data_dir).map_err(failed)?;
+    let services = secure_config::enabled_services(&user_config);
+    let configs = secure_config::service_configs(&user_config);
+    let weibo_prefix = secure_config::weibo_link_prefix(&user_config);
+
+    let registry = app.state::<UploaderRegistry>();
+    let mut urls = Vec::with_capacity(files.len());
+
+    // 与 PicGo 一致：任一文件失败即整体失败
+    for (index, file_path) in files.into_iter().enumerate() {
+        println!("[PicGoServer] 上传: {}", file_path);
+        let request = MultiUploadRequest {
+            id: format!("picgo-{}-{}", now_millis(), index),
+            file_path,
+            services: services.clone(),
+            configs: configs.clone(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            retry: RetryPolicy::default(),
+            force_fresh: false,
+        };
+        let output = upload_to_services(app.clone(), &registry, http_client.clone(), request)
+            .await
+            .map_err(failed)?;
+        urls.extend(output.links(false, weibo_prefix.as_deref()));
+    }
+
+    Ok(serde_json::json!(urls))
+}
+
+async fn read_body(mut body: Body) -> Result<Vec<u8>, Rejection> {
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| {
+            (StatusCode::BAD_REQUEST, AppError::network(format!("读取请求失败: {}", e)))
+        })?;
+        if data.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                AppError::validation(format!("请求体超过 {} MB", MAX_BODY_SIZE / 1024 / 1024)),
+            ));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+#[derive(Deserialize, Default)]
+struct UploadList {
+    #[serde(default)]
+    list: Vec<String>,
+}
+
+/// 把请求内容转换为待上传的本地文件路径
+async fn prepare_files(
+    content_type: &str,
+    body: &[u8],
+    http_client: &reqwest::Client,
+    temp_files: &mut TempFiles,
+) -> Result<Vec<String>, AppError> {
+    if content_type.starts_with("multipart/form-data") {
+        let boundary = content_type
+            .split(';')
+            .find_map(|param| param.trim().strip_prefix("boundary="))
+            .map(|b| b.trim_matches('"'))
+            .ok_or_else(|| AppError::validation("multipart 请求缺少 boundary"))?;
+
+        let parts = parse_multipart(body, boundary);
+        if parts.is_empty() {
+            return Err(AppError::validation("请求中没有文件"));
+        }
+
+        let mut paths = Vec::with_capacity(parts.len());
+        for (file_name, content) in parts {
+            // 按内容识别格式并修正扩展名，上传时按修正后的扩展名校验
+            let image_type = ImageType::sniff(content)
+                .ok_or_else(|| AppError::validation(format!("不是图片文件: {}", file_name)))?;
+            let file_name = image_format::corrected_file_name(&file_name, image_type);
+            let path = std::env::temp_dir().join(format!(
+                "picnexus_picgo_{}_{:08x}_{}",
+                now_millis(),
+                rand::random::<u32>(),
+                file_name
+            ));
+            tokio::fs::write(&path, content)
+                .await
+                .map_err(|e| AppError::file_io(format!("写入临时文件失败: {}", e)))?;
+            paths.push(path.to_string_lossy().into_owned());
+            temp_files.0.push(path);
+        }
+        return Ok(paths);
+    }
+
+    let list = if body.iter().all(u8::is_ascii_whitespace) {
+        Vec::new()
+    } else {
+        serde_json::from_slice::<UploadList>(body)
+            .map_err(|e| AppError::validation(format!("请求格式错误: {}", e)))?
+            .list
+    };
+
+    // 空列表：上传剪贴板中的图片
+    if list.is_empty() {
+        let path = tokio::task::spawn_blocking(read_clipboard_image)
+            .await
+            .map_err(|e| AppError::external(format!("读取剪贴板失败: {}", e)))??;
+        temp_files.0.push(PathBuf::from(&path));
+        return Ok(vec![path]);
+    }
+
+    let mut paths = Vec::with_capacity(list.len());
+    for item in list {
+        if item.starts_with("http://") || item.starts_with("https://") {
+            let path = download_to_temp(http_client, &item).await?;
+            temp_files.0.push(PathBuf::from(&path));
+            paths.push(path);
+        } else {
+            paths.push(check_local_image(&item)?);
+        }
+    }
+    Ok(paths)
+}
+
+/// 只允许上传本地图片文件，避免任意文件被发送到图床
+fn check_local_image(path: &str) -> Result<String, AppError> {
+    let file_path = std::path::Path::new(path);
+    if !file_path.is_absolute() || !file_path.is_file() {
+        return Err(AppError::file_io(format!("文件不存在: {}", path)));
+    }
+    // 按文件内容判断，改了扩展名的其他文件也会被拒绝
+    if ImageType::detect(file_path).is_none() {
+        return Err(AppError::validation(format!("不是图片文件: {}", path)));
+    }
+    Ok(path.to_string())
+}
+
+/// 解析 multipart/form-data 请求体，返回各文件字段的文件名和内容
+fn parse_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<(String, &'a [u8])> {
+    let open = format!("--{}", boundary).into_bytes();
+    let delimiter = format!("\r\n--{}", boundary).into_bytes();
+    let mut files = Vec::new();
+
+    let Some(first) = find(body, &open, 0) else {
+        return files;
+    };
+    let mut cursor = first + open.len();
+
+    // 分隔符后紧跟 `--` 表示结束
+    while !body[cursor..].starts_with(b"--") {
+        let Some(end) = find(body, &delimiter, cursor) else {
+            break;
+        };
+        let part = &body[cursor..end];
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+
+        if let Some(header_end) = find(part, b"\r\n\r\n", 0) {
+            let headers = String::from_utf8_lossy(&part[..header_end]);
+            if let Some(file_name) = disposition_file_name(&headers) {
+                files.push((file_name, &part[header_end + 4..]));
+            }
+        }
+        cursor = end + delimiter.len();
+    }
+
+    files
+}
+
+/// 从 Content-Disposition 中取出文件名（只保留最后一段，防止路径穿越）
+fn disposition_file_name(headers: &str) -> Option<String> {
+    let disposition = headers
+        .lines()
+        .find(|line| line.to_ascii_lowercase().starts_with("content-disposition:"))?;
+    let value = disposition
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("filename="))?
+        .trim_matches('"');
+
+    let name = value.rsplit(['/', '\\']).next().unwrap_or_default();
+    if name.is_empty() || name == "." || name == ".." {
+        Some("image.png".to_string())
+    } else {
+        Some(name.to_string())
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| index + from)
+}
@@ -0,0 +1,291 @@
+// src-tauri/src/queue/mod.rs
+// 持久化上传队列
+//
+// 前端 uploadQueue.ts 的队列只存在于内存中，应用退出或崩溃后未完成的上传会丢失。
+// 这里把任务写入 SQLite，由后台 worker 逐个执行：
+// - 启动时恢复上次未完成的任务
+// - 瞬时错误（网络、限流）按指数退避重试，超过最大次数标记为 failed；认证、配置等错误直接标记为 failed
+// - 支持暂停 / 恢复 / 删除
+//
+// 图床配置在每次尝试时从 `.settings.dat` 读取，队列中不保存任何凭证。
+
+pub mod store;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::error::{AppError, ErrorClass};
+use crate::secure_config;
+use crate::uploader::cancel::CancelRegistry;
+use crate::uploader::orchestrator::{upload_to_services, MultiUploadRequest, DEFAULT_MAX_CONCURRENCY};
+use crate::uploader::registry::UploaderRegistry;
+use crate::uploader::retry::RetryPolicy;
+use crate::HttpClient;
+
+pub use store::{JobStatus, QueueStore, UploadJob};
+use store::now_millis;
+
+/// 默认最大尝试次数
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// 首次重试的等待时间
+const BASE_RETRY_DELAY_MS: i64 = 5_000;
+
+/// 重试等待时间上限
+const MAX_RETRY_DELAY_MS: i64 = 10 * 60 * 1000;
+
+/// 没有到期任务时的最长轮询间隔
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 上传队列（作为 Tauri 全局状态注册）
+pub struct UploadQueue {
+    store: QueueStore,
+    /// 有新任务或任务状态变化时唤醒 worker
+    notify: Arc<Notify>,
+}
+
+/// `queue://updated` 事件载荷
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct QueueUpdatedEvent<'a> {
+    id: &'a str,
+    /// 任务被删除时为 None
+    job: Option<&'a UploadJob>,
+}
+
+impl UploadQueue {
+    pub fn new(store: QueueStore) -> Self {
+        Self {
+            store,
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// 添加任务
+    pub async fn enqueue(
+        &self,
+        app: &AppHandle,
+        file_path: String,
+        services: Vec<String>,
+        max_attempts: Option<u32>,
+    ) -> Result<UploadJob, AppError> {
+        if services.is_empty() {
+            return Err(AppError::validation("没有启用任何图床服务，请在上传界面选择至少一个图床"));
+        }
+        if !std::path::Path::new(&file_path).is_file() {
+            return Err(AppError::file_io(format!("文件不存在: {}", file_path)));
+        }
+
+        let now = now_millis();
+        let job = UploadJob {
+            id: format!("job-{}-{:08x}", now, rand::random::<u32>()),
+            file_path,
+            services,
+            status: JobStatus::Pending,
+            attempts: 0,
+            max_attempts: max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+            last_error: None,
+            next_attempt_at: now,
+            result: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.store.insert(&job).await?;
+        println!("[UploadQueue] 新任务 {}: {}", job.id, job.file_path);
+
+        emit_updated(app, &job.id, Some(&job));
+        self.notify.notify_one();
+        Ok(job)
+    }
+
+    pub async fn list(&self) -> Result<Vec<UploadJob>, AppError> {
+        self.store.list().await
+    }
+
+    /// 暂停任务，正在上传的任务会被取消
+    pub async fn pause(&self, app: &AppHandle, id: &str) -> Result<UploadJob, AppError> {
+        if !self.store.pause(id).await? {
+            return Err(self.not_in_state_error(id, "只有等待中或上传中的任务可以暂停").await);
+        }
+        if let Some(registry) = app.try_state::<CancelRegistry>() {
+            registry.cancel(id);
+        }
+        println!("[UploadQueue] 已暂停任务 {}", id);
+        self.emit_current(app, id).await
+    }
+
+    /// 恢复已暂停或已失败的任务
+    pub async fn resume(&self, app: &AppHandle, id: &str) -> Result<UploadJob, AppError> {
+        if !self.store.resume(id).await? {
+            return Err(self.not_in_state_error(id, "只有已暂停或已失败的任务可以恢复").await);
+        }
+        println!("[UploadQueue] 已恢复任务 {}", id);
+        self.notify.notify_one();
+        self.emit_current(app, id).await
+    }
+
+    /// 删除任务，正在上传的任务会被取消
+    pub async fn remove(&self, app: &AppHandle, id: &str) -> Result<(), AppError> {
+        if !self.store.remove(id).await? {
+            return Err(AppError::validation(format!("队列任务不存在: {}", id)));
+        }
+        if let Some(registry) = app.try_state::<CancelRegistry>() {
+            registry.cancel(id);
+        }
+        println!("[UploadQueue] 已删除任务 {}", id);
+        emit_updated(app, id, None);
+        Ok(())
+    }
+
+    async fn not_in_state_error(&self, id: &str, message: &str) -> AppError {
+        match self.store.get(id).await {
+            Ok(Some(job)) => AppError::validation(format!("{}（当前状态: {}）", message, job.status.as_str())),
+            Ok(None) => AppError::validation(format!("队列任务不存在: {}", id)),
+            Err(e) => e,
+        }
+    }
+
+    async fn emit_current(&self, app: &AppHandle, id: &str) -> Result<UploadJob, AppError> {
+        let job = self
+            .store
+            .get(id)
+            .await?
+            .ok_or_else(|| AppError::validation(format!("队列任务不存在: {}", id)))?;
+        emit_updated(app, id, Some(&job));
+        Ok(job)
+    }
+}
+
+fn emit_updated(app: &AppHandle, id: &str, job: Option<&UploadJob>) {
+    let _ = app.emit("queue://updated", QueueUpdatedEvent { id, job });
+}
+
+/// 第 n 次失败后的重试等待时间：5s、10s、20s ... 上限 10 分钟
+fn retry_delay_ms(attempts: u32) -> i64 {
+    let exponent = attempts.saturating_sub(1).min(16);
+    (BASE_RETRY_DELAY_MS << exponent).min(MAX_RETRY_DELAY_MS)
+}
+
+/// 启动后台 worker（在 setup 中、`UploadQueue` 注册之后调用）
+pub fn spawn_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let queue = app.state::<UploadQueue>();
+
+        match queue.store.recover_interrupted().await {
+            Ok(0) => {}
+            Ok(count) => println!("[UploadQueue] 恢复了 {} 个上次未完成的任务", count),
+            Err(e) => eprintln!("[UploadQueue] 恢复未完成任务失败: {}", e),
+        }
+
+        loop {
+            match queue.store.claim_next_due(now_millis()).await {
+                Ok(Some(job)) => {
+                    run_job(&app, &queue, job).await;
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("[UploadQueue] {}", e),
+            }
+
+            // 等到最早的重试时间，或者被新任务唤醒
+            let wait = match queue.store.next_due_at().await {
+                Ok(Some(at)) => Duration::from_millis((at - now_millis()).max(0) as u64).min(IDLE_POLL_INTERVAL),
+                _ => IDLE_POLL_INTERVAL,
+            };
+            tokio::select! {
+                _ = queue.notify.notified() => {}
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    });
+}
+
+/// 执行一次任务尝试并写回结果
+async fn run_job(app: &AppHandle, queue: &UploadQueue, job: UploadJob) {
+    let attempts = job.attempts + 1;
+    println!(
+        "[UploadQueue] 开始任务 {}（第 {}/{} 次）: {}",
+        job.id, attempts, job.max_attempts, job.file_path
+    );
+    emit_updated(app, &job.id, Some(&job));
+
+    let outcome = attempt_upload(app, &job).await;
+
+    let written = match &outcome {
+        Ok(output) => {
+            let warning = output.partial_failures.as_ref().map(|failures| {
+                failures
+                    .iter()
+                    .map(|f| format!("{}: {}", f.service_id, f.error))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            });
+            let result = serde_json::to_value(output).unwrap_or(serde_json::Value::Null);
+            println!("[UploadQueue] 任务 {} 完成: {}", job.id, output.primary_url);
+            queue.store.mark_completed(&job.id, attempts, &result, warning.as_deref()).await
+        }
+        // 暂停或删除导致的取消：状态已由对应命令更新，这里不计入尝试次数
+        Err(e) if e.is_cancelled() => {
+            println!("[UploadQueue] 任务 {} 已中止", job.id);
+            Ok(())
+        }
+        Err(e) => {
+            // 认证失效、配置错误、文件不支持等重试也不会成功，直接标记失败
+            let retryable = e.class() == ErrorClass::Transient;
+            let next_attempt_at = (retryable && attempts < job.max_attempts)
+                .then(|| now_millis() + retry_delay_ms(attempts));
+            match next_attempt_at {
+                Some(at) => eprintln!(
+                    "[UploadQueue] 任务 {} 失败，{} 秒后重试: {}",
+                    job.id,
+                    (at - now_millis()) / 1000,
+                    e
+                ),
+                None if !retryable => eprintln!("[UploadQueue] 任务 {} 失败（不可重试）: {}", job.id, e),
+                None => eprintln!("[UploadQueue] 任务 {} 重试次数已用尽: {}", job.id, e),
+            }
+            queue
+                .store
+                .mark_attempt_failed(&job.id, attempts, &e.to_string(), next_attempt_at)
+                .await
+        }
+    };
+    if let Err(e) = written {
+        eprintln!("[UploadQueue] {}", e);
+    }
+
+    if let Ok(Some(updated)) = queue.store.get(&job.id).await {
+        emit_updated(app, &job.id, Some(&updated));
+        if updated.status == JobStatus::Completed {
+            let _ = app.emit("queue://job-completed", &updated);
+        }
+    }
+}
+
+async fn attempt_upload(
+    app: &AppHandle,
+    job: &UploadJob,
+) -> Result<crate::uploader::orchestrator::MultiUploadOutput, AppError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::config(format!("无法获取应用数据目录: {}", e)))?;
+    let user_config = secure_config::load_user_config(&app_data_dir)?;
+
+    let registry = app.state::<UploaderRegistry>();
+    let http_client = app.state::<HttpClient>().0.clone();
+    let request = MultiUploadRequest {
+        id: job.id.clone(),
+        file_path: job.file_path.clone(),
+        services: job.services.clone(),
+        configs: secure_config::service_configs(&user_config),
+        max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        retry: RetryPolicy::default(),
+        force_fresh: false,
+    };
+    upload_to_services(app.clone(), &registry, http_client, request).await
+}
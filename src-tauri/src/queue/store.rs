@@ -0,0 +1,339 @@
+// src-tauri/src/queue/store.rs
+// 上传队列的 SQLite 持久化
+//
+// 与前端历史记录共用 `history.db`（tauri-plugin-sql 的 `sqlite:history.db` 位于 app_config_dir），
+// 队列单独使用 `upload_queue` 表，不影响前端的 history_items 表结构。
+
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::Row;
+
+use crate::error::{AppError, IntoAppError};
+
+/// 队列任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// 等待上传（包括等待重试）
+    Pending,
+    /// 正在上传
+    Running,
+    /// 已暂停
+    Paused,
+    /// 重试次数用尽
+    Failed,
+    /// 上传完成（至少一个图床成功）
+    Completed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Paused => "paused",
+            JobStatus::Failed => "failed",
+            JobStatus::Completed => "completed",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "paused" => Ok(JobStatus::Paused),
+            "failed" => Ok(JobStatus::Failed),
+            "completed" => Ok(JobStatus::Completed),
+            other => Err(AppError::storage(format!("未知的队列任务状态: {}", other))),
+        }
+    }
+}
+
+/// 队列任务
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadJob {
+    pub id: String,
+    pub file_path: String,
+    /// 按优先级排列的目标图床
+    pub services: Vec<String>,
+    pub status: JobStatus,
+    /// 已尝试次数
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub last_error: Option<String>,
+    /// 下次尝试时间（毫秒时间戳）
+    pub next_attempt_at: i64,
+    /// 上传结果（MultiUploadOutput 的 JSON）
+    pub result: Option<serde_json::Value>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl UploadJob {
+    fn from_row(row: &SqliteRow) -> Result<Self, AppError> {
+        let services: String = row.get("services");
+        let status: String = row.get("status");
+        let result: Option<String> = row.get("result");
+
+        Ok(Self {
+            id: row.get("id"),
+            file_path: row.get("file_path"),
+            services: serde_json::from_str(&services)
+                .into_storage_err_with("解析任务图床列表失败")?,
+            status: status.parse()?,
+            attempts: row.get::<i64, _>("attempts") as u32,
+            max_attempts: row.get::<i64, _>("max_attempts") as u32,
+            last_error: row.get("last_error"),
+            next_attempt_at: row.get("next_attempt_at"),
+            result: result.and_then(|r| serde_json::from_str(&r).ok()),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+const CREATE_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS upload_queue (
+    id TEXT PRIMARY KEY,
+    file_path TEXT NOT NULL,
+    services TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    last_error TEXT,
+    next_attempt_at INTEGER NOT NULL,
+    result TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+)
+"#;
+
+const CREATE_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_upload_queue_status ON upload_queue(status, next_attempt_at)";
+
+/// 当前毫秒时间戳
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// 队列数据库访问层
+#[derive(Clone)]
+pub struct QueueStore {
+    pool: SqlitePool,
+}
+
+impl QueueStore {
+    /// 打开（必要时创建）数据库并建表
+    pub async fn open(db_path: &Path) -> Result<Self, AppError> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent).into_file_io_err_with("创建数据库目录失败")?;
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            // 前端插件会同时写入 history.db，等待锁而不是立即报 SQLITE_BUSY
+            .busy_timeout(Duration::from_secs(5));
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(2)
+            .connect_with(options)
+            .await
+            .into_storage_err_with("打开队列数据库失败")?;
+
+        sqlx::query(CREATE_TABLE_SQL)
+            .execute(&pool)
+            .await
+            .into_storage_err_with("创建队列表失败")?;
+        sqlx::query(CREATE_INDEX_SQL)
+            .execute(&pool)
+            .await
+            .into_storage_err_with("创建队列索引失败")?;
+
+        Ok(Self { pool })
+    }
+
+    /// 数据库连接池（去重缓存共用同一个数据库）
+    pub fn pool(&self) -> SqlitePool {
+        self.pool.clone()
+    }
+
+    pub async fn insert(&self, job: &UploadJob) -> Result<(), AppError> {
+        let services = serde_json::to_string(&job.services).into_storage_err()?;
+        sqlx::query(
+            "INSERT INTO upload_queue (id, file_path, services, status, attempts, max_attempts, \
+             last_error, next_attempt_at, result, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, NULL, ?, NULL, ?, ?)",
+        )
+        .bind(&job.id)
+        .bind(&job.file_path)
+        .bind(services)
+        .bind(job.status.as_str())
+        .bind(job.attempts as i64)
+        .bind(job.max_attempts as i64)
+        .bind(job.next_attempt_at)
+        .bind(job.created_at)
+        .bind(job.updated_at)
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("写入队列任务失败")?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<UploadJob>, AppError> {
+        let row = sqlx::query("SELECT * FROM upload_queue WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .into_storage_err_with("读取队列任务失败")?;
+        row.as_ref().map(UploadJob::from_row).transpose()
+    }
+
+    /// 列出所有任务（按创建时间倒序）
+    pub async fn list(&self) -> Result<Vec<UploadJob>, AppError> {
+        let rows = sqlx::query("SELECT * FROM upload_queue ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await
+            .into_storage_err_with("读取队列任务失败")?;
+        rows.iter().map(UploadJob::from_row).collect()
+    }
+
+    /// 取出下一个已到期的等待任务，并原子地标记为 running
+    pub async fn claim_next_due(&self, now: i64) -> Result<Option<UploadJob>, AppError> {
+        let row = sqlx::query(
+            "UPDATE upload_queue SET status = 'running', updated_at = ?1 \
+             WHERE id = (SELECT id FROM upload_queue WHERE status = 'pending' AND next_attempt_at <= ?1 \
+                         ORDER BY next_attempt_at, created_at LIMIT 1) \
+             RETURNING *",
+        )
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .into_storage_err_with("领取队列任务失败")?;
+        row.as_ref().map(UploadJob::from_row).transpose()
+    }
+
+    /// 最早的等待任务的下次尝试时间
+    pub async fn next_due_at(&self) -> Result<Option<i64>, AppError> {
+        let row = sqlx::query(
+            "SELECT MIN(next_attempt_at) AS next_at FROM upload_queue WHERE status = 'pending'",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .into_storage_err_with("读取队列任务失败")?;
+        Ok(row.get("next_at"))
+    }
+
+    /// 启动时把上次异常退出遗留的 running 任务恢复为 pending，返回恢复的数量
+    pub async fn recover_interrupted(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            "UPDATE upload_queue SET status = 'pending', next_attempt_at = ?1, updated_at = ?1 \
+             WHERE status = 'running'",
+        )
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("恢复队列任务失败")?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn mark_completed(
+        &self,
+        id: &str,
+        attempts: u32,
+        result: &serde_json::Value,
+        warning: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE upload_queue SET status = 'completed', attempts = ?, result = ?, last_error = ?, \
+             updated_at = ? WHERE id = ? AND status = 'running'",
+        )
+        .bind(attempts as i64)
+        .bind(result.to_string())
+        .bind(warning)
+        .bind(now_millis())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("更新队列任务失败")?;
+        Ok(())
+    }
+
+    /// 记录一次失败：还有重试次数时回到 pending 并设置下次尝试时间，否则标记为 failed
+    pub async fn mark_attempt_failed(
+        &self,
+        id: &str,
+        attempts: u32,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> Result<(), AppError> {
+        let (status, next_at) = match next_attempt_at {
+            Some(at) => (JobStatus::Pending, at),
+            None => (JobStatus::Failed, now_millis()),
+        };
+        sqlx::query(
+            "UPDATE upload_queue SET status = ?, attempts = ?, last_error = ?, next_attempt_at = ?, \
+             updated_at = ? WHERE id = ? AND status = 'running'",
+        )
+        .bind(status.as_str())
+        .bind(attempts as i64)
+        .bind(error)
+        .bind(next_at)
+        .bind(now_millis())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("更新队列任务失败")?;
+        Ok(())
+    }
+
+    /// 暂停任务（pending / running 可暂停），返回是否有任务被暂停
+    pub async fn pause(&self, id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            "UPDATE upload_queue SET status = 'paused', updated_at = ? \
+             WHERE id = ? AND status IN ('pending', 'running')",
+        )
+        .bind(now_millis())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("暂停队列任务失败")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 恢复任务（paused / failed 可恢复），失败的任务会重置尝试次数
+    pub async fn resume(&self, id: &str) -> Result<bool, AppError> {
+        let now = now_millis();
+        let result = sqlx::query(
+            "UPDATE upload_queue SET \
+               attempts = CASE WHEN status = 'failed' THEN 0 ELSE attempts END, \
+               status = 'pending', next_attempt_at = ?1, updated_at = ?1 \
+             WHERE id = ?2 AND status IN ('paused', 'failed')",
+        )
+        .bind(now)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("恢复队列任务失败")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM upload_queue WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .into_storage_err_with("删除队列任务失败")?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,181 @@
+// src-tauri/src/secure_config.rs
+// 读取前端加密保存的用户配置
+//
+// 前端通过 src/store.ts 将配置写入 `{appDataDir}/.settings.dat`，
+// 内容格式为 `PNXENC:` + Base64(IV(12字节) + AES-256-GCM 密文)，密钥保存在系统钥匙串中。
+// 后端任务（持久化队列、监听目录、命令行等）在没有 webview 的情况下也需要读取图床配置。
+
+use std::path::Path;
+
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use keyring::Entry;
+use rand::Rng;
+use serde::de::DeserializeOwned;
+
+use crate::error::{AppError, IntoAppError};
+
+/// 钥匙串服务名，防止与其他应用冲突
+pub const SERVICE_NAME: &str = "us.picnex.app.secure";
+/// 钥匙串中保存配置加密密钥的条目名
+pub const KEY_NAME: &str = "config_encryption_key";
+
+/// 配置文件名（相对于应用数据目录）
+pub const SETTINGS_FILE: &str = ".settings.dat";
+
+/// 加密数据魔数前缀（与 src/crypto.ts 保持一致）
+const ENCRYPTED_MAGIC_PREFIX: &str = "PNXENC:";
+
+/// AES-GCM IV 长度
+const IV_LEN: usize = 12;
+
+/// 获取或创建配置加密密钥（Base64 编码的 32 字节密钥）
+pub fn get_or_create_key() -> Result<String, AppError> {
+    let entry = Entry::new(SERVICE_NAME, KEY_NAME).map_err(|e| {
+        AppError::external(format!("无法访问系统钥匙串: {}", e))
+    })?;
+
+    match entry.get_password() {
+        Ok(key) => {
+            eprintln!("[密钥管理] 从钥匙串读取现有密钥");
+            Ok(key)
+        },
+        Err(_) => {
+            eprintln!("[密钥管理] 生成新的加密密钥");
+            let mut key_bytes = [0u8; 32];
+            rand::thread_rng().fill(&mut key_bytes);
+            let new_key = STANDARD.encode(key_bytes);
+
+            entry.set_password(&new_key).map_err(|e| {
+                AppError::external(format!("无法保存密钥到系统钥匙串: {}", e))
+            })?;
+
+            eprintln!("[密钥管理] ✓ 新密钥已保存到系统钥匙串");
+            Ok(new_key)
+        }
+    }
+}
+
+/// 解密前端加密的数据
+fn decrypt(encrypted: &str, key_b64: &str) -> Result<String, AppError> {
+    let encrypted_b64 = encrypted.strip_prefix(ENCRYPTED_MAGIC_PREFIX).unwrap_or(encrypted);
+
+    let key_bytes = STANDARD.decode(key_b64.trim())
+        .into_external_err_with("密钥格式无效")?;
+    let combined = STANDARD.decode(encrypted_b64.trim())
+        .into_validation_err_with("加密数据格式无效")?;
+
+    if combined.len() <= IV_LEN {
+        return Err(AppError::validation("加密数据长度不足"));
+    }
+    let (iv, ciphertext) = combined.split_at(IV_LEN);
+
+    let cipher = Aes256Gcm::new_from_slice(&key_bytes)
+        .into_external_err_with("密钥长度无效")?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(iv), ciphertext)
+        .map_err(|_| AppError::validation("解密失败：数据损坏或密钥不匹配"))?;
+
+    String::from_utf8(plaintext).into_validation_err_with("解密结果不是有效的 UTF-8 文本")
+}
+
+/// 读取 `.settings.dat` 中指定键的值
+pub fn load_settings_value(app_data_dir: &Path, key: &str) -> Result<Option<serde_json::Value>, AppError> {
+    let path = app_data_dir.join(SETTINGS_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .into_file_io_err_with("读取配置文件失败")?;
+    let content = content.trim();
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    // 与前端一致：有魔数前缀的是加密数据，以 { 或 [ 开头的是明文，其他按旧版加密数据尝试解密
+    let json_text = if content.starts_with(ENCRYPTED_MAGIC_PREFIX) {
+        decrypt(content, &get_or_create_key()?)?
+    } else if !content.starts_with('{') && !content.starts_with('[') {
+        decrypt(content, &get_or_create_key()?).unwrap_or_else(|_| content.to_string())
+    } else {
+        content.to_string()
+    };
+
+    let mut data: serde_json::Value = serde_json::from_str(&json_text)
+        .map_err(|e| AppError::config(format!("配置文件格式错误: {}", e)))?;
+
+    Ok(data.get_mut(key).map(serde_json::Value::take))
+}
+
+/// 读取用户配置（前端 UserConfig）
+pub fn load_user_config(app_data_dir: &Path) -> Result<serde_json::Value, AppError> {
+    load_settings_value(app_data_dir, "config")?
+        .ok_or_else(|| AppError::config("尚未保存用户配置，请先在设置页面完成配置"))
+}
+
+/// 取出用户配置中的一项并反序列化，未配置（缺失或为 null）时使用默认值
+pub fn section<T: DeserializeOwned + Default>(
+    user_config: &serde_json::Value,
+    key: &str,
+) -> Result<T, serde_json::Error> {
+    match user_config.get(key) {
+        Some(value) if !value.is_null() => T::deserialize(value),
+        _ => Ok(T::default()),
+    }
+}
+
+/// 提取各图床配置表（`config.services`），缺失时返回空对象
+pub fn service_configs(user_config: &serde_json::Value) -> serde_json::Value {
+    user_config
+        .get("services")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+/// 启用的图床（`config.enabledServices`，按优先级排列）
+pub fn enabled_services(user_config: &serde_json::Value) -> Vec<String> {
+    user_config
+        .get("enabledServices")
+        .and_then(|v| v.as_array())
+        .map(|services| {
+            services
+                .iter()
+                .filter_map(|s| s.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 微博链接的代理前缀（与前端 `getActivePrefix` 一致）
+pub fn weibo_link_prefix(user_config: &serde_json::Value) -> Option<String> {
+    const DEFAULT_PREFIX: &str = "https://image.baidu.com/search/down?thumburl=";
+
+    let Some(prefix_config) = user_config.get("linkPrefixConfig") else {
+        let legacy = user_config.get("baiduPrefix").and_then(|v| v.as_str());
+        return Some(legacy.unwrap_or(DEFAULT_PREFIX).to_string());
+    };
+
+    if !prefix_config.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return None;
+    }
+
+    let list: Vec<&str> = prefix_config
+        .get("prefixList")
+        .and_then(|v| v.as_array())
+        .map(|list| list.iter().filter_map(|p| p.as_str()).collect())
+        .unwrap_or_default();
+    let index = prefix_config
+        .get("selectedIndex")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as usize;
+
+    Some(
+        list.get(index)
+            .or_else(|| list.first())
+            .copied()
+            .unwrap_or(DEFAULT_PREFIX)
+            .to_string(),
+    )
+}
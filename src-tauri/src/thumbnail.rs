@@ -0,0 +1,502 @@
+// src-tauri/src/thumbnail.rs
+// 缩略图缓存
+//
+// 历史记录画廊可能同时显示上千张远程图片，直接加载原图又慢又依赖网络。这里生成 WebP 缩略图，
+// 保存在应用缓存目录（`{app_cache_dir}/thumbnails`），通过自定义协议 `thumb://` 提供给前端：
+// - 上传完成时用本地文件生成（写入历史记录时触发），之后画廊离线也能显示
+// - 缓存中没有时按需下载远程图片生成（`src` 可以指定图床提供的较小尺寸，减少下载量）
+// - 按图片链接 + 尺寸命名，总大小超过上限时按最近使用时间淘汰（LRU）
+//
+// 请求格式：`thumb://localhost/{尺寸}?url={图片链接}&src={下载地址，可选}`
+// （Windows 上为 `http://thumb.localhost/...`，前端见 src/utils/thumbnail.ts）。
+// 尺寸按配置（`config.thumbnails.sizes`）取整，避免同一张图片缓存过多尺寸。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use image::codecs::webp::{WebPEncoder, WebPQuality};
+use image::{ColorType, DynamicImage, ImageEncoder};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tauri::http::{header, Request, Response, StatusCode, Uri};
+use tauri::{AppHandle, Manager, UriSchemeResponder};
+use tokio::sync::Semaphore;
+
+use crate::error::AppError;
+use crate::secure_config;
+use crate::uploader::processing;
+use crate::HttpClient;
+
+/// 自定义协议名
+pub const PROTOCOL: &str = "thumb";
+
+/// 缓存子目录
+const CACHE_DIR: &str = "thumbnails";
+
+/// 同时生成的缩略图数量（下载 + 解码都比较重，画廊滚动时会一次请求很多张）
+const MAX_CONCURRENT_RENDERS: usize = 4;
+
+/// 按需生成时下载的图片大小上限
+const MAX_SOURCE_SIZE: usize = 30 * 1024 * 1024;
+
+/// 下载超时
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 淘汰后保留的比例，避免每次写入都触发淘汰
+const EVICT_TARGET_RATIO: f64 = 0.9;
+
+/// 缩略图配置（`config.thumbnails`）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThumbnailSettings {
+    pub enabled: bool,
+    /// 生成的尺寸（最长边像素），请求的尺寸取不小于它的最近一档
+    pub sizes: Vec<u32>,
+    /// WebP 编码质量
+    pub quality: u8,
+    /// 缓存大小上限（MB）
+    pub max_cache_mb: u64,
+}
+
+impl Default for ThumbnailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sizes: vec![150, 400],
+            quality: 75,
+            max_cache_mb: 200,
+        }
+    }
+}
+
+impl ThumbnailSettings {
+    /// 请求的尺寸取整到配置的档位，超过最大一档时使用最大一档
+    fn snap_size(&self, requested: u32) -> Option<u32> {
+        let mut sizes: Vec<u32> = self.sizes.iter().copied().filter(|&s| s > 0).collect();
+        sizes.sort_unstable();
+        sizes
+            .iter()
+            .copied()
+            .find(|&s| s >= requested)
+            .or_else(|| sizes.last().copied())
+    }
+
+    fn max_cache_bytes(&self) -> u64 {
+        self.max_cache_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// 缓存文件的大小和最近使用时间
+struct CacheEntry {
+    size: u64,
+    last_used: u64,
+}
+
+/// 缓存目录索引（首次使用时扫描目录建立，最近使用时间取文件修改时间）
+#[derive(Default)]
+struct CacheIndex {
+    entries: HashMap<String, CacheEntry>,
+    total: u64,
+}
+
+impl CacheIndex {
+    fn scan(dir: &Path) -> Self {
+        let mut index = Self::default();
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return index;
+        };
+        for entry in read_dir.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.ends_with(".webp") {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let last_used = metadata.modified().map_or(0, millis_since_epoch);
+            index.insert(name, metadata.len(), last_used);
+        }
+        index
+    }
+
+    fn insert(&mut self, name: String, size: u64, last_used: u64) {
+        if let Some(old) = self.entries.insert(name, CacheEntry { size, last_used }) {
+            self.total -= old.size;
+        }
+        self.total += size;
+    }
+
+    /// 按最近使用时间从旧到新删除，直到总大小不超过 `max_bytes` 的 90%
+    fn evict(&mut self, dir: &Path, max_bytes: u64) {
+        if self.total <= max_bytes {
+            return;
+        }
+        let target = (max_bytes as f64 * EVICT_TARGET_RATIO) as u64;
+        let mut names: Vec<(u64, String)> = self
+            .entries
+            .iter()
+            .map(|(name, entry)| (entry.last_used, name.clone()))
+            .collect();
+        names.sort_unstable();
+
+        let mut removed = 0;
+        for (_, name) in names {
+            if self.total <= target {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&name) {
+                self.total -= entry.size;
+                let _ = fs::remove_file(dir.join(&name));
+                removed += 1;
+            }
+        }
+        println!(
+            "[Thumbnail] 缓存超过上限，已淘汰 {} 个缩略图，当前 {:.1}MB",
+            removed,
+            self.total as f64 / 1024.0 / 1024.0
+        );
+    }
+}
+
+/// 缩略图缓存（作为 Tauri 全局状态注册）
+pub struct ThumbnailCache {
+    settings: Mutex<ThumbnailSettings>,
+    dir: Mutex<Option<PathBuf>>,
+    index: Mutex<Option<CacheIndex>>,
+    renders: Semaphore,
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        Self {
+            settings: Mutex::new(ThumbnailSettings::default()),
+            dir: Mutex::new(None),
+            index: Mutex::new(None),
+            renders: Semaphore::new(MAX_CONCURRENT_RENDERS),
+        }
+    }
+}
+
+impl ThumbnailCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn settings(&self) -> ThumbnailSettings {
+        self.settings.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    fn dir(&self) -> Option<PathBuf> {
+        self.dir.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    /// 应用用户配置中的 `thumbnails`，格式错误时保持当前配置
+    pub fn apply_config(&self, app: &AppHandle, user_config: &serde_json::Value) {
+        let dir = match app.path().app_cache_dir() {
+            Ok(dir) => dir.join(CACHE_DIR),
+            Err(e) => {
+                eprintln!("[Thumbnail] 无法获取应用缓存目录: {}", e);
+                return;
+            }
+        };
+        match secure_config::section::<ThumbnailSettings>(user_config, "thumbnails") {
+            Ok(settings) => self.apply(dir, settings),
+            Err(e) => eprintln!("[Thumbnail] thumbnails 配置格式错误: {}", e),
+        }
+    }
+
+    /// 应用新配置，缓存上限变小时立即淘汰
+    fn apply(&self, dir: PathBuf, settings: ThumbnailSettings) {
+        *self.dir.lock().unwrap_or_else(|p| p.into_inner()) = Some(dir);
+        let mut current = self.settings.lock().unwrap_or_else(|p| p.into_inner());
+        if *current == settings {
+            return;
+        }
+        println!(
+            "[Thumbnail] 缩略图缓存: {}，尺寸 {:?}，上限 {}MB",
+            if settings.enabled { "开启" } else { "关闭" },
+            settings.sizes,
+            settings.max_cache_mb
+        );
+        let max_bytes = settings.max_cache_bytes();
+        *current = settings;
+        drop(current);
+        self.with_index(|dir, index| index.evict(dir, max_bytes));
+    }
+
+    /// 在索引上执行操作（首次调用时扫描缓存目录）
+    fn with_index<T>(&self, f: impl FnOnce(&Path, &mut CacheIndex) -> T) -> Option<T> {
+        let dir = self.dir()?;
+        let mut index = self.index.lock().unwrap_or_else(|p| p.into_inner());
+        let index = index.get_or_insert_with(|| CacheIndex::scan(&dir));
+        Some(f(&dir, index))
+    }
+
+    /// 读取缓存，命中时更新最近使用时间（同时写回文件修改时间，重启后仍按使用时间淘汰）
+    fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let dir = self.dir()?;
+        let path = dir.join(name);
+        let bytes = fs::read(&path).ok()?;
+
+        let now = SystemTime::now();
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(now);
+        }
+        self.with_index(|_, index| index.insert(name.to_string(), bytes.len() as u64, millis_since_epoch(now)));
+        Some(bytes)
+    }
+
+    /// 写入缓存（先写临时文件再重命名，并发读取不会读到一半的文件）
+    fn write(&self, name: &str, bytes: &[u8]) -> Result<(), AppError> {
+        let dir = self.dir().ok_or_else(|| AppError::config("缩略图缓存目录未初始化"))?;
+        fs::create_dir_all(&dir).map_err(|e| AppError::file_io(format!("创建缩略图目录失败: {}", e)))?;
+        let temp = dir.join(format!("{}.{:08x}.tmp", name, rand::random::<u32>()));
+        fs::write(&temp, bytes)
+            .and_then(|_| fs::rename(&temp, dir.join(name)))
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp);
+                AppError::file_io(format!("写入缩略图失败: {}", e))
+            })?;
+
+        let max_bytes = self.settings().max_cache_bytes();
+        self.with_index(|dir, index| {
+            index.insert(name.to_string(), bytes.len() as u64, millis_since_epoch(SystemTime::now()));
+            index.evict(dir, max_bytes);
+        });
+        Ok(())
+    }
+
+    /// 清空缓存，返回删除的文件数
+    pub fn clear(&self) -> usize {
+        self.with_index(|dir, index| {
+            let count = index.entries.len();
+            for name in index.entries.keys() {
+                let _ = fs::remove_file(dir.join(name));
+            }
+            *index = CacheIndex::default();
+            count
+        })
+        .unwrap_or(0)
+    }
+}
+
+/// 缓存文件名：图片链接的 SHA-256（前 32 位十六进制）+ 尺寸
+fn cache_name(url: &str, size: u32) -> String {
+    let hash = hex::encode(Sha256::digest(url.as_bytes()));
+    format!("{}_{}.webp", &hash[..32], size)
+}
+
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 按配置的各档尺寸生成 WebP 缩略图（原图小于该尺寸时不放大）
+#[allow(deprecated)]
+fn render(image: &DynamicImage, sizes: &[u32], quality: u8) -> Result<Vec<(u32, Vec<u8>)>, AppError> {
+    sizes
+        .iter()
+        .map(|&size| {
+            let thumb = if image.width().max(image.height()) > size {
+                image.thumbnail(size, size)
+            } else {
+                image.clone()
+            };
+            let rgba = thumb.to_rgba8();
+            let mut buf = Vec::new();
+            WebPEncoder::new_with_quality(&mut buf, WebPQuality::lossy(quality.clamp(1, 100)))
+                .write_image(rgba.as_raw(), rgba.width(), rgba.height(), ColorType::Rgba8)
+                .map_err(|e| AppError::external(format!("缩略图编码失败: {}", e)))?;
+            Ok((size, buf))
+        })
+        .collect()
+}
+
+/// 上传完成后用本地文件生成缩略图，按各图床链接分别保存（后台执行，失败只记录日志）
+pub fn generate_for_upload(app: &AppHandle, file_path: &str, urls: Vec<String>) {
+    if urls.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    let file_path = file_path.to_string();
+    tauri::async_runtime::spawn(async move {
+        let Some(cache) = app.try_state::<ThumbnailCache>() else {
+            return;
+        };
+        let settings = cache.settings();
+        if !settings.enabled || settings.sizes.is_empty() {
+            return;
+        }
+
+        let Ok(_permit) = cache.renders.acquire().await else {
+            return;
+        };
+        let path = file_path.clone();
+        let rendered = tauri::async_runtime::spawn_blocking(move || {
+            let image = image::io::Reader::open(&path)
+                .and_then(|reader| reader.with_guessed_format())
+                .map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?
+                .decode()
+                .map_err(|e| AppError::validation(format!("图片解码失败: {}", e)))?;
+            // 与上传前的方向校正一致，缩略图按显示方向保存
+            let image = processing::apply_orientation(image, processing::read_orientation(Path::new(&path)));
+            render(&image, &settings.sizes, settings.quality)
+        })
+        .await
+        .map_err(|e| AppError::external(format!("缩略图生成任务失败: {}", e)))
+        .and_then(|result| result);
+
+        let thumbnails = match rendered {
+            Ok(thumbnails) => thumbnails,
+            Err(e) => {
+                eprintln!("[Thumbnail] 生成失败: {}: {}", file_path, e);
+                return;
+            }
+        };
+        for url in &urls {
+            for (size, bytes) in &thumbnails {
+                if let Err(e) = cache.write(&cache_name(url, *size), bytes) {
+                    eprintln!("[Thumbnail] {}", e);
+                    return;
+                }
+            }
+        }
+        println!("[Thumbnail] 已生成缩略图: {} ({} 个链接)", file_path, urls.len());
+    });
+}
+
+/// 协议请求参数
+struct ThumbnailRequest {
+    size: u32,
+    url: String,
+    src: Option<String>,
+}
+
+fn parse_request(uri: &Uri) -> Result<ThumbnailRequest, AppError> {
+    let parsed = tauri::Url::parse(&uri.to_string())
+        .map_err(|e| AppError::validation(format!("缩略图地址无效: {}", e)))?;
+    let size = parsed
+        .path()
+        .trim_matches('/')
+        .parse::<u32>()
+        .map_err(|_| AppError::validation("缩略图尺寸无效"))?;
+
+    let mut url = None;
+    let mut src = None;
+    for (key, value) in parsed.query_pairs() {
+        match key.as_ref() {
+            "url" => url = Some(value.into_owned()),
+            "src" => src = Some(value.into_owned()).filter(|s| !s.is_empty()),
+            _ => {}
+        }
+    }
+    let url = url.filter(|u| !u.is_empty()).ok_or_else(|| AppError::validation("缺少图片链接"))?;
+
+    // 只允许下载 HTTP(S) 地址
+    let remote = |u: &str| u.starts_with("http://") || u.starts_with("https://");
+    if !remote(src.as_deref().unwrap_or(&url)) {
+        return Err(AppError::validation("只支持 HTTP(S) 图片链接"));
+    }
+    Ok(ThumbnailRequest { size, url, src })
+}
+
+/// 下载远程图片（按需生成缩略图时使用）
+async fn download(app: &AppHandle, url: &str) -> Result<Vec<u8>, AppError> {
+    let client = app.state::<HttpClient>().0.clone();
+    let response = client
+        .get(url)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("下载图片失败: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("下载图片失败: HTTP {}", response.status())));
+    }
+    if response.content_length().is_some_and(|len| len as usize > MAX_SOURCE_SIZE) {
+        return Err(AppError::validation("图片过大，不生成缩略图"));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::network(format!("读取图片失败: {}", e)))?;
+    if bytes.len() > MAX_SOURCE_SIZE {
+        return Err(AppError::validation("图片过大，不生成缩略图"));
+    }
+    Ok(bytes.to_vec())
+}
+
+/// 返回缓存中的缩略图，没有时下载生成
+async fn serve(app: &AppHandle, uri: &Uri) -> Result<Vec<u8>, AppError> {
+    let cache = app
+        .try_state::<ThumbnailCache>()
+        .ok_or_else(|| AppError::config("缩略图缓存不可用"))?;
+    let settings = cache.settings();
+    if !settings.enabled {
+        return Err(AppError::config("缩略图缓存已关闭"));
+    }
+
+    let request = parse_request(uri)?;
+    let size = settings
+        .snap_size(request.size)
+        .ok_or_else(|| AppError::config("未配置缩略图尺寸"))?;
+    let name = cache_name(&request.url, size);
+    if let Some(bytes) = cache.read(&name) {
+        return Ok(bytes);
+    }
+
+    let _permit = cache
+        .renders
+        .acquire()
+        .await
+        .map_err(|e| AppError::external(format!("缩略图生成任务失败: {}", e)))?;
+    // 等待期间同一张图片可能已由其他请求生成
+    if let Some(bytes) = cache.read(&name) {
+        return Ok(bytes);
+    }
+
+    let data = download(app, request.src.as_deref().unwrap_or(&request.url)).await?;
+    let quality = settings.quality;
+    let bytes = tauri::async_runtime::spawn_blocking(move || {
+        let image = image::load_from_memory(&data)
+            .map_err(|e| AppError::validation(format!("图片解码失败: {}", e)))?;
+        render(&image, &[size], quality).map(|mut thumbnails| thumbnails.remove(0).1)
+    })
+    .await
+    .map_err(|e| AppError::external(format!("缩略图生成任务失败: {}", e)))??;
+
+    // 写入失败（如磁盘已满）不影响本次显示
+    if let Err(e) = cache.write(&name, &bytes) {
+        eprintln!("[Thumbnail] {}", e);
+    }
+    Ok(bytes)
+}
+
+/// `thumb://` 协议处理（异步执行，不阻塞 WebView）
+pub fn handle_protocol(app: AppHandle, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    tauri::async_runtime::spawn(async move {
+        let response = match serve(&app, request.uri()).await {
+            Ok(bytes) => Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "image/webp")
+                .header(header::CACHE_CONTROL, "max-age=31536000, immutable")
+                .body(bytes),
+            Err(e) => {
+                let status = match &e {
+                    AppError::Validation { .. } | AppError::Config { .. } => StatusCode::NOT_FOUND,
+                    AppError::Network { .. } => StatusCode::BAD_GATEWAY,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                Response::builder()
+                    .status(status)
+                    .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+                    .body(e.to_string().into_bytes())
+            }
+        };
+        match response {
+            Ok(response) => responder.respond(response),
+            Err(e) => eprintln!("[Thumbnail] 构建响应失败: {}", e),
+        }
+    });
+}
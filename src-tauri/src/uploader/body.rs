@@ -0,0 +1,266 @@
+// src-tauri/src/uploader/body.rs
+// 流式文件请求体
+//
+// 上传时不再把整个文件读入内存，而是按块从磁盘读取后直接交给 HTTP 连接，
+// 每个上传任务的内存占用与文件大小无关（约为一个读取块的大小）。
+// multipart 表单、原始 PUT 请求体、S3 SDK 请求体以及分片上传的区间请求体都由这里生成。
+
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+use aws_sdk_s3::primitives::{ByteStream, SdkBody};
+use futures::stream::{self, Stream, StreamExt};
+use tokio::io::AsyncReadExt;
+use tokio_util::bytes::Bytes;
+use tokio_util::io::ReaderStream;
+
+use super::progress::ProgressReporter;
+use crate::error::{AppError, IntoAppError};
+use crate::image_format::{self, ImageType, HEADER_LEN};
+
+/// 每次从磁盘读取的块大小
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+type ByteResultStream = Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send + Sync>>;
+
+/// 待上传的文件
+#[derive(Debug, Clone)]
+pub struct FileBody {
+    path: PathBuf,
+    size: u64,
+    file_name: String,
+    /// 修改时间（毫秒时间戳），用于判断断点续传记录是否仍对应同一个文件
+    modified_ms: u128,
+    /// 按文件头识别的图片格式
+    image_type: Option<ImageType>,
+}
+
+impl FileBody {
+    /// 打开文件并读取元数据（只读取用于识别格式的文件头）
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        let path = path.as_ref();
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| AppError::file_io(format!("无法获取文件元数据: {}", e)))?;
+
+        if !metadata.is_file() {
+            return Err(AppError::file_io(format!("指定的路径不是有效的文件: {}", path.display())));
+        }
+
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| AppError::validation("无法获取文件名"))?
+            .to_string();
+
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            file_name,
+            modified_ms,
+            image_type: sniff_file(path).await,
+        })
+    }
+
+    /// 文件大小（字节）
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// 按文件内容识别的图片格式
+    pub fn image_type(&self) -> Option<ImageType> {
+        self.image_type
+    }
+
+    /// 格式扩展名（小写）：优先按内容识别，无法识别时使用文件扩展名
+    pub fn ext(&self) -> String {
+        match self.image_type {
+            Some(image_type) => image_type.ext().to_string(),
+            None => self
+                .file_name
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_lowercase())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 请求使用的 Content-Type：优先按内容识别，无法识别时按扩展名推断
+    pub fn content_type(&self) -> &'static str {
+        self.image_type
+            .or_else(|| ImageType::from_ext(&self.ext()))
+            .map_or("application/octet-stream", ImageType::mime)
+    }
+
+    /// 上传时使用的文件名：扩展名与实际格式不符时改为正确的扩展名
+    pub fn upload_file_name(&self) -> String {
+        match self.image_type {
+            Some(image_type) => image_format::corrected_file_name(&self.file_name, image_type),
+            None => self.file_name.clone(),
+        }
+    }
+
+    /// 文件指纹（路径 + 大小 + 修改时间），文件被修改后指纹随之变化
+    pub fn fingerprint(&self) -> String {
+        format!("{}|{}|{}", self.path.display(), self.size, self.modified_ms)
+    }
+
+    /// 读取文件中 `[offset, offset + len)` 区间的字节流
+    ///
+    /// 文件在调用时同步打开，打开失败时返回一个只产出该错误的流，
+    /// 这样也可以在同步上下文中（如 SDK 重试时重建请求体）使用
+    fn stream_range(&self, offset: u64, len: u64) -> ByteResultStream {
+        let opened = std::fs::File::open(&self.path).and_then(|mut file| {
+            if offset > 0 {
+                file.seek(SeekFrom::Start(offset))?;
+            }
+            Ok(file)
+        });
+
+        match opened {
+            Ok(file) => {
+                let reader = tokio::fs::File::from_std(file).take(len);
+                Box::pin(ReaderStream::with_capacity(reader, READ_CHUNK_SIZE))
+            }
+            Err(e) => Box::pin(stream::iter([Err(e)])),
+        }
+    }
+
+    /// 整个文件作为原始请求体（调用方需要自行设置 Content-Length）
+    pub fn body(&self, progress: &ProgressReporter) -> reqwest::Body {
+        self.range_body(0, self.size, progress)
+    }
+
+    /// 文件的一个区间作为请求体（分片上传使用）
+    pub fn range_body(&self, offset: u64, len: u64, progress: &ProgressReporter) -> reqwest::Body {
+        reqwest::Body::wrap_stream(progress.track(self.stream_range(offset, len)))
+    }
+
+    /// 文件的一个区间作为请求体，不计入进度（由调用方在分片完成后自行计入）
+    pub fn untracked_range_body(&self, offset: u64, len: u64) -> reqwest::Body {
+        reqwest::Body::wrap_stream(self.stream_range(offset, len))
+    }
+
+    /// 整个文件作为 multipart 文件字段（已设置文件名，长度已知，表单可以计算 Content-Length）
+    pub fn multipart_part(&self, progress: &ProgressReporter) -> reqwest::multipart::Part {
+        reqwest::multipart::Part::stream_with_length(self.body(progress), self.size)
+            .file_name(self.file_name.clone())
+    }
+
+    /// 整个文件作为 S3 请求体（需要同时设置 `content_length(size)`）
+    pub fn byte_stream(&self, progress: &ProgressReporter) -> ByteStream {
+        self.range_byte_stream(0, self.size, progress)
+    }
+
+    /// 文件的一个区间作为 S3 请求体
+    ///
+    /// SDK 内部重试时会重新打开文件生成请求体，此时该区间已计入的字节会被扣除
+    pub fn range_byte_stream(&self, offset: u64, len: u64, progress: &ProgressReporter) -> ByteStream {
+        let file = self.clone();
+        let progress = progress.clone();
+        let sent = Arc::new(AtomicU64::new(0));
+        ByteStream::new(SdkBody::retryable(move || {
+            let previously_sent = sent.swap(0, Ordering::Relaxed);
+            progress.rewind(previously_sent);
+
+            let sent = sent.clone();
+            let counted = file.stream_range(offset, len).map(move |chunk| {
+                if let Ok(bytes) = &chunk {
+                    sent.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+                }
+                chunk
+            });
+            SdkBody::from_body_0_4(hyper::Body::wrap_stream(progress.track(counted)))
+        }))
+    }
+
+    /// 按块读取整个文件计算摘要（如秒传需要的 SHA1 / MD5），不会把文件读入内存
+    pub async fn digest<D: sha2::Digest>(&self) -> Result<Vec<u8>, AppError> {
+        let mut file = tokio::fs::File::open(&self.path)
+            .await
+            .into_file_io_err_with("无法打开文件")?;
+
+        let mut hasher = D::new();
+        let mut buf = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            let n = file.read(&mut buf).await.into_file_io_err_with("无法读取文件")?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hasher.finalize().to_vec())
+    }
+
+    /// 把整个文件编码为 Base64 流（GitHub Contents API 需要 Base64 内容）
+    ///
+    /// 按 3 的整数倍读取，保证各块独立编码后拼接的结果与整体编码一致
+    pub fn base64_stream(&self) -> ByteResultStream {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+        // ReaderStream 每块长度不固定，先重新切分为 3 的整数倍
+        let mut raw = self.stream_range(0, self.size);
+        let mut carry: Vec<u8> = Vec::new();
+        let mut finished = false;
+        Box::pin(stream::poll_fn(move |cx| {
+            loop {
+                if finished {
+                    return Poll::Ready(None);
+                }
+                match raw.poll_next_unpin(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        carry.extend_from_slice(&chunk);
+                        let usable = carry.len() - carry.len() % 3;
+                        if usable == 0 {
+                            continue;
+                        }
+                        let rest = carry.split_off(usable);
+                        let encoded = STANDARD.encode(&carry);
+                        carry = rest;
+                        return Poll::Ready(Some(Ok(Bytes::from(encoded))));
+                    }
+                    Poll::Ready(Some(Err(e))) => {
+                        finished = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    Poll::Ready(None) => {
+                        finished = true;
+                        if carry.is_empty() {
+                            return Poll::Ready(None);
+                        }
+                        let encoded = STANDARD.encode(&carry);
+                        carry.clear();
+                        return Poll::Ready(Some(Ok(Bytes::from(encoded))));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }))
+    }
+
+    /// Base64 编码后的长度
+    pub fn base64_len(&self) -> u64 {
+        self.size.div_ceil(3) * 4
+    }
+}
+
+/// 读取文件头识别图片格式
+async fn sniff_file(path: &Path) -> Option<ImageType> {
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    file.take(HEADER_LEN as u64).read_to_end(&mut header).await.ok()?;
+    ImageType::sniff(&header)
+}
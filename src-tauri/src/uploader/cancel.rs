@@ -0,0 +1,55 @@
+// src-tauri/src/uploader/cancel.rs
+// 上传取消注册表
+//
+// 每个 UploadContext 创建时以任务 id 注册一个 CancellationToken，
+// 最后一个 UploadContext 副本释放时自动注销。
+// `cancel_upload(id)` 触发 token 后，`UploadContext::run` 包裹的 future 会被直接丢弃，
+// reqwest / aws-sdk 的在途请求随之中断。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tokio_util::sync::CancellationToken;
+
+/// 取消注册表（作为 Tauri 全局状态注册）
+#[derive(Default)]
+pub struct CancelRegistry {
+    /// id -> (注册序号, token)
+    tokens: Mutex<HashMap<String, (u64, CancellationToken)>>,
+    next_seq: AtomicU64,
+}
+
+impl CancelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册任务，返回注册序号（同 id 的旧任务会被覆盖）
+    pub fn register(&self, id: &str, token: CancellationToken) -> u64 {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let mut tokens = self.tokens.lock().unwrap_or_else(|p| p.into_inner());
+        tokens.insert(id.to_string(), (seq, token));
+        seq
+    }
+
+    /// 注销任务（只有序号一致时才移除，避免误删复用同一 id 的新任务）
+    pub fn unregister(&self, id: &str, seq: u64) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|p| p.into_inner());
+        if tokens.get(id).is_some_and(|(current, _)| *current == seq) {
+            tokens.remove(id);
+        }
+    }
+
+    /// 取消任务，返回是否找到了对应的任务
+    pub fn cancel(&self, id: &str) -> bool {
+        let tokens = self.tokens.lock().unwrap_or_else(|p| p.into_inner());
+        match tokens.get(id) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
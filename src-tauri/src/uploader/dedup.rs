@@ -0,0 +1,184 @@
+// src-tauri/src/uploader/dedup.rs
+// 内容去重缓存
+//
+// 以文件内容的 SHA-256 为键记录每个图床的上传结果（`upload_dedup` 表，与上传队列共用 history.db）。
+// 同一图床可以配置不同的上传位置（S3 的端点/存储桶/路径、GitHub 的仓库/分支/路径），
+// 键中还包含决定上传位置的配置摘要（`target`），修改配置后不会返回旧位置的链接。
+// 再次上传相同内容的文件（如同一张截图）时直接返回上次的结果，不再发起网络请求；
+// 上传上下文设置了 `force_fresh` 时跳过查找，上传成功后覆盖旧记录（见 `UploadContext::run_deduped`）。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+
+use super::body::FileBody;
+use crate::error::{AppError, IntoAppError};
+use crate::queue::store::now_millis;
+
+/// 内存中缓存的文件哈希数量上限（多图床上传同一文件时只需计算一次）
+const HASH_MEMO_LIMIT: usize = 256;
+
+const CREATE_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS upload_dedup (
+    sha256 TEXT NOT NULL,
+    service_id TEXT NOT NULL,
+    target TEXT NOT NULL DEFAULT '',
+    result_type TEXT NOT NULL,
+    url TEXT,
+    result TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (sha256, service_id, target, result_type)
+)
+"#;
+
+/// 决定上传位置的配置字段摘要（十六进制 SHA-256 的前 16 位）
+///
+/// 上传位置与配置无关的图床（如微博、京东）使用空字符串
+pub fn target_digest(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hex::encode(hasher.finalize())[..16].to_string()
+}
+
+/// 去重缓存（作为 Tauri 全局状态注册，未注册时上传不做去重）
+pub struct DedupCache {
+    pool: SqlitePool,
+    /// 文件指纹（路径 + 大小 + 修改时间）到 SHA-256 的映射
+    hashes: Mutex<HashMap<String, String>>,
+}
+
+impl DedupCache {
+    /// 在已打开的数据库上建表
+    pub async fn new(pool: SqlitePool) -> Result<Self, AppError> {
+        sqlx::query(CREATE_TABLE_SQL)
+            .execute(&pool)
+            .await
+            .into_storage_err_with("创建去重缓存表失败")?;
+
+        Ok(Self {
+            pool,
+            hashes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 计算文件内容的 SHA-256（十六进制）
+    pub async fn file_hash(&self, file_path: &str) -> Result<String, AppError> {
+        let file = FileBody::open(file_path).await?;
+        let fingerprint = file.fingerprint();
+
+        if let Some(hash) = self.hashes.lock().unwrap_or_else(|p| p.into_inner()).get(&fingerprint) {
+            return Ok(hash.clone());
+        }
+
+        let hash = hex::encode(file.digest::<Sha256>().await?);
+
+        let mut hashes = self.hashes.lock().unwrap_or_else(|p| p.into_inner());
+        if hashes.len() >= HASH_MEMO_LIMIT {
+            hashes.clear();
+        }
+        hashes.insert(fingerprint, hash.clone());
+        Ok(hash)
+    }
+
+    /// 查找该图床（同一上传位置）上次上传相同内容的结果
+    ///
+    /// 通用命令和各图床的旧命令返回的结果结构不同，按结果类型分开记录
+    pub async fn lookup<T: DeserializeOwned>(
+        &self,
+        sha256: &str,
+        service_id: &str,
+        target: &str,
+    ) -> Result<Option<T>, AppError> {
+        let row = sqlx::query(
+            "SELECT result FROM upload_dedup \
+             WHERE sha256 = ? AND service_id = ? AND target = ? AND result_type = ?",
+        )
+        .bind(sha256)
+        .bind(service_id)
+        .bind(target)
+        .bind(std::any::type_name::<T>())
+        .fetch_optional(&self.pool)
+        .await
+        .into_storage_err_with("读取去重缓存失败")?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let result: String = row.get("result");
+        // 结果结构变化后旧记录无法解析，视为未命中，上传成功后会被覆盖
+        Ok(serde_json::from_str(&result).ok())
+    }
+
+    /// 记录上传结果（覆盖同一内容、同一图床、同一上传位置的旧记录）
+    pub async fn record<T: Serialize>(
+        &self,
+        sha256: &str,
+        service_id: &str,
+        target: &str,
+        result: &T,
+    ) -> Result<(), AppError> {
+        let value = serde_json::to_value(result).into_storage_err_with("序列化上传结果失败")?;
+        let url = value.get("url").and_then(|v| v.as_str()).map(str::to_string);
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO upload_dedup (sha256, service_id, target, result_type, url, result, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(sha256)
+        .bind(service_id)
+        .bind(target)
+        .bind(std::any::type_name::<T>())
+        .bind(url)
+        .bind(value.to_string())
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await
+        .into_storage_err_with("写入去重缓存失败")?;
+        Ok(())
+    }
+
+    /// 相同内容在各图床的链接（服务 ID → URL）
+    pub async fn urls_for(&self, sha256: &str) -> Result<BTreeMap<String, String>, AppError> {
+        let rows = sqlx::query(
+            "SELECT service_id, url FROM upload_dedup WHERE sha256 = ? AND url IS NOT NULL \
+             ORDER BY created_at",
+        )
+        .bind(sha256)
+        .fetch_all(&self.pool)
+        .await
+        .into_storage_err_with("读取去重缓存失败")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("service_id"), row.get("url")))
+            .collect())
+    }
+
+    /// 删除指向某个链接的记录（图床上的文件已被删除时调用），返回删除的数量
+    pub async fn forget_url(&self, service_id: &str, url: &str) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM upload_dedup WHERE service_id = ? AND url = ?")
+            .bind(service_id)
+            .bind(url)
+            .execute(&self.pool)
+            .await
+            .into_storage_err_with("删除去重缓存失败")?;
+        Ok(result.rows_affected())
+    }
+
+    /// 清空缓存，返回删除的数量
+    pub async fn clear(&self) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM upload_dedup")
+            .execute(&self.pool)
+            .await
+            .into_storage_err_with("清空去重缓存失败")?;
+        Ok(result.rows_affected())
+    }
+}
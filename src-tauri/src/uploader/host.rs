@@ -0,0 +1,121 @@
+// src-tauri/src/uploader/host.rs
+// 上传运行环境
+//
+// 上传上下文通过 `UploadHost` 发送进度事件、查找共享状态（限流、去重、图片处理等）、
+// 定位应用数据目录和启动 sidecar。桌面程序由 AppHandle 实现：事件发给前端，状态来自 Tauri 全局状态；
+// 命令行工具（cli.rs）自行持有这些状态，在普通的 tokio 运行时中上传，不需要创建 Tauri 应用。
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+
+use super::cancel::CancelRegistry;
+use super::dedup::DedupCache;
+use super::limiter::RateLimiter;
+use super::processing::ImageProcessor;
+use super::registry::UploaderRegistry;
+use crate::error::{AppError, IntoAppError};
+
+/// sidecar 进程的输出
+#[derive(Debug, Default)]
+pub struct SidecarOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// 上传运行环境（未提供的状态返回 None，对应的功能跳过，如不做限流、不做去重）
+#[async_trait]
+pub trait UploadHost: Send + Sync {
+    /// 发送事件（如 `upload://progress`），没有前端时直接丢弃
+    fn emit_event(&self, event: &str, payload: serde_json::Value);
+
+    fn rate_limiter(&self) -> Option<&RateLimiter>;
+
+    fn dedup_cache(&self) -> Option<&DedupCache>;
+
+    fn image_processor(&self) -> Option<&ImageProcessor>;
+
+    fn uploader_registry(&self) -> Option<&UploaderRegistry>;
+
+    fn cancel_registry(&self) -> Option<&CancelRegistry>;
+
+    /// 应用数据目录（续传记录等保存在这里）
+    fn app_data_dir(&self) -> Result<PathBuf, AppError>;
+
+    /// 运行 sidecar（七鱼、纳米的 Token 获取程序），等待退出并返回全部输出
+    async fn run_sidecar(&self, name: &str, args: &[&str]) -> Result<SidecarOutput, AppError>;
+}
+
+/// 序列化并发送事件
+pub fn emit<T: Serialize>(host: &dyn UploadHost, event: &str, payload: T) {
+    match serde_json::to_value(payload) {
+        Ok(payload) => host.emit_event(event, payload),
+        Err(e) => eprintln!("[Uploader] 序列化事件 {} 失败: {}", event, e),
+    }
+}
+
+#[async_trait]
+impl UploadHost for AppHandle {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) {
+        let _ = Emitter::emit(self, event, payload);
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.try_state::<RateLimiter>().map(|state| state.inner())
+    }
+
+    fn dedup_cache(&self) -> Option<&DedupCache> {
+        self.try_state::<DedupCache>().map(|state| state.inner())
+    }
+
+    fn image_processor(&self) -> Option<&ImageProcessor> {
+        self.try_state::<ImageProcessor>().map(|state| state.inner())
+    }
+
+    fn uploader_registry(&self) -> Option<&UploaderRegistry> {
+        self.try_state::<UploaderRegistry>().map(|state| state.inner())
+    }
+
+    fn cancel_registry(&self) -> Option<&CancelRegistry> {
+        self.try_state::<CancelRegistry>().map(|state| state.inner())
+    }
+
+    fn app_data_dir(&self) -> Result<PathBuf, AppError> {
+        self.path()
+            .app_data_dir()
+            .map_err(|e| AppError::file_io(format!("无法获取应用数据目录: {}", e)))
+    }
+
+    async fn run_sidecar(&self, name: &str, args: &[&str]) -> Result<SidecarOutput, AppError> {
+        let sidecar = self.shell()
+            .sidecar(name)
+            .into_external_err_with("创建 sidecar 失败")?;
+
+        let (mut rx, _child) = sidecar
+            .args(args)
+            .spawn()
+            .into_external_err_with("启动 sidecar 失败")?;
+
+        let mut output = SidecarOutput::default();
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(line) => {
+                    output.stdout.push_str(&String::from_utf8_lossy(&line));
+                }
+                CommandEvent::Stderr(line) => {
+                    output.stderr.push_str(&String::from_utf8_lossy(&line));
+                    output.stderr.push('\n');
+                }
+                CommandEvent::Terminated(status) => {
+                    println!("[Sidecar] {} 退出，状态: {:?}", name, status);
+                }
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+}
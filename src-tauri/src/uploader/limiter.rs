@@ -0,0 +1,224 @@
+// src-tauri/src/uploader/limiter.rs
+// 按图床的并发与频率限制
+//
+// 每个图床一份预算：同时进行的上传数（信号量）和每分钟请求数（令牌桶）。
+// 所有上传在每次尝试前都要在这里排队（见 `UploadContext::run_limited`），
+// 前端多图床上传、后台队列、托盘上传同时进行时，也不会对同一个图床突发大量请求。
+// 默认预算见 `default_budget`，用户可以在配置 `rateLimits` 中按图床覆盖，保存配置后立即生效。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::progress::emit_queued;
+use super::UploadContext;
+use crate::error::AppError;
+use crate::secure_config;
+
+/// 单个图床的请求预算
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceBudget {
+    /// 每分钟最多发起的上传请求数，0 表示不限制
+    pub requests_per_minute: u32,
+    /// 同时进行的上传数上限（至少为 1）
+    pub max_concurrent: u32,
+}
+
+/// 内置默认预算
+///
+/// 公共图床（借用各网站的上传接口）容易触发风控，限制较严；自有存储（S3、GitHub 等）较宽松
+pub fn default_budget(service_id: &str) -> ServiceBudget {
+    let (requests_per_minute, max_concurrent) = match service_id {
+        // 与前端 JDRateLimiter 一致：串行，每秒最多一次
+        "jd" => (60, 1),
+        "weibo" | "nowcoder" | "zhihu" | "bilibili" | "chaoxing" | "qiyu" | "nami" => (30, 2),
+        "smms" | "imgur" => (20, 2),
+        _ => (120, 4),
+    };
+    ServiceBudget {
+        requests_per_minute,
+        max_concurrent,
+    }
+}
+
+/// 令牌桶（桶容量等于并发上限，允许并发数以内的突发）
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    /// 每秒补充的令牌数，0 表示不限制
+    refill_per_sec: f64,
+    updated_at: Instant,
+    /// 服务端要求的冷却结束时间（收到 Retry-After 时设置）
+    blocked_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(budget: ServiceBudget) -> Self {
+        let capacity = budget.max_concurrent.max(1) as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: budget.requests_per_minute as f64 / 60.0,
+            updated_at: Instant::now(),
+            blocked_until: None,
+        }
+    }
+
+    /// 预订一个令牌，返回需要等待的时间
+    ///
+    /// 令牌数可以为负（表示已被后面排队的请求预订），每个请求只需等待一次，不需要轮询
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let cooldown = self
+            .blocked_until
+            .map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+
+        if self.refill_per_sec <= 0.0 {
+            return cooldown;
+        }
+
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated_at = now;
+        self.tokens -= 1.0;
+
+        let wait = if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_sec)
+        };
+        wait.max(cooldown)
+    }
+
+    fn block_for(&mut self, duration: Duration) {
+        let until = Instant::now() + duration;
+        if self.blocked_until.is_none_or(|current| current < until) {
+            self.blocked_until = Some(until);
+        }
+    }
+}
+
+/// 单个图床的限流器
+struct ServiceLimiter {
+    budget: ServiceBudget,
+    semaphore: Arc<Semaphore>,
+    bucket: Mutex<TokenBucket>,
+}
+
+impl ServiceLimiter {
+    fn new(budget: ServiceBudget) -> Self {
+        Self {
+            budget,
+            semaphore: Arc::new(Semaphore::new(budget.max_concurrent.max(1) as usize)),
+            bucket: Mutex::new(TokenBucket::new(budget)),
+        }
+    }
+}
+
+/// 上传许可，释放时归还并发名额
+pub struct LimitPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 全局限流器（作为 Tauri 全局状态注册）
+#[derive(Default)]
+pub struct RateLimiter {
+    services: Mutex<HashMap<String, Arc<ServiceLimiter>>>,
+    /// 用户配置的预算（覆盖默认值）
+    overrides: Mutex<HashMap<String, ServiceBudget>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 图床当前生效的预算
+    pub fn budget(&self, service_id: &str) -> ServiceBudget {
+        let overrides = self.overrides.lock().unwrap_or_else(|p| p.into_inner());
+        overrides
+            .get(service_id)
+            .copied()
+            .unwrap_or_else(|| default_budget(service_id))
+    }
+
+    /// 替换用户配置的预算
+    ///
+    /// 预算变化的图床会换用新的限流器，正在进行的上传仍在旧限流器上完成
+    pub fn set_budgets(&self, budgets: HashMap<String, ServiceBudget>) {
+        *self.overrides.lock().unwrap_or_else(|p| p.into_inner()) = budgets;
+
+        let mut services = self.services.lock().unwrap_or_else(|p| p.into_inner());
+        services.retain(|service_id, limiter| limiter.budget == self.budget(service_id));
+    }
+
+    /// 应用用户配置中的 `rateLimits`，格式错误时保留当前预算
+    pub fn apply_config(&self, user_config: &serde_json::Value) {
+        match secure_config::section::<HashMap<String, ServiceBudget>>(user_config, "rateLimits") {
+            Ok(budgets) => {
+                if !budgets.is_empty() {
+                    println!("[RateLimiter] 已加载 {} 个图床的自定义限流配置", budgets.len());
+                }
+                self.set_budgets(budgets);
+            }
+            Err(e) => eprintln!("[RateLimiter] rateLimits 配置格式错误: {}", e),
+        }
+    }
+
+    fn limiter(&self, service_id: &str) -> Arc<ServiceLimiter> {
+        let budget = self.budget(service_id);
+        let mut services = self.services.lock().unwrap_or_else(|p| p.into_inner());
+        services
+            .entry(service_id.to_string())
+            .or_insert_with(|| Arc::new(ServiceLimiter::new(budget)))
+            .clone()
+    }
+
+    /// 等待并发名额和请求令牌
+    ///
+    /// 需要等待时通过进度事件通知前端（`queue_wait`），实际等待时间计入上传上下文
+    pub async fn acquire(&self, ctx: &UploadContext, service_id: &str) -> Result<LimitPermit, AppError> {
+        let limiter = self.limiter(service_id);
+        let started = Instant::now();
+
+        // 1. 并发名额
+        let permit = match limiter.semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                println!(
+                    "[RateLimiter] {} 并发已满 ({})，排队等待: {}",
+                    service_id, limiter.budget.max_concurrent, ctx.id
+                );
+                emit_queued(ctx, "等待其他上传完成...", started.elapsed());
+                limiter
+                    .semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| AppError::external("上传限流器已关闭"))?
+            }
+        };
+
+        // 2. 请求频率
+        let wait = limiter.bucket.lock().unwrap_or_else(|p| p.into_inner()).reserve();
+        if !wait.is_zero() {
+            println!("[RateLimiter] {} 请求过于频繁，等待 {}ms: {}", service_id, wait.as_millis(), ctx.id);
+            emit_queued(ctx, "请求频率限制，排队中...", started.elapsed() + wait);
+            tokio::time::sleep(wait).await;
+        }
+
+        ctx.add_queue_wait(started.elapsed());
+        Ok(LimitPermit { _permit: permit })
+    }
+
+    /// 服务端要求等待（Retry-After）时暂停该图床的所有新请求
+    pub fn back_off(&self, service_id: &str, duration: Duration) {
+        let limiter = self.limiter(service_id);
+        limiter.bucket.lock().unwrap_or_else(|p| p.into_inner()).block_for(duration);
+    }
+}
+
@@ -0,0 +1,283 @@
+// src-tauri/src/uploader/metadata.rs
+// 图片元数据清理（不重新编码像素）
+//
+// 手机照片通常带有 GPS 坐标、设备型号和序列号等信息，上传到公开图床前按格式删除对应的数据块：
+// - JPEG：APP1（EXIF / XMP）、APP3-APP13（IPTC 等厂商数据）、APP15、COM，以及 EOI 之后的附加数据；
+//   保留 JFIF（APP0）、ICC 配置（APP2 ICC_PROFILE）和 Adobe（APP14），避免颜色显示异常；
+//   EXIF 中的方向不为 1 时换成只含方向的最小 EXIF，像素未旋转的照片仍按正确方向显示
+// - PNG：tEXt / zTXt / iTXt / eXIf / tIME
+// - WebP：EXIF / XMP 块，并同步清除 VP8X 中对应的标志位
+//
+// 只删除整块数据，压缩的图像数据原样复制，画质不受影响。
+// 另外提供重新编码时保留 JPEG EXIF 的辅助函数（方向校正后重置方向标记，见 processing.rs）。
+
+/// 清理元数据，返回清理后的文件内容；没有需要删除的数据或文件结构无法识别时返回 None
+pub fn strip(ext: &str, data: &[u8]) -> Option<Vec<u8>> {
+    match ext {
+        "jpg" | "jpeg" => strip_jpeg(data),
+        "png" => strip_png(data),
+        "webp" => strip_webp(data),
+        _ => None,
+    }
+}
+
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..2]);
+    let mut removed = false;
+    let mut pos = 2;
+
+    loop {
+        if pos + 2 > data.len() || data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+
+        // 标记前的填充字节
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+
+        // SOS 之后是压缩数据（渐进式 JPEG 中间还有表定义），原样复制到 EOI 为止
+        if marker == 0xDA {
+            let eoi = data[pos..]
+                .windows(2)
+                .position(|w| w == [0xFF, 0xD9])
+                .map(|i| pos + i + 2)?;
+            out.extend_from_slice(&data[pos..eoi]);
+            // EOI 之后的附加数据（如多图格式的深度图、厂商尾部信息）一并删除
+            removed |= eoi < data.len();
+            break;
+        }
+        if marker == 0xD9 {
+            return None;
+        }
+
+        if pos + 4 > data.len() {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > data.len() {
+            return None;
+        }
+
+        let payload = &data[pos + 4..end];
+        let strip = match marker {
+            0xE2 => !payload.starts_with(b"ICC_PROFILE\0"),
+            0xE1 | 0xE3..=0xED | 0xEF | 0xFE => true,
+            _ => false,
+        };
+        if strip {
+            removed = true;
+            // 方向只保存在 EXIF 中，删除后旋转拍摄的照片会横着显示
+            if marker == 0xE1 && payload.starts_with(b"Exif\0\0") {
+                let orientation = read_tiff_orientation(&payload[6..]).filter(|&o| (2..=8).contains(&o));
+                if let Some(orientation) = orientation {
+                    out.extend_from_slice(&orientation_exif_segment(orientation));
+                }
+            }
+        } else {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+    }
+
+    removed.then_some(out)
+}
+
+/// 取出 JPEG 的 EXIF 段（APP1，含标记和长度），并把其中的方向重置为 1（正常方向）
+///
+/// 重新编码时像素已按方向旋转，保留原 EXIF 时需要同时重置方向，否则查看器会再旋转一次
+pub fn jpeg_exif_reset_orientation(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        if matches!(marker, 0xDA | 0xD9) {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > data.len() {
+            return None;
+        }
+
+        // 标记（2）+ 长度（2）+ "Exif\0\0"（6）之后是 TIFF 数据
+        if marker == 0xE1 && data[pos + 4..end].starts_with(b"Exif\0\0") {
+            let mut segment = data[pos..end].to_vec();
+            reset_tiff_orientation(&mut segment[10..]);
+            return Some(segment);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// 在 JPEG 的 SOI 之后插入数据段
+pub fn insert_jpeg_segment(data: &[u8], segment: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + segment.len());
+    out.extend_from_slice(&data[..2]);
+    out.extend_from_slice(segment);
+    out.extend_from_slice(&data[2..]);
+    out
+}
+
+/// 把 TIFF 结构 IFD0 中的方向（0x0112）改为 1
+fn reset_tiff_orientation(tiff: &mut [u8]) {
+    let Some((value, big_endian)) = find_tiff_orientation(tiff) else {
+        return;
+    };
+    let one = if big_endian { 1u16.to_be_bytes() } else { 1u16.to_le_bytes() };
+    tiff[value..value + 2].copy_from_slice(&one);
+}
+
+/// 读取 TIFF 结构 IFD0 中的方向
+fn read_tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let (value, big_endian) = find_tiff_orientation(tiff)?;
+    let bytes = [tiff[value], tiff[value + 1]];
+    Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+}
+
+/// 查找 IFD0 中方向（0x0112）的值所在位置和字节序
+///
+/// 方向为 SHORT 类型，值直接存放在条目的值字段（条目偏移 + 8）中
+fn find_tiff_orientation(tiff: &[u8]) -> Option<(usize, bool)> {
+    let big_endian = match tiff.get(0..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let read_u16 = |b: &[u8]| {
+        if big_endian {
+            u16::from_be_bytes([b[0], b[1]])
+        } else {
+            u16::from_le_bytes([b[0], b[1]])
+        }
+    };
+    let ifd = tiff.get(4..8).map(|b| {
+        if big_endian {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        }
+    })? as usize;
+    let count = tiff.get(ifd..ifd.checked_add(2)?).map(read_u16)?;
+
+    for i in 0..count as usize {
+        let entry = ifd + 2 + i * 12;
+        let tag = tiff.get(entry..entry + 2).map(read_u16)?;
+        if tag == 0x0112 {
+            return tiff.get(entry + 8..entry + 10).map(|_| (entry + 8, big_endian));
+        }
+    }
+    None
+}
+
+/// 只含方向的 EXIF 段（APP1，大端 TIFF，IFD0 中只有一个条目）
+fn orientation_exif_segment(orientation: u16) -> Vec<u8> {
+    let mut tiff = Vec::with_capacity(26);
+    tiff.extend_from_slice(b"MM\0\x2A");
+    tiff.extend_from_slice(&8u32.to_be_bytes()); // IFD0 偏移
+    tiff.extend_from_slice(&1u16.to_be_bytes()); // 条目数
+    tiff.extend_from_slice(&0x0112u16.to_be_bytes());
+    tiff.extend_from_slice(&3u16.to_be_bytes()); // SHORT
+    tiff.extend_from_slice(&1u32.to_be_bytes()); // 数量
+    tiff.extend_from_slice(&orientation.to_be_bytes());
+    tiff.extend_from_slice(&[0, 0]); // 值字段补齐 4 字节
+    tiff.extend_from_slice(&0u32.to_be_bytes()); // 没有下一个 IFD
+
+    let len = (2 + 6 + tiff.len()) as u16;
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&len.to_be_bytes());
+    segment.extend_from_slice(b"Exif\0\0");
+    segment.extend_from_slice(&tiff);
+    segment
+}
+
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !data.starts_with(SIGNATURE) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(SIGNATURE);
+    let mut removed = false;
+    let mut pos = SIGNATURE.len();
+
+    while pos < data.len() {
+        if pos + 8 > data.len() {
+            return None;
+        }
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        // 长度 + 类型 + 数据 + CRC
+        let end = pos.checked_add(12 + len).filter(|&end| end <= data.len())?;
+        let kind = &data[pos + 4..pos + 8];
+
+        if matches!(kind, b"tEXt" | b"zTXt" | b"iTXt" | b"eXIf" | b"tIME") {
+            removed = true;
+        } else {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+
+        if kind == b"IEND" {
+            break;
+        }
+    }
+
+    removed.then_some(out)
+}
+
+fn strip_webp(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..12]);
+    let mut removed = false;
+    let mut vp8x_flags = None;
+    let mut pos = 12;
+
+    while pos + 8 <= data.len() {
+        let kind = &data[pos..pos + 4];
+        let len = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        // 块数据按偶数字节对齐
+        let end = pos.checked_add(8 + len + (len & 1))?.min(data.len());
+        if pos + 8 + len > data.len() {
+            return None;
+        }
+
+        if matches!(kind, b"EXIF" | b"XMP ") {
+            removed = true;
+        } else {
+            if kind == b"VP8X" && len > 0 {
+                vp8x_flags = Some(out.len() + 8);
+            }
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+    }
+
+    if !removed {
+        return None;
+    }
+
+    // VP8X 标志位：0x08 EXIF，0x04 XMP
+    if let Some(flags) = vp8x_flags {
+        out[flags] &= !0x0C;
+    }
+    let riff_size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
@@ -0,0 +1,500 @@
+// src-tauri/src/uploader/mod.rs
+// 统一上传器抽象
+//
+// 每个图床实现 `Uploader` trait，并在 `registry` 中按服务 ID 注册。
+// 前端只需调用通用的 `upload(service_id, file_path, config)` 命令，
+// 新增图床时不再需要改动 main.rs 的 generate_handler! 和对应的 TS 上传器。
+
+pub mod body;
+pub mod cancel;
+pub mod dedup;
+pub mod host;
+pub mod limiter;
+pub mod metadata;
+pub mod orchestrator;
+pub mod processing;
+pub mod progress;
+pub mod registry;
+pub mod resume;
+pub mod retry;
+pub mod watermark;
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use host::UploadHost;
+use processing::PreparedFile;
+use retry::RetryPolicy;
+
+use crate::error::AppError;
+use crate::image_format;
+
+/// 图床能力描述
+///
+/// 用于上传前的统一校验，也会原样返回给前端用于 UI 展示
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploaderCapabilities {
+    /// 单文件大小上限（字节），None 表示不限制
+    pub max_file_size: Option<u64>,
+    /// GIF 单独的大小上限（如 Imgur 允许 200MB 的 GIF），None 表示沿用 `max_file_size`
+    pub max_gif_size: Option<u64>,
+    /// 接受的文件扩展名（小写），为空表示不限制格式
+    pub accepted_formats: &'static [&'static str],
+    /// 是否支持删除已上传的文件
+    pub supports_delete: bool,
+}
+
+impl UploaderCapabilities {
+    /// 获取指定扩展名对应的大小上限
+    pub fn max_size_for(&self, ext: &str) -> Option<u64> {
+        if ext == "gif" {
+            self.max_gif_size.or(self.max_file_size)
+        } else {
+            self.max_file_size
+        }
+    }
+
+    /// 是否接受该扩展名
+    pub fn accepts_format(&self, ext: &str) -> bool {
+        self.accepted_formats.is_empty() || self.accepted_formats.contains(&ext)
+    }
+
+    /// 校验文件格式和大小是否满足该图床的限制
+    pub fn check(&self, service_name: &str, ext: &str, file_size: u64) -> Result<(), AppError> {
+        if !self.accepts_format(ext) {
+            return Err(AppError::validation(format!(
+                "{} 不支持 .{} 格式，仅支持: {}",
+                service_name,
+                ext,
+                self.accepted_formats.join("、")
+            )));
+        }
+
+        if let Some(max_size) = self.max_size_for(ext) {
+            if file_size > max_size {
+                return Err(AppError::validation(format!(
+                    "文件大小 ({:.2}MB) 超过 {} 限制 ({:.0}MB)",
+                    file_size as f64 / 1024.0 / 1024.0,
+                    service_name,
+                    max_size as f64 / 1024.0 / 1024.0
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 单次上传的运行时上下文
+///
+/// 通过 `UploadHost` 发送事件和查找共享状态：桌面程序中为 AppHandle（窗口隐藏到托盘时也能发送事件），
+/// 命令行工具中为不依赖 Tauri 应用的实现
+#[derive(Clone)]
+pub struct UploadContext {
+    pub host: Arc<dyn UploadHost>,
+    /// 上传任务 ID（与前端进度事件的 id 对应）
+    pub id: String,
+    /// 全局 HTTP 客户端（复用连接池）
+    pub http_client: reqwest::Client,
+    /// 取消令牌，`cancel_upload(id)` 时触发
+    pub cancel: CancellationToken,
+    /// 瞬时错误的重试策略
+    pub retry: RetryPolicy,
+    /// 跳过去重缓存，强制重新上传
+    pub force_fresh: bool,
+    /// 在限流队列中累计等待的时间（毫秒）
+    queue_wait: Arc<AtomicU64>,
+    /// 取消注册凭证，最后一个副本释放时自动从注册表注销
+    _registration: Arc<CancelRegistration>,
+}
+
+impl UploadContext {
+    pub fn new(host: impl UploadHost + 'static, id: impl Into<String>, http_client: reqwest::Client) -> Self {
+        Self::with_token(Arc::new(host), id.into(), http_client, CancellationToken::new())
+    }
+
+    /// 创建子任务上下文（如多图床上传中的单个图床）
+    ///
+    /// 取消父任务会同时取消所有子任务，子任务也可以通过自己的 id 单独取消
+    pub fn child(&self, id: impl Into<String>) -> Self {
+        Self::with_token(
+            self.host.clone(),
+            id.into(),
+            self.http_client.clone(),
+            self.cancel.child_token(),
+        )
+        .with_retry(self.retry)
+        .with_force_fresh(self.force_fresh)
+    }
+
+    /// 指定重试策略
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 是否跳过去重缓存
+    pub fn with_force_fresh(mut self, force_fresh: bool) -> Self {
+        self.force_fresh = force_fresh;
+        self
+    }
+
+    fn with_token(
+        host: Arc<dyn UploadHost>,
+        id: String,
+        http_client: reqwest::Client,
+        cancel: CancellationToken,
+    ) -> Self {
+        let seq = host
+            .cancel_registry()
+            .map(|registry| registry.register(&id, cancel.clone()));
+        let registration = Arc::new(CancelRegistration {
+            host: host.clone(),
+            id: id.clone(),
+            seq,
+            notified: AtomicBool::new(false),
+        });
+
+        Self {
+            host,
+            id,
+            http_client,
+            cancel,
+            retry: RetryPolicy::default(),
+            force_fresh: false,
+            queue_wait: Arc::new(AtomicU64::new(0)),
+            _registration: registration,
+        }
+    }
+
+    /// 是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// 在步骤之间检查取消状态
+    pub fn check_cancelled(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(self.cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 运行可取消的 future
+    ///
+    /// 取消时直接丢弃 future（中断在途的网络请求），发送 `upload://cancelled` 事件
+    /// 并返回 `AppError::Cancelled`。需要清理服务端状态的实现（如分片上传）
+    /// 应使用 drop guard，保证 future 被丢弃时也能触发清理。
+    pub async fn run<T, F>(&self, fut: F) -> Result<T, AppError>
+    where
+        F: Future<Output = Result<T, AppError>>,
+    {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(self.cancelled_error()),
+            result = fut => result,
+        }
+    }
+
+    /// 运行可取消的上传：每次尝试前按 `service_id` 的预算排队（见 limiter.rs），
+    /// 瞬时错误（网络中断、5xx、限流）按 `self.retry` 重试
+    ///
+    /// 每次尝试都重新调用 `op` 从头开始；分片上传的实现会从续传记录继续，不会重复发送已完成的分片。
+    /// 自行重试单个分片的实现（纳米、七鱼）在分片重试用尽后返回不可重试的错误（见 `retry::exhausted`），
+    /// 这里只重试初始化、完成等步骤的瞬时错误。
+    /// 服务端返回 Retry-After 时，同一图床的其他上传也会一起暂停
+    pub async fn run_limited<T, F, Fut>(&self, service_id: &str, mut op: F) -> Result<T, AppError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let limiter = self.host.rate_limiter();
+
+        let attempt = || {
+            let fut = op();
+            async move {
+                let _permit = match limiter {
+                    Some(limiter) => Some(limiter.acquire(self, service_id).await?),
+                    None => None,
+                };
+                let result = fut.await;
+                if let (Some(limiter), Err(e)) = (limiter, &result) {
+                    if let Some(retry_after) = e.retry_after() {
+                        limiter.back_off(service_id, retry_after);
+                    }
+                }
+                result
+            }
+        };
+
+        self.run(self.retry.run(&self.id, attempt)).await
+    }
+
+    /// 先查去重缓存，相同内容已上传到该图床时直接返回上次的结果，否则按 `run_limited` 上传并记录结果
+    ///
+    /// 缓存不可用（未注册、读写失败）时只记录日志，不影响上传
+    pub async fn run_deduped<T, F, Fut>(&self, service_id: &str, file_path: &str, op: F) -> Result<T, AppError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        self.run_deduped_at(service_id, "", file_path, op).await
+    }
+
+    /// 同 `run_deduped`，`target` 为决定上传位置的配置摘要（见 `dedup::target_digest`），
+    /// 只有上传到同一位置时才复用上次的结果
+    pub async fn run_deduped_at<T, F, Fut>(
+        &self,
+        service_id: &str,
+        target: &str,
+        file_path: &str,
+        op: F,
+    ) -> Result<T, AppError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let Some(cache) = self.host.dedup_cache() else {
+            return self.run_limited(service_id, op).await;
+        };
+
+        let hash = match self.run(cache.file_hash(file_path)).await {
+            Ok(hash) => Some(hash),
+            Err(e) if e.is_cancelled() => return Err(e),
+            Err(e) => {
+                eprintln!("[Dedup] 计算文件哈希失败，跳过去重: {}", e);
+                None
+            }
+        };
+
+        if let (Some(hash), false) = (&hash, self.force_fresh) {
+            match cache.lookup::<T>(hash, service_id, target).await {
+                Ok(Some(cached)) => {
+                    println!("[Dedup] {} 已上传过相同内容，直接返回上次的结果: {}", service_id, file_path);
+                    return Ok(cached);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("[Dedup] {}", e),
+            }
+        }
+
+        let result = self.run_limited(service_id, op).await?;
+
+        if let Some(hash) = &hash {
+            if let Err(e) = cache.record(hash, service_id, target, &result).await {
+                eprintln!("[Dedup] {}", e);
+            }
+        }
+        Ok(result)
+    }
+
+    /// 按用户配置对待上传的图片做压缩、格式转换和元数据清理（见 processing.rs），返回实际要上传的文件
+    ///
+    /// 处理失败时上传原文件；需要加水印但处理失败时返回错误（见 `ImageProcessor::prepare`）
+    pub async fn prepare_file(&self, service_id: &str, file_path: &str) -> Result<PreparedFile, AppError> {
+        let Some(processor) = self.host.image_processor() else {
+            return Ok(PreparedFile::original(file_path));
+        };
+        let capabilities = self
+            .host
+            .uploader_registry()
+            .and_then(|registry| registry.get(service_id).ok())
+            .map(|uploader| uploader.capabilities());
+
+        self.run(processor.prepare(service_id, file_path, capabilities.as_ref())).await
+    }
+
+    /// 累计在限流队列中等待的时间
+    pub fn add_queue_wait(&self, wait: Duration) {
+        self.queue_wait.fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// 在限流队列中累计等待的时间（毫秒）
+    pub fn queue_wait_ms(&self) -> u64 {
+        self.queue_wait.load(Ordering::Relaxed)
+    }
+
+    fn cancelled_error(&self) -> AppError {
+        // 嵌套的 run / check_cancelled 可能多次走到这里，事件只发送一次
+        if !self._registration.notified.swap(true, Ordering::SeqCst) {
+            println!("[Uploader] 任务已取消: {}", self.id);
+            self.host.emit_event("upload://cancelled", serde_json::json!({ "id": self.id }));
+        }
+        AppError::cancelled("上传已被用户取消")
+    }
+}
+
+/// 取消注册凭证
+struct CancelRegistration {
+    host: Arc<dyn UploadHost>,
+    id: String,
+    /// 未注册 CancelRegistry 状态时为 None
+    seq: Option<u64>,
+    /// 是否已发送过 `upload://cancelled` 事件
+    notified: AtomicBool,
+}
+
+impl Drop for CancelRegistration {
+    fn drop(&mut self) {
+        if let (Some(seq), Some(registry)) = (self.seq, self.host.cancel_registry()) {
+            registry.unregister(&self.id, seq);
+        }
+    }
+}
+
+/// 统一上传结果
+///
+/// 字段与前端 `UploadResult` 接口保持一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadOutput {
+    /// 所属图床标识
+    pub service_id: String,
+    /// 文件唯一标识（微博：PID，S3：Key，其他图床：各自的标识符）
+    pub file_key: String,
+    /// 公开访问链接
+    pub url: String,
+    /// 文件大小（字节）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// 图床特定的额外信息（删除凭证、sha、宽高等）
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl UploadOutput {
+    pub fn new(service_id: &str, file_key: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            service_id: service_id.to_string(),
+            file_key: file_key.into(),
+            url: url.into(),
+            size: None,
+            metadata: serde_json::Map::new(),
+        }
+    }
+
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_meta(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// 读取字符串类型的元数据
+    pub fn meta_str(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).and_then(|v| v.as_str())
+    }
+}
+
+/// 图床上传器
+///
+/// 实现说明：
+/// - `id` 与前端 ServiceType 保持一致（小写英文，如 'weibo'、'r2'）
+/// - `config` 为前端 `config.services[serviceId]` 的原始 JSON，由实现自行反序列化
+/// - 不支持删除的图床无需实现 `delete`
+#[async_trait]
+pub trait Uploader: Send + Sync {
+    /// 图床服务唯一标识符
+    fn id(&self) -> &'static str;
+
+    /// 图床服务显示名称
+    fn name(&self) -> &'static str;
+
+    /// 图床能力描述
+    fn capabilities(&self) -> UploaderCapabilities;
+
+    /// 上传文件
+    async fn upload(
+        &self,
+        ctx: &UploadContext,
+        file_path: &str,
+        config: &serde_json::Value,
+    ) -> Result<UploadOutput, AppError>;
+
+    /// 决定上传位置的配置摘要（去重缓存按此区分，见 `dedup::target_digest`）
+    ///
+    /// 上传位置与配置无关的图床返回空字符串
+    fn dedup_target(&self, _config: &serde_json::Value) -> String {
+        String::new()
+    }
+
+    /// 删除已上传的文件
+    async fn delete(
+        &self,
+        _ctx: &UploadContext,
+        _output: &UploadOutput,
+        _config: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        Err(AppError::validation(format!("{} 不支持删除操作", self.name())))
+    }
+}
+
+/// 将前端传入的 JSON 配置反序列化为图床配置结构
+pub fn parse_config<T: serde::de::DeserializeOwned>(
+    service_name: &str,
+    config: &serde_json::Value,
+) -> Result<T, AppError> {
+    serde_json::from_value(config.clone())
+        .map_err(|e| AppError::config(format!("{} 配置无效: {}", service_name, e)))
+}
+
+/// 提取文件名和小写扩展名
+pub fn file_name_and_ext(file_path: &str) -> Result<(String, String), AppError> {
+    let file_name = std::path::Path::new(file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::validation("无法获取文件名"))?;
+
+    let ext = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+
+    Ok((file_name.to_string(), ext))
+}
+
+/// 执行单个图床上传：先按能力描述统一校验格式和大小，失败时无需发起网络请求
+pub async fn run_upload(
+    uploader: &dyn Uploader,
+    ctx: &UploadContext,
+    file_path: &str,
+    config: &serde_json::Value,
+) -> Result<UploadOutput, AppError> {
+    let prepared = ctx.prepare_file(uploader.id(), file_path).await?;
+    let file_path = prepared.path();
+
+    // 按文件内容判断格式，避免改错扩展名的文件绕过格式限制
+    let ext = image_format::detect_ext(file_path);
+    let file_size = tokio::fs::metadata(file_path)
+        .await
+        .map_err(|e| AppError::file_io(format!("无法读取文件信息: {}", e)))?
+        .len();
+    uploader.capabilities().check(uploader.name(), &ext, file_size)?;
+
+    println!("[Uploader] {} 开始上传: {}", uploader.name(), file_path);
+    let output = ctx
+        .run_deduped_at(uploader.id(), &uploader.dedup_target(config), file_path, || {
+            uploader.upload(ctx, file_path, config)
+        })
+        .await?;
+
+    // 处理前后的大小随结果返回，写入历史记录
+    Ok(match prepared.summary() {
+        Some(summary) => output.with_meta("processing", serde_json::to_value(summary).unwrap_or_default()),
+        None => output,
+    })
+}
@@ -0,0 +1,295 @@
+// src-tauri/src/uploader/orchestrator.rs
+// 多图床并行上传编排器（Rust 端）
+//
+// 对应前端 src/core/MultiServiceUploader.ts 的逻辑：
+// 一个文件并行上传到多个图床，第一个成功的（按传入顺序）作为主力图床，其余作为备份。
+// 整个流程只依赖 `UploadHost`，不依赖 webview，窗口隐藏到托盘时和命令行工具中也能完成。
+
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+
+use super::host::{self, UploadHost};
+use super::registry::UploaderRegistry;
+use super::retry::RetryPolicy;
+use super::{run_upload, UploadContext, UploadOutput};
+use crate::error::{AppError, ErrorClass};
+
+/// 默认最大并发上传数
+pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// 多图床上传请求
+pub struct MultiUploadRequest {
+    /// 上传任务 ID，单个图床的进度事件 id 为 `{id}:{serviceId}`
+    pub id: String,
+    pub file_path: String,
+    /// 按优先级排列的图床列表
+    pub services: Vec<String>,
+    /// 以服务 ID 为键的配置表，缺失的服务传入 null
+    pub configs: serde_json::Value,
+    pub max_concurrency: usize,
+    /// 单个图床的重试策略
+    pub retry: RetryPolicy,
+    /// 跳过去重缓存，强制重新上传
+    pub force_fresh: bool,
+}
+
+/// 单个图床的上传结果（字段与前端 `SingleServiceResult` 保持一致）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceUploadResult {
+    pub service_id: String,
+    /// "success" | "failed"
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<UploadOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 结构化错误（与命令返回的 AppError 格式相同）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<AppError>,
+}
+
+/// 备份链接
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupLink {
+    pub service_id: String,
+    pub url: String,
+}
+
+/// 部分失败的图床
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialFailure {
+    pub service_id: String,
+    pub error: String,
+}
+
+/// 多图床上传汇总结果（字段与前端 `MultiUploadResult` 保持一致）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiUploadOutput {
+    /// 主力图床（按优先级第一个成功的）
+    pub primary_service: String,
+    /// 主力图床的 URL
+    pub primary_url: String,
+    /// 其余成功图床的链接
+    pub backup_links: Vec<BackupLink>,
+    /// 所有图床的上传结果（与传入顺序一致）
+    pub results: Vec<ServiceUploadResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_failures: Option<Vec<PartialFailure>>,
+    pub is_partial_success: bool,
+}
+
+impl MultiUploadOutput {
+    /// 对外输出的链接：主力图床，`all` 时附带备份图床；微博链接加上代理前缀
+    pub fn links(&self, all: bool, weibo_prefix: Option<&str>) -> Vec<String> {
+        let with_prefix = |service_id: &str, url: &str| match weibo_prefix {
+            Some(prefix) if service_id == "weibo" => format!("{}{}", prefix, url),
+            _ => url.to_string(),
+        };
+
+        let mut links = vec![with_prefix(&self.primary_service, &self.primary_url)];
+        if all {
+            links.extend(
+                self.backup_links
+                    .iter()
+                    .map(|link| with_prefix(&link.service_id, &link.url)),
+            );
+        }
+        links
+    }
+}
+
+/// `upload://service-result` 事件载荷
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ServiceResultEvent<'a> {
+    id: &'a str,
+    #[serde(flatten)]
+    result: &'a ServiceUploadResult,
+}
+
+/// `upload://multi-complete` 事件载荷
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct MultiCompleteEvent<'a> {
+    id: &'a str,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<&'a MultiUploadOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// 并行上传到多个图床
+///
+/// 单个图床被取消（`{id}:{serviceId}`）视为该图床失败；整体被取消时返回 `AppError::Cancelled`
+pub async fn upload_to_services(
+    host: impl UploadHost + 'static,
+    registry: &UploaderRegistry,
+    http_client: reqwest::Client,
+    request: MultiUploadRequest,
+) -> Result<MultiUploadOutput, AppError> {
+    if request.services.is_empty() {
+        return Err(AppError::validation("没有启用任何图床服务，请在上传界面选择至少一个图床"));
+    }
+
+    // 同一图床重复出现时只上传一次，保持优先级顺序
+    let mut services: Vec<String> = Vec::with_capacity(request.services.len());
+    for service_id in &request.services {
+        if !services.contains(service_id) {
+            services.push(service_id.clone());
+        }
+    }
+
+    let concurrency = request.max_concurrency.max(1);
+    println!(
+        "[MultiUploader] 开始并行上传到 {:?}，最大并发数: {}",
+        services, concurrency
+    );
+
+    // 父任务以 request.id 注册，取消它会同时取消所有图床的子任务
+    let parent = UploadContext::new(host, request.id.clone(), http_client)
+        .with_retry(request.retry)
+        .with_force_fresh(request.force_fresh);
+
+    let tasks = services.into_iter().enumerate().map(|(index, service_id)| {
+        let ctx = parent.child(format!("{}:{}", request.id, service_id));
+        let config = request.configs.get(&service_id).cloned().unwrap_or(serde_json::Value::Null);
+        let file_path = request.file_path.as_str();
+        let id = request.id.as_str();
+
+        async move {
+            let outcome = match registry.get(&service_id) {
+                Ok(uploader) => run_upload(uploader.as_ref(), &ctx, file_path, &config).await,
+                Err(e) => Err(e),
+            };
+
+            let result = match outcome {
+                Ok(output) => {
+                    println!("[MultiUploader] {} 上传成功", service_id);
+                    ServiceUploadResult {
+                        service_id: service_id.clone(),
+                        status: "success",
+                        result: Some(output),
+                        error: None,
+                        error_detail: None,
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[MultiUploader] {} 上传失败: {}", service_id, e);
+                    ServiceUploadResult {
+                        service_id: service_id.clone(),
+                        status: "failed",
+                        result: None,
+                        error: Some(e.to_string()),
+                        error_detail: Some(e),
+                    }
+                }
+            };
+
+            // 每个图床完成后立即通知，实现实时 UI 更新
+            host::emit(ctx.host.as_ref(), "upload://service-result", ServiceResultEvent { id, result: &result });
+
+            (index, result)
+        }
+    });
+
+    let mut results: Vec<(usize, ServiceUploadResult)> = stream::iter(tasks)
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<ServiceUploadResult> = results.into_iter().map(|(_, r)| r).collect();
+
+    // 整体被取消时不再汇总，已完成的图床结果已通过 service-result 事件发出
+    parent.check_cancelled()?;
+
+    let aggregated = aggregate(results);
+    match &aggregated {
+        Ok(output) => {
+            println!(
+                "[MultiUploader] 主力图床: {}，备份: {} 个，失败: {} 个",
+                output.primary_service,
+                output.backup_links.len(),
+                output.partial_failures.as_ref().map_or(0, |f| f.len())
+            );
+            host::emit(parent.host.as_ref(), "upload://multi-complete", MultiCompleteEvent {
+                id: &request.id,
+                success: true,
+                output: Some(output),
+                error: None,
+            });
+        }
+        Err(e) => {
+            host::emit(parent.host.as_ref(), "upload://multi-complete", MultiCompleteEvent {
+                id: &request.id,
+                success: false,
+                output: None,
+                error: Some(e.to_string()),
+            });
+        }
+    }
+
+    aggregated
+}
+
+/// 汇总各图床结果：确定主力图床、备份链接和部分失败列表
+fn aggregate(results: Vec<ServiceUploadResult>) -> Result<MultiUploadOutput, AppError> {
+    let primary = results.iter().find_map(|r| {
+        r.result.as_ref().map(|output| (r.service_id.clone(), output.url.clone()))
+    });
+
+    let failures: Vec<PartialFailure> = results
+        .iter()
+        .filter_map(|r| {
+            r.error.as_ref().map(|error| PartialFailure {
+                service_id: r.service_id.clone(),
+                error: error.clone(),
+            })
+        })
+        .collect();
+
+    let Some((primary_service, primary_url)) = primary else {
+        let details = failures
+            .iter()
+            .map(|f| format!("  - {}: {}", f.service_id, f.error))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let message = format!("所有图床上传均失败：\n{}\n\n请检查网络连接和服务配置", details);
+        // 只要有图床是瞬时错误（网络、限流），整体仍按网络错误返回，调用方（持久化队列）可以稍后重试
+        let transient = results
+            .iter()
+            .filter_map(|r| r.error_detail.as_ref())
+            .any(|e| e.class() == ErrorClass::Transient);
+        return Err(if transient {
+            AppError::network(message)
+        } else {
+            AppError::upload("多图床", message)
+        });
+    };
+
+    let backup_links = results
+        .iter()
+        .filter(|r| r.service_id != primary_service)
+        .filter_map(|r| {
+            r.result.as_ref().map(|output| BackupLink {
+                service_id: r.service_id.clone(),
+                url: output.url.clone(),
+            })
+        })
+        .collect();
+
+    let is_partial_success = !failures.is_empty();
+
+    Ok(MultiUploadOutput {
+        primary_service,
+        primary_url,
+        backup_links,
+        results,
+        partial_failures: is_partial_success.then_some(failures),
+        is_partial_success,
+    })
+}
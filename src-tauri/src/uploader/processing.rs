@@ -0,0 +1,1032 @@
+// src-tauri/src/uploader/processing.rs
+// 上传前的图片处理：方向校正、压缩、格式转换与元数据清理
+//
+// 所有上传入口（前端上传、剪贴板、监听文件夹、PicGo 服务、命令行）在发送前都先经过这里
+// （见 `UploadContext::prepare_file`）：
+// - 方向校正（`imageProcessing.autoOrient`，默认开启，不受 `enabled` 影响）：EXIF 方向不是正常方向时
+//   按方向旋转 / 翻转像素，避免清理元数据的图床（微博、京东、哔哩哔哩等）上显示为横躺。
+//   没有方向信息或已是正常方向的文件不重新编码
+// - 压缩与格式转换（`config.imageProcessing`，默认关闭）：按质量重新编码 JPEG / WebP、
+//   PNG 转 WebP、按最长边等比缩小（Lanczos3）。目标格式不被图床接受时保持原格式，动图不重新编码
+// - 适配图床限制（`imageProcessing.fitToLimits`，默认开启，不受 `enabled` 影响）：文件超过图床的大小上限
+//   或格式不被接受（如 BMP）时，只为该图床生成一份刚好满足限制的副本：先换成可接受的格式，
+//   再逐步降低质量、按比例缩小。限制宽松的图床仍然上传原图；GIF 无法在保留动画的前提下压缩，不做处理
+// - 水印（`config.watermark`，当前选中的方案）：文字或 Logo，需要重新编码（见 watermark.rs）
+// - 元数据清理（`config.metadataStripping`，默认开启，可按图床关闭）：删除 EXIF / XMP / IPTC 等数据块，
+//   不重新编码像素（见 metadata.rs）。重新编码的输出本身不含元数据；图床关闭了清理时，
+//   JPEG 重新编码后保留原 EXIF，并把其中的方向重置为正常方向
+//
+// 处理结果写入临时文件，原文件不做任何修改；同一文件上传到多个图床时相同的处理只执行一次。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, UNIX_EPOCH};
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::codecs::webp::{WebPEncoder, WebPQuality};
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, ImageEncoder, ImageFormat, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use super::watermark::{self, WatermarkConfig, WatermarkProfile};
+use super::{metadata, UploaderCapabilities};
+use crate::error::AppError;
+use crate::secure_config;
+
+/// 默认编码质量
+const DEFAULT_QUALITY: u8 = 85;
+
+/// 适配大小限制时每次降低的质量
+const FIT_QUALITY_STEP: u8 = 10;
+
+/// 适配大小限制时的最低质量，仍然超限时改为缩小尺寸
+const FIT_MIN_QUALITY: u8 = 50;
+
+/// 适配大小限制的最大尝试次数
+const FIT_MAX_ATTEMPTS: usize = 10;
+
+/// 内存中保留的处理结果数量上限（超过时全部清空，临时文件随之删除）
+const MEMO_LIMIT: usize = 64;
+
+/// 处理结果所在的临时目录名
+const OUTPUT_DIR: &str = "picnexus-processed";
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// 保持原格式
+    #[default]
+    Original,
+    Jpeg,
+    Webp,
+}
+
+/// 图片处理配置（`config.imageProcessing`）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProcessingSettings {
+    pub enabled: bool,
+    pub output_format: OutputFormat,
+    /// 输出格式为 `original` 时把 PNG 转为 WebP
+    pub png_to_webp: bool,
+    /// JPEG / WebP 编码质量（1-100，100 时 WebP 使用无损编码）
+    pub quality: u8,
+    /// 最长边像素上限，超过时等比缩小；0 表示不限制
+    pub max_dimension: u32,
+    /// 超过图床的大小限制或格式不被接受时自动转换（不受 `enabled` 影响）
+    pub fit_to_limits: bool,
+    /// 按 EXIF 方向旋转像素（不受 `enabled` 影响）
+    pub auto_orient: bool,
+}
+
+impl Default for ProcessingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            output_format: OutputFormat::Original,
+            png_to_webp: false,
+            quality: DEFAULT_QUALITY,
+            max_dimension: 0,
+            fit_to_limits: true,
+            auto_orient: true,
+        }
+    }
+}
+
+/// 元数据清理配置（`config.metadataStripping`）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MetadataSettings {
+    pub enabled: bool,
+    /// 按图床覆盖 `enabled`（如私有 R2 存储桶保留元数据）
+    pub services: HashMap<String, bool>,
+}
+
+impl Default for MetadataSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            services: HashMap::new(),
+        }
+    }
+}
+
+impl MetadataSettings {
+    fn strip_for(&self, service_id: &str) -> bool {
+        self.services.get(service_id).copied().unwrap_or(self.enabled)
+    }
+}
+
+/// 图片处理的全部配置
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ImageSettings {
+    processing: ProcessingSettings,
+    metadata: MetadataSettings,
+    /// 当前使用的水印方案
+    watermark: Option<Arc<WatermarkProfile>>,
+}
+
+/// 参与处理的编码格式（BMP 只作为输入，需要重新编码时输出 PNG）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Jpeg,
+    Png,
+    Webp,
+    Bmp,
+}
+
+impl Encoding {
+    fn from_format(format: ImageFormat) -> Option<Self> {
+        match format {
+            ImageFormat::Jpeg => Some(Self::Jpeg),
+            ImageFormat::Png => Some(Self::Png),
+            ImageFormat::WebP => Some(Self::Webp),
+            ImageFormat::Bmp => Some(Self::Bmp),
+            _ => None,
+        }
+    }
+
+    fn ext(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::Bmp => "bmp",
+        }
+    }
+
+    /// 原格式不被图床接受时依次尝试的格式
+    fn fallbacks(self) -> &'static [Encoding] {
+        match self {
+            Self::Jpeg => &[Self::Webp, Self::Png],
+            Self::Png | Self::Bmp => &[Self::Png, Self::Webp, Self::Jpeg],
+            Self::Webp => &[Self::Jpeg, Self::Png],
+        }
+    }
+
+    fn is_lossy(self) -> bool {
+        matches!(self, Self::Jpeg | Self::Webp)
+    }
+
+    fn accepted_by(self, capabilities: Option<&UploaderCapabilities>) -> bool {
+        let Some(capabilities) = capabilities else {
+            return true;
+        };
+        match self {
+            Self::Jpeg => capabilities.accepts_format("jpg") || capabilities.accepts_format("jpeg"),
+            _ => capabilities.accepts_format(self.ext()),
+        }
+    }
+}
+
+/// 待处理文件的基本信息（只读取文件头）
+struct SourceInfo {
+    encoding: Encoding,
+    width: u32,
+    height: u32,
+    size: u64,
+    modified_ms: u128,
+    /// APNG / 动态 WebP，只清理元数据，不重新编码（也不加水印）
+    animated: bool,
+    /// EXIF 方向（1-8），没有方向信息时为 1
+    orientation: u8,
+}
+
+impl SourceInfo {
+    /// 按方向校正后的显示尺寸（方向 5-8 需要旋转 90°，宽高互换）
+    fn display_size(&self) -> (u32, u32) {
+        if self.orientation >= 5 {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+}
+
+/// 一次处理的具体参数（同一文件按不同图床的配置和限制可能得到不同的计划）
+#[derive(Debug, Clone)]
+struct Plan {
+    reencode: Option<Reencode>,
+    strip_metadata: bool,
+}
+
+/// 重新编码参数
+#[derive(Debug, Clone)]
+struct Reencode {
+    target: Encoding,
+    quality: u8,
+    /// 需要缩小时的最长边，0 表示不缩放
+    max_dimension: u32,
+    watermark: Option<Arc<WatermarkProfile>>,
+    /// 图床的大小上限，超过时继续降低质量、缩小尺寸
+    max_size: Option<u64>,
+    /// 原格式不被图床接受，换成了其他格式
+    format_fitted: bool,
+    /// 需要校正的 EXIF 方向，1 表示不旋转
+    orientation: u8,
+    /// 保留原 JPEG 的 EXIF（图床关闭了元数据清理时）
+    keep_exif: bool,
+}
+
+/// 重新编码的结果
+struct Reencoded {
+    bytes: Vec<u8>,
+    width: u32,
+    height: u32,
+    /// 为适配大小上限降低了质量或缩小了尺寸
+    size_fitted: bool,
+}
+
+/// 处理结果摘要（原始 / 处理后的大小、格式和尺寸）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessingSummary {
+    pub original_size: u64,
+    pub processed_size: u64,
+    pub original_format: &'static str,
+    pub format: &'static str,
+    pub original_width: u32,
+    pub original_height: u32,
+    pub width: u32,
+    pub height: u32,
+    /// 是否重新编码（重新编码的输出不含元数据）
+    pub reencoded: bool,
+    /// 是否添加了水印
+    pub watermarked: bool,
+    /// 是否为满足图床的大小 / 格式限制做了调整
+    pub fitted: bool,
+    /// 是否按 EXIF 方向旋转了像素
+    pub oriented: bool,
+    /// 是否在不重新编码的情况下删除了元数据
+    pub metadata_stripped: bool,
+}
+
+/// 处理后的临时文件，释放时删除
+struct ProcessedFile {
+    path: String,
+    summary: ProcessingSummary,
+    /// 文件指纹 + 处理计划，决定输出路径
+    key: String,
+    live: LiveOutputs,
+}
+
+/// 仍在使用的处理结果（文件指纹 + 处理计划 → 结果）
+///
+/// 输出路径由 key 决定，内存中的处理结果被清空（配置变化、数量超限）后再次处理同一文件时，
+/// 先从这里找回仍在上传的结果，避免覆盖或删除正在使用的文件
+type LiveOutputs = Arc<Mutex<HashMap<String, Weak<ProcessedFile>>>>;
+
+impl Drop for ProcessedFile {
+    fn drop(&mut self) {
+        // 与 write_output 持有同一把锁：删除期间不会有新的结果写入同一路径
+        let mut live = self.live.lock().unwrap_or_else(|p| p.into_inner());
+        // 本结果释放的同时同一路径已经写入了新的结果，文件不再属于这里
+        if live.get(&self.key).is_some_and(|w| w.strong_count() > 0) {
+            return;
+        }
+        live.remove(&self.key);
+
+        let path = Path::new(&self.path);
+        let _ = std::fs::remove_file(path);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+/// 实际要上传的文件：处理后的临时文件，或未经处理的原文件
+///
+/// 持有期间临时文件不会被删除
+pub struct PreparedFile {
+    path: String,
+    processed: Option<Arc<ProcessedFile>>,
+}
+
+impl PreparedFile {
+    /// 不经处理，直接上传原文件
+    pub fn original(file_path: &str) -> Self {
+        Self {
+            path: file_path.to_string(),
+            processed: None,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// 经过处理时返回处理摘要
+    pub fn summary(&self) -> Option<&ProcessingSummary> {
+        self.processed.as_ref().map(|p| &p.summary)
+    }
+
+    /// 把处理摘要附加到上传命令的结果中
+    pub fn attach<T>(&self, result: T) -> WithProcessing<T> {
+        WithProcessing {
+            result,
+            processing: self.summary().cloned(),
+        }
+    }
+
+    /// 格式转换后，把按原文件名生成的对象 Key 的扩展名替换为新格式
+    pub fn adjust_key(&self, key: &str) -> String {
+        match self.summary() {
+            Some(summary) if summary.format != summary.original_format => {
+                let stem = key
+                    .rsplit_once('.')
+                    .filter(|(_, ext)| !ext.contains('/'))
+                    .map_or(key, |(stem, _)| stem);
+                format!("{}.{}", stem, summary.format)
+            }
+            _ => key.to_string(),
+        }
+    }
+}
+
+/// 单个图床上传命令的返回值：图床的上传结果加上传前处理的摘要（未经处理时不输出 `processing`）
+///
+/// 前端写入结果的 `metadata.processing`，与统一上传器（`run_upload`）的结果一致
+#[derive(Debug, Serialize)]
+pub struct WithProcessing<T> {
+    #[serde(flatten)]
+    pub result: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub processing: Option<ProcessingSummary>,
+}
+
+type MemoCell = Arc<OnceCell<Option<Arc<ProcessedFile>>>>;
+
+/// 图片处理器（作为 Tauri 全局状态注册，未注册时上传不做处理）
+#[derive(Default)]
+pub struct ImageProcessor {
+    settings: Mutex<ImageSettings>,
+    /// 文件指纹 + 处理计划 → 处理结果（None 表示无需改动，上传原文件）
+    memo: Mutex<HashMap<String, MemoCell>>,
+    live: LiveOutputs,
+}
+
+impl ImageProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 应用用户配置中的 `imageProcessing`、`metadataStripping` 和 `watermark`，某一项格式错误时只保持该项的当前配置
+    pub fn apply_config(&self, user_config: &serde_json::Value) {
+        let mut settings = self.settings.lock().unwrap_or_else(|p| p.into_inner()).clone();
+        match secure_config::section::<ProcessingSettings>(user_config, "imageProcessing") {
+            Ok(processing) => settings.processing = processing,
+            Err(e) => eprintln!("[ImageProcessor] imageProcessing 配置格式错误: {}", e),
+        }
+        match secure_config::section::<MetadataSettings>(user_config, "metadataStripping") {
+            Ok(metadata) => settings.metadata = metadata,
+            Err(e) => eprintln!("[ImageProcessor] metadataStripping 配置格式错误: {}", e),
+        }
+        match secure_config::section::<WatermarkConfig>(user_config, "watermark") {
+            Ok(watermark) => settings.watermark = watermark.into_active().map(Arc::new),
+            Err(e) => eprintln!("[ImageProcessor] watermark 配置格式错误: {}", e),
+        }
+        self.apply(settings);
+    }
+
+    /// 应用新配置，配置变化时丢弃已有的处理结果
+    fn apply(&self, settings: ImageSettings) {
+        let mut current = self.settings.lock().unwrap_or_else(|p| p.into_inner());
+        if *current == settings {
+            return;
+        }
+
+        let processing = &settings.processing;
+        if processing.enabled {
+            println!(
+                "[ImageProcessor] 已开启上传前处理: 格式 {:?}，质量 {}，最长边 {}",
+                processing.output_format, processing.quality, processing.max_dimension
+            );
+        } else if current.processing.enabled {
+            println!("[ImageProcessor] 已关闭上传前处理");
+        }
+        if settings.watermark != current.watermark {
+            match &settings.watermark {
+                Some(profile) => println!("[ImageProcessor] 使用水印方案: {}", profile.name),
+                None => println!("[ImageProcessor] 已关闭水印"),
+            }
+        }
+        if settings.metadata != current.metadata {
+            println!(
+                "[ImageProcessor] 元数据清理: {}，按图床覆盖: {:?}",
+                if settings.metadata.enabled { "开启" } else { "关闭" },
+                settings.metadata.services
+            );
+        }
+        *current = settings;
+        self.memo.lock().unwrap_or_else(|p| p.into_inner()).clear();
+    }
+
+    /// 按当前配置处理上传到 `service_id` 的文件，`capabilities` 为该图床的能力描述（用于判断是否接受目标格式）
+    ///
+    /// 处理失败时只记录日志，上传原文件；需要加水印时不能退回未加水印的原图，返回错误
+    pub async fn prepare(
+        &self,
+        service_id: &str,
+        file_path: &str,
+        capabilities: Option<&UploaderCapabilities>,
+    ) -> Result<PreparedFile, AppError> {
+        let (key, source, plan) = match self.plan_for(service_id, file_path, capabilities).await {
+            Ok(Some(planned)) => planned,
+            Ok(None) => return Ok(PreparedFile::original(file_path)),
+            Err(e) => {
+                eprintln!("[ImageProcessor] 读取图片信息失败，上传原文件: {}", e);
+                return Ok(PreparedFile::original(file_path));
+            }
+        };
+        let watermarked = plan.reencode.as_ref().is_some_and(|r| r.watermark.is_some());
+
+        let cell = {
+            let mut memo = self.memo.lock().unwrap_or_else(|p| p.into_inner());
+            if memo.len() >= MEMO_LIMIT && !memo.contains_key(&key) {
+                memo.clear();
+            }
+            memo.entry(key.clone()).or_default().clone()
+        };
+
+        // 多个图床同时上传同一文件时，只有第一个真正执行处理，其余等待结果
+        let processed = cell
+            .get_or_try_init(|| {
+                let path = PathBuf::from(file_path);
+                let live = Arc::clone(&self.live);
+                async move {
+                    // 内存中的结果已被清空，但同一处理结果仍在上传时直接复用
+                    let reused = live
+                        .lock()
+                        .unwrap_or_else(|p| p.into_inner())
+                        .get(&key)
+                        .and_then(Weak::upgrade);
+                    if let Some(processed) = reused {
+                        return Ok(Some(processed));
+                    }
+                    tokio::task::spawn_blocking(move || process(&path, &key, &source, plan, &live))
+                        .await
+                        .map_err(|e| AppError::external(format!("图片处理任务异常: {}", e)))?
+                }
+            })
+            .await;
+        let processed = match processed {
+            Ok(processed) => processed.clone(),
+            Err(e) if watermarked => return Err(e),
+            Err(e) => {
+                eprintln!("[ImageProcessor] 图片处理失败，上传原文件: {}", e);
+                return Ok(PreparedFile::original(file_path));
+            }
+        };
+
+        Ok(match processed {
+            Some(processed) => PreparedFile {
+                path: processed.path.clone(),
+                processed: Some(processed),
+            },
+            None => PreparedFile::original(file_path),
+        })
+    }
+
+    /// 读取文件头并按当前配置生成处理计划，无需处理时返回 None
+    async fn plan_for(
+        &self,
+        service_id: &str,
+        file_path: &str,
+        capabilities: Option<&UploaderCapabilities>,
+    ) -> Result<Option<(String, SourceInfo, Plan)>, AppError> {
+        let settings = self.settings.lock().unwrap_or_else(|p| p.into_inner()).clone();
+        if !settings.processing.enabled
+            && !settings.processing.fit_to_limits
+            && settings.watermark.is_none()
+            && !settings.metadata.strip_for(service_id)
+        {
+            return Ok(None);
+        }
+
+        let path = PathBuf::from(file_path);
+        let source = tokio::task::spawn_blocking(move || probe(&path))
+            .await
+            .map_err(|e| AppError::external(format!("读取图片信息任务异常: {}", e)))??;
+        let Some(source) = source else {
+            return Ok(None);
+        };
+
+        Ok(plan(&settings, service_id, &source, capabilities).map(|plan| {
+            let key = format!(
+                "{}|{}|{}|{:?}",
+                file_path, source.size, source.modified_ms, plan
+            );
+            (key, source, plan)
+        }))
+    }
+}
+
+/// 读取格式、尺寸和文件指纹；不参与处理的格式（GIF 等）返回 None
+fn probe(path: &Path) -> Result<Option<SourceInfo>, AppError> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| AppError::file_io(format!("无法读取文件信息: {}", e)))?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis());
+
+    let reader = image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?;
+    let Some(encoding) = reader.format().and_then(Encoding::from_format) else {
+        return Ok(None);
+    };
+
+    let animated =
+        is_animated(path, encoding).map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?;
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|e| AppError::validation(format!("无法读取图片尺寸: {}", e)))?;
+    let orientation = match encoding {
+        Encoding::Jpeg | Encoding::Png | Encoding::Webp => read_orientation(path),
+        Encoding::Bmp => 1,
+    };
+
+    Ok(Some(SourceInfo {
+        encoding,
+        width,
+        height,
+        size: metadata.len(),
+        modified_ms,
+        animated,
+        orientation,
+    }))
+}
+
+/// EXIF 方向（1-8），没有 EXIF 或方向无效时返回 1
+pub(crate) fn read_orientation(path: &Path) -> u8 {
+    let Ok(file) = File::open(path) else {
+        return 1;
+    };
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .filter(|v| (1..=8).contains(v))
+        .map_or(1, |v| v as u8)
+}
+
+/// 是否为动图（APNG / 动态 WebP），重新编码会丢失动画
+fn is_animated(path: &Path, encoding: Encoding) -> std::io::Result<bool> {
+    let mut file = File::open(path)?;
+    match encoding {
+        Encoding::Jpeg | Encoding::Bmp => Ok(false),
+        // VP8X 扩展头的动画标志位
+        Encoding::Webp => {
+            let mut header = [0u8; 21];
+            if file.read_exact(&mut header).is_err() {
+                return Ok(false);
+            }
+            Ok(&header[12..16] == b"VP8X" && header[20] & 0x02 != 0)
+        }
+        // APNG 的 acTL 块出现在第一个 IDAT 之前
+        Encoding::Png => {
+            file.seek(SeekFrom::Start(8))?;
+            let mut chunk = [0u8; 8];
+            while file.read_exact(&mut chunk).is_ok() {
+                match &chunk[4..8] {
+                    b"acTL" => return Ok(true),
+                    b"IDAT" => return Ok(false),
+                    _ => {}
+                }
+                let len = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                // 跳过数据和 CRC
+                file.seek(SeekFrom::Current(len as i64 + 4))?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// 按配置和图床限制生成处理计划，无需处理时返回 None
+fn plan(
+    settings: &ImageSettings,
+    service_id: &str,
+    source: &SourceInfo,
+    capabilities: Option<&UploaderCapabilities>,
+) -> Option<Plan> {
+    let strip_metadata = settings.metadata.strip_for(service_id);
+    let reencode = reencode_plan(settings, source, capabilities).map(|mut reencode| {
+        reencode.keep_exif =
+            !strip_metadata && source.encoding == Encoding::Jpeg && reencode.target == Encoding::Jpeg;
+        reencode
+    });
+    if reencode.is_none() && !strip_metadata {
+        return None;
+    }
+
+    Some(Plan {
+        reencode,
+        strip_metadata,
+    })
+}
+
+/// 压缩、格式转换和水印的参数，无需重新编码时返回 None
+fn reencode_plan(
+    settings: &ImageSettings,
+    source: &SourceInfo,
+    capabilities: Option<&UploaderCapabilities>,
+) -> Option<Reencode> {
+    if source.animated {
+        return None;
+    }
+
+    let processing = &settings.processing;
+    let watermark = settings
+        .watermark
+        .clone()
+        .filter(|profile| {
+            let (width, height) = source.display_size();
+            profile.applies_to(width, height)
+        });
+
+    let wanted = match processing.output_format {
+        _ if !processing.enabled => source.encoding,
+        OutputFormat::Jpeg => Encoding::Jpeg,
+        OutputFormat::Webp => Encoding::Webp,
+        OutputFormat::Original if processing.png_to_webp && source.encoding == Encoding::Png => Encoding::Webp,
+        OutputFormat::Original => source.encoding,
+    };
+    let mut target = if wanted.accepted_by(capabilities) {
+        wanted
+    } else {
+        source.encoding
+    };
+
+    // 原格式也不被接受时换成图床接受的格式
+    let mut format_fitted = false;
+    if processing.fit_to_limits && !target.accepted_by(capabilities) {
+        if let Some(&fallback) = source.encoding.fallbacks().iter().find(|e| e.accepted_by(capabilities)) {
+            target = fallback;
+            format_fitted = true;
+        }
+    }
+
+    // BMP 不作为输出格式，需要重新编码时输出 PNG
+    let output = if target == Encoding::Bmp { Encoding::Png } else { target };
+    let max_size = capabilities
+        .filter(|_| processing.fit_to_limits)
+        .and_then(|c| c.max_size_for(output.ext()));
+    let oversized = target == source.encoding && max_size.is_some_and(|max| source.size > max);
+
+    // 只加水印或适配限制时也按配置的质量编码
+    let quality = processing.quality.clamp(1, 100);
+    let resize = processing.enabled
+        && processing.max_dimension > 0
+        && source.width.max(source.height) > processing.max_dimension;
+    let lossy = processing.enabled && target.is_lossy() && quality < 100;
+    let orient = processing.auto_orient && source.orientation > 1;
+    if target == source.encoding && !resize && !lossy && !oversized && !orient && watermark.is_none() {
+        return None;
+    }
+
+    Some(Reencode {
+        target: output,
+        quality,
+        max_dimension: if resize { processing.max_dimension } else { 0 },
+        watermark,
+        max_size,
+        format_fitted,
+        orientation: if orient { source.orientation } else { 1 },
+        keep_exif: false,
+    })
+}
+
+/// 按计划处理并写入临时文件，结果与原文件相同时返回 None（直接上传原文件）
+///
+/// `key` 为文件指纹 + 处理计划，决定输出路径（见 `write_output`）
+fn process(
+    path: &Path,
+    key: &str,
+    source: &SourceInfo,
+    plan: Plan,
+    live: &LiveOutputs,
+) -> Result<Option<Arc<ProcessedFile>>, AppError> {
+    if let Some(reencode) = plan.reencode {
+        match reencode_image(path, source, &reencode) {
+            Ok(Some(Reencoded { bytes, width, height, size_fitted })) => {
+                let summary = ProcessingSummary {
+                    original_size: source.size,
+                    processed_size: bytes.len() as u64,
+                    original_format: source.encoding.ext(),
+                    format: reencode.target.ext(),
+                    original_width: source.width,
+                    original_height: source.height,
+                    width,
+                    height,
+                    reencoded: true,
+                    watermarked: reencode.watermark.is_some(),
+                    fitted: size_fitted || reencode.format_fitted,
+                    oriented: reencode.orientation > 1,
+                    metadata_stripped: false,
+                };
+                return write_output(path, key, source, &bytes, summary, live).map(Some);
+            }
+            Ok(None) => {}
+            // 水印是用户明确要求的，加水印失败时整个上传失败，不上传未加水印的原图
+            Err(e) if reencode.watermark.is_some() => {
+                return Err(AppError::external(format!("添加水印失败，未上传原图: {}", e)));
+            }
+            // 只做压缩 / 格式转换时，解码失败（如文件损坏）上传原图，不影响元数据清理
+            Err(e) => eprintln!("[ImageProcessor] 重新编码失败，上传原图: {}", e),
+        }
+        // 没有重新编码时上传原图，继续按配置清理元数据
+    }
+
+    if !plan.strip_metadata {
+        return Ok(None);
+    }
+
+    let data = std::fs::read(path).map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?;
+    let Some(bytes) = metadata::strip(source.encoding.ext(), &data) else {
+        return Ok(None);
+    };
+    let summary = ProcessingSummary {
+        original_size: source.size,
+        processed_size: bytes.len() as u64,
+        original_format: source.encoding.ext(),
+        format: source.encoding.ext(),
+        original_width: source.width,
+        original_height: source.height,
+        width: source.width,
+        height: source.height,
+        reencoded: false,
+        watermarked: false,
+        fitted: false,
+        oriented: false,
+        metadata_stripped: true,
+    };
+    write_output(path, key, source, &bytes, summary, live).map(Some)
+}
+
+/// 解码、校正方向、缩放并重新编码，返回编码结果和输出尺寸
+///
+/// 格式不变、没有旋转、缩放也没有水印时，结果不比原文件小则返回 None
+fn reencode_image(
+    path: &Path,
+    source: &SourceInfo,
+    reencode: &Reencode,
+) -> Result<Option<Reencoded>, AppError> {
+    let image = image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?
+        .decode()
+        .map_err(|e| AppError::validation(format!("图片解码失败: {}", e)))?;
+
+    // 先校正方向，缩放和水印按显示方向生效
+    let image = apply_orientation(image, reencode.orientation);
+    if reencode.orientation > 1 {
+        println!(
+            "[ImageProcessor] 按 EXIF 方向 {} 校正: {}",
+            reencode.orientation,
+            path.display()
+        );
+    }
+
+    // 保留的 EXIF 段计入大小限制
+    let exif = if reencode.keep_exif {
+        let data = std::fs::read(path).map_err(|e| AppError::file_io(format!("无法读取图片: {}", e)))?;
+        metadata::jpeg_exif_reset_orientation(&data)
+    } else {
+        None
+    };
+    let exif_len = exif.as_ref().map_or(0, |segment| segment.len() as u64);
+
+    let image = if reencode.max_dimension > 0 {
+        image.resize(reencode.max_dimension, reencode.max_dimension, FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    // 水印在缩放之后添加，字号和边距按最终尺寸生效
+    let image = match &reencode.watermark {
+        Some(profile) => watermark::apply(image, profile)?,
+        None => image,
+    };
+
+    let mut image = image;
+    let mut quality = reencode.quality;
+    let mut bytes = encode(&image, reencode.target, quality)?;
+
+    // 超过图床的大小上限：先降低质量，到最低质量后按面积比例缩小（文件大小大致与像素数成正比）
+    let mut size_fitted = false;
+    if let Some(max_size) = reencode.max_size.map(|max| max.saturating_sub(exif_len)) {
+        let mut attempts = 0;
+        while bytes.len() as u64 > max_size && attempts < FIT_MAX_ATTEMPTS {
+            attempts += 1;
+            size_fitted = true;
+            if reencode.target.is_lossy() && quality > FIT_MIN_QUALITY {
+                quality = quality.saturating_sub(FIT_QUALITY_STEP).max(FIT_MIN_QUALITY);
+            } else {
+                let ratio = (max_size as f64 / bytes.len() as f64).sqrt() * 0.9;
+                let width = ((image.width() as f64 * ratio) as u32).max(1);
+                let height = ((image.height() as f64 * ratio) as u32).max(1);
+                image = image.resize(width, height, FilterType::Lanczos3);
+            }
+            bytes = encode(&image, reencode.target, quality)?;
+        }
+
+        if bytes.len() as u64 > max_size {
+            eprintln!(
+                "[ImageProcessor] 尝试 {} 次后仍超过大小限制 ({} > {} bytes): {}",
+                attempts,
+                bytes.len(),
+                max_size,
+                path.display()
+            );
+        } else if size_fitted {
+            println!(
+                "[ImageProcessor] 已适配大小限制: 质量 {}，{}x{}，{} bytes",
+                quality,
+                image.width(),
+                image.height(),
+                bytes.len()
+            );
+        }
+    }
+
+    if let Some(segment) = &exif {
+        bytes = metadata::insert_jpeg_segment(&bytes, segment);
+    }
+
+    if reencode.target == source.encoding
+        && reencode.orientation == 1
+        && reencode.max_dimension == 0
+        && reencode.watermark.is_none()
+        && bytes.len() as u64 >= source.size
+    {
+        println!(
+            "[ImageProcessor] 重新编码后没有变小 ({} → {} bytes)，上传原图: {}",
+            source.size,
+            bytes.len(),
+            path.display()
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(Reencoded {
+        width: image.width(),
+        height: image.height(),
+        bytes,
+        size_fitted,
+    }))
+}
+
+/// 按 EXIF 方向旋转 / 翻转像素
+pub(crate) fn apply_orientation(image: DynamicImage, orientation: u8) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        // 沿主对角线翻转
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        // 沿副对角线翻转
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// 把处理结果写入临时目录（保留原文件名，扩展名随输出格式变化）
+///
+/// 目录按 `key`（原文件指纹 + 处理计划）的哈希命名，修改时间设为原文件的修改时间：
+/// 同一文件按相同计划再次处理时，输出文件的指纹（路径 + 大小 + 修改时间）不变，
+/// 分片上传的续传记录（按 `FileBody::fingerprint` 区分）中断后仍能命中
+fn write_output(
+    path: &Path,
+    key: &str,
+    source: &SourceInfo,
+    bytes: &[u8],
+    summary: ProcessingSummary,
+    live: &LiveOutputs,
+) -> Result<Arc<ProcessedFile>, AppError> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let dir = std::env::temp_dir()
+        .join(OUTPUT_DIR)
+        .join(&hex::encode(Sha256::digest(key.as_bytes()))[..16]);
+
+    // 持锁写入并登记，与 `ProcessedFile` 释放时的删除互斥；
+    // 同时处理同一文件的另一次调用已经写入且仍在使用时，直接复用它的结果
+    let mut outputs = live.lock().unwrap_or_else(|p| p.into_inner());
+    if let Some(processed) = outputs.get(key).and_then(Weak::upgrade) {
+        return Ok(processed);
+    }
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::file_io(format!("创建临时目录失败: {}", e)))?;
+    let output = dir.join(format!("{}.{}", stem, summary.format));
+
+    // 先写入临时文件再重命名，上次残留的同名输出被整体替换
+    let temp = dir.join(format!(".{:08x}.tmp", rand::random::<u32>()));
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.set_modified(UNIX_EPOCH + Duration::from_millis(source.modified_ms as u64))
+    });
+    if let Err(e) = written.and_then(|_| std::fs::rename(&temp, &output)) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(AppError::file_io(format!("写入临时文件失败: {}", e)));
+    }
+
+    println!(
+        "[ImageProcessor] {} → {}{}: {} → {} bytes, {}x{} → {}x{}",
+        summary.original_format,
+        summary.format,
+        match (summary.watermarked, summary.metadata_stripped) {
+            (true, _) => "（已加水印）",
+            (_, true) => "（已清理元数据）",
+            _ => "",
+        },
+        summary.original_size,
+        summary.processed_size,
+        summary.original_width,
+        summary.original_height,
+        summary.width,
+        summary.height
+    );
+
+    let processed = Arc::new(ProcessedFile {
+        path: output.to_string_lossy().into_owned(),
+        summary,
+        key: key.to_string(),
+        live: Arc::clone(live),
+    });
+    outputs.insert(key.to_string(), Arc::downgrade(&processed));
+    Ok(processed)
+}
+
+/// 按目标格式编码
+fn encode(image: &DynamicImage, encoding: Encoding, quality: u8) -> Result<Vec<u8>, AppError> {
+    let (width, height) = (image.width(), image.height());
+    let mut buf = Vec::new();
+
+    let result = match encoding {
+        // JPEG 不支持透明通道，透明区域按白色背景合成
+        Encoding::Jpeg => JpegEncoder::new_with_quality(&mut buf, quality).write_image(
+            flatten_on_white(image).as_raw(),
+            width,
+            height,
+            ColorType::Rgb8,
+        ),
+        // BMP 只作为输入格式，不会出现在编码目标中
+        Encoding::Png | Encoding::Bmp => {
+            let (pixels, color) = pixels(image);
+            PngEncoder::new_with_quality(&mut buf, CompressionType::Best, PngFilter::Adaptive)
+                .write_image(&pixels, width, height, color)
+        }
+        #[allow(deprecated)]
+        Encoding::Webp => {
+            let (pixels, color) = pixels(image);
+            let quality = if quality >= 100 {
+                WebPQuality::lossless()
+            } else {
+                WebPQuality::lossy(quality)
+            };
+            WebPEncoder::new_with_quality(&mut buf, quality).write_image(&pixels, width, height, color)
+        }
+    };
+
+    result.map_err(|e| AppError::external(format!("{} 编码失败: {}", encoding.ext(), e)))?;
+    Ok(buf)
+}
+
+/// 8 位 RGB / RGBA 像素数据（有透明通道时保留）
+fn pixels(image: &DynamicImage) -> (Vec<u8>, ColorType) {
+    if image.color().has_alpha() {
+        (image.to_rgba8().into_raw(), ColorType::Rgba8)
+    } else {
+        (image.to_rgb8().into_raw(), ColorType::Rgb8)
+    }
+}
+
+/// 把带透明通道的图片合成到白色背景上
+fn flatten_on_white(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
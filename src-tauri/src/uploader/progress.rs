@@ -0,0 +1,215 @@
+// src-tauri/src/uploader/progress.rs
+// 统一的上传进度事件
+//
+// 所有图床共用同一个 `upload://progress` 载荷（字段与前端 BaseUploader.ts 的 ProgressEvent 一致）。
+// `progress` / `total` 始终是真实的字节数：请求体（见 body.rs）通过 `ProgressReporter::track` 包装，
+// 每当一块数据交给 HTTP 连接时累加已发送字节，并据此计算速度和剩余时间。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+
+use super::host::{self, UploadHost};
+use super::UploadContext;
+
+/// 两次字节进度事件之间的最小间隔，避免大文件刷屏
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 进度事件载荷
+#[derive(Debug, Serialize, Clone)]
+pub struct ProgressPayload {
+    pub id: String,
+    /// 已发送字节数
+    pub progress: u64,
+    /// 总字节数
+    pub total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_steps: Option<u32>,
+    /// 发送速度（字节/秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<u64>,
+    /// 预计剩余时间（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta: Option<u64>,
+    /// 在限流队列中等待的时间（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_wait: Option<u64>,
+}
+
+struct StepState {
+    label: Option<String>,
+    index: Option<u32>,
+}
+
+struct ReporterInner {
+    host: Arc<dyn UploadHost>,
+    id: String,
+    total_steps: u32,
+    total: AtomicU64,
+    transferred: AtomicU64,
+    step: Mutex<StepState>,
+    /// 第一块数据发送的时间（用于计算速度）
+    started_at: Mutex<Option<Instant>>,
+    last_emit: Mutex<Option<Instant>>,
+    /// 上传上下文中累计的排队时间
+    queue_wait: Arc<AtomicU64>,
+}
+
+/// 上传进度报告器
+///
+/// 克隆后共享同一份计数，可以同时包装多个请求体（如分片上传）
+#[derive(Clone)]
+pub struct ProgressReporter {
+    inner: Arc<ReporterInner>,
+}
+
+impl ProgressReporter {
+    /// `total_steps` 为该图床上传流程的步骤数（用于前端显示"步骤 x/y"）
+    pub fn new(ctx: &UploadContext, total_steps: u32) -> Self {
+        Self {
+            inner: Arc::new(ReporterInner {
+                host: ctx.host.clone(),
+                id: ctx.id.clone(),
+                total_steps,
+                total: AtomicU64::new(0),
+                transferred: AtomicU64::new(0),
+                step: Mutex::new(StepState { label: None, index: None }),
+                started_at: Mutex::new(None),
+                last_emit: Mutex::new(None),
+                queue_wait: ctx.queue_wait.clone(),
+            }),
+        }
+    }
+
+    /// 设置需要发送的总字节数
+    pub fn set_total(&self, total: u64) {
+        self.inner.total.store(total, Ordering::Relaxed);
+    }
+
+    /// 进入新步骤并立即发送一次进度
+    pub fn step(&self, index: u32, label: &str) {
+        {
+            let mut step = self.inner.step.lock().unwrap_or_else(|p| p.into_inner());
+            step.label = Some(label.to_string());
+            step.index = Some(index);
+        }
+        self.emit(true);
+    }
+
+    /// 清零已发送字节（请求重试、重新发送整个文件前调用）
+    pub fn reset(&self) {
+        self.inner.transferred.store(0, Ordering::Relaxed);
+        *self.inner.started_at.lock().unwrap_or_else(|p| p.into_inner()) = None;
+    }
+
+    /// 扣除已计入的字节（某个请求体被重新发送时调用）
+    pub fn rewind(&self, bytes: u64) {
+        let _ = self.inner.transferred.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+            Some(current.saturating_sub(bytes))
+        });
+    }
+
+    /// 累加已发送字节（已在别处完成发送的数据，如秒传或断点续传跳过的分片也可以直接计入）
+    pub fn advance(&self, bytes: u64) {
+        {
+            let mut started = self.inner.started_at.lock().unwrap_or_else(|p| p.into_inner());
+            started.get_or_insert_with(Instant::now);
+        }
+        self.inner.transferred.fetch_add(bytes, Ordering::Relaxed);
+        self.emit(false);
+    }
+
+    /// 包装任意字节流，流经的每一块都计入已发送字节
+    pub fn track<S, B, E>(&self, stream: S) -> impl Stream<Item = Result<B, E>> + Send + Sync + 'static
+    where
+        S: Stream<Item = Result<B, E>> + Send + Sync + 'static,
+        B: AsRef<[u8]>,
+    {
+        let reporter = self.clone();
+        stream.map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                reporter.advance(bytes.as_ref().len() as u64);
+            }
+            chunk
+        })
+    }
+
+    /// 发送进度事件；`force` 为 false 时按 `EMIT_INTERVAL` 节流（发送完最后一块时总会发送）
+    fn emit(&self, force: bool) {
+        let inner = &self.inner;
+        let total = inner.total.load(Ordering::Relaxed);
+        let transferred = inner.transferred.load(Ordering::Relaxed);
+        let finished = total > 0 && transferred >= total;
+
+        {
+            let mut last_emit = inner.last_emit.lock().unwrap_or_else(|p| p.into_inner());
+            let now = Instant::now();
+            if !force && !finished && last_emit.is_some_and(|t| now.duration_since(t) < EMIT_INTERVAL) {
+                return;
+            }
+            *last_emit = Some(now);
+        }
+
+        // 数据发送完毕但服务器尚未响应时保持在 99%，避免业务校验失败前就显示 100%
+        let progress = if finished {
+            total.saturating_sub(total / 100).max(1)
+        } else {
+            transferred
+        };
+
+        let elapsed = inner
+            .started_at
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .map(|t| t.elapsed().as_secs_f64());
+        let speed = elapsed
+            .filter(|secs| *secs > 0.0 && transferred > 0)
+            .map(|secs| (transferred as f64 / secs) as u64)
+            .filter(|speed| *speed > 0);
+        let eta = speed
+            .filter(|_| total > transferred)
+            .map(|speed| (total - transferred).div_ceil(speed));
+
+        let (step, step_index) = {
+            let step = inner.step.lock().unwrap_or_else(|p| p.into_inner());
+            (step.label.clone(), step.index)
+        };
+
+        host::emit(inner.host.as_ref(), "upload://progress", ProgressPayload {
+            id: inner.id.clone(),
+            progress,
+            total,
+            step,
+            step_index,
+            total_steps: step_index.map(|_| inner.total_steps),
+            speed,
+            eta,
+            queue_wait: Some(inner.queue_wait.load(Ordering::Relaxed)).filter(|ms| *ms > 0),
+        });
+    }
+}
+
+/// 发送排队中的进度事件（限流器在需要等待时调用）
+///
+/// `wait` 为本次排队已经等待或预计等待的时间，加上之前各次尝试累计的排队时间后发送
+pub(crate) fn emit_queued(ctx: &UploadContext, label: &str, wait: Duration) {
+    let queue_wait = ctx.queue_wait_ms() + wait.as_millis() as u64;
+    host::emit(ctx.host.as_ref(), "upload://progress", ProgressPayload {
+        id: ctx.id.clone(),
+        progress: 0,
+        total: 0,
+        step: Some(label.to_string()),
+        step_index: None,
+        total_steps: None,
+        speed: None,
+        eta: None,
+        queue_wait: Some(queue_wait),
+    });
+}
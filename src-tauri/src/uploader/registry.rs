@@ -0,0 +1,105 @@
+// src-tauri/src/uploader/registry.rs
+// 图床服务注册表：按服务 ID 查找上传器
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use super::{Uploader, UploaderCapabilities};
+use crate::commands::{
+    bilibili::BilibiliUploader,
+    chaoxing::ChaoxingUploader,
+    github::GithubUploader,
+    imgur::ImgurUploader,
+    jd::JdUploader,
+    nami::NamiUploader,
+    nowcoder::NowcoderUploader,
+    qiyu::QiyuUploader,
+    s3_compatible::S3CompatibleUploader,
+    smms::SmmsUploader,
+    upload::WeiboUploader,
+    zhihu::ZhihuUploader,
+};
+use crate::error::AppError;
+
+/// 上传器描述信息（返回给前端）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploaderInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub capabilities: UploaderCapabilities,
+}
+
+/// 图床服务注册表
+///
+/// 作为 Tauri 全局状态注册，所有通用命令通过它分发到具体图床
+pub struct UploaderRegistry {
+    uploaders: BTreeMap<&'static str, Arc<dyn Uploader>>,
+}
+
+impl UploaderRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        Self {
+            uploaders: BTreeMap::new(),
+        }
+    }
+
+    /// 创建包含所有内置图床的注册表
+    ///
+    /// 新增图床时只需在此处追加一行
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(WeiboUploader);
+        registry.register(JdUploader);
+        registry.register(NowcoderUploader);
+        registry.register(QiyuUploader);
+        registry.register(ZhihuUploader);
+        registry.register(NamiUploader);
+        registry.register(BilibiliUploader);
+        registry.register(ChaoxingUploader);
+        registry.register(SmmsUploader);
+        registry.register(GithubUploader);
+        registry.register(ImgurUploader);
+        registry.register(S3CompatibleUploader::r2());
+        registry.register(S3CompatibleUploader::tencent());
+        registry.register(S3CompatibleUploader::aliyun());
+        registry.register(S3CompatibleUploader::qiniu());
+        registry.register(S3CompatibleUploader::upyun());
+        registry
+    }
+
+    /// 注册上传器（同 ID 会覆盖旧的实现）
+    pub fn register(&mut self, uploader: impl Uploader + 'static) {
+        let uploader: Arc<dyn Uploader> = Arc::new(uploader);
+        self.uploaders.insert(uploader.id(), uploader);
+    }
+
+    /// 按服务 ID 获取上传器
+    pub fn get(&self, service_id: &str) -> Result<Arc<dyn Uploader>, AppError> {
+        self.uploaders
+            .get(service_id)
+            .cloned()
+            .ok_or_else(|| AppError::config(format!("未知的图床服务: {}", service_id)))
+    }
+
+    /// 列出所有已注册的上传器
+    pub fn list(&self) -> Vec<UploaderInfo> {
+        self.uploaders
+            .values()
+            .map(|u| UploaderInfo {
+                id: u.id(),
+                name: u.name(),
+                capabilities: u.capabilities(),
+            })
+            .collect()
+    }
+}
+
+impl Default for UploaderRegistry {
+    fn default() -> Self {
+        Self::with_builtin()
+    }
+}
@@ -0,0 +1,79 @@
+// src-tauri/src/uploader/resume.rs
+// 断点续传记录
+//
+// 分片上传过程中把已完成分片的信息（S3 ETag、TOS 分片号、NOS context 等）保存到
+// `{appDataDir}/upload-resume/{key}.json`，上传中断（网络故障、取消、应用退出）后
+// 再次上传同一个文件时可以跳过已完成的分片。记录内容由各图床自行定义。
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::host::UploadHost;
+use crate::error::{AppError, IntoAppError};
+
+/// 续传记录的有效期，超过后视为孤儿记录（服务端通常也会在数天后清理未完成的分片上传）
+pub const RESUME_RECORD_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// 续传记录存储
+#[derive(Clone)]
+pub struct ResumeStore {
+    dir: PathBuf,
+}
+
+impl ResumeStore {
+    pub fn new(host: &dyn UploadHost) -> Result<Self, AppError> {
+        let dir = host.app_data_dir()?.join("upload-resume");
+        std::fs::create_dir_all(&dir).into_file_io_err_with("创建续传记录目录失败")?;
+        Ok(Self { dir })
+    }
+
+    /// 由上传目标和文件指纹生成记录键
+    pub fn key(parts: &[&str]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// 读取记录；记录不存在或已损坏时返回 None
+    pub fn load<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let content = std::fs::read_to_string(self.path(key)).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                eprintln!("[续传] 记录 {} 已损坏，忽略: {}", key, e);
+                self.remove(key);
+                None
+            }
+        }
+    }
+
+    /// 保存记录（先写临时文件再重命名，避免中途退出留下半个文件）
+    pub fn save<T: Serialize>(&self, key: &str, value: &T) -> Result<(), AppError> {
+        let content = serde_json::to_vec(value).into_storage_err_with("序列化续传记录失败")?;
+        let tmp = self.dir.join(format!("{}.json.tmp", key));
+        std::fs::write(&tmp, content).into_file_io_err_with("写入续传记录失败")?;
+        std::fs::rename(&tmp, self.path(key)).into_file_io_err_with("写入续传记录失败")?;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) {
+        let _ = std::fs::remove_file(self.path(key));
+    }
+}
+
+/// 续传记录是否已过期（`created_at` 为毫秒时间戳）
+pub fn is_expired(created_at: i64) -> bool {
+    let age_ms = chrono::Utc::now().timestamp_millis() - created_at;
+    age_ms < 0 || age_ms as u128 > RESUME_RECORD_TTL.as_millis()
+}
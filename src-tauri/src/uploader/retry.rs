@@ -0,0 +1,176 @@
+// src-tauri/src/uploader/retry.rs
+// 统一重试策略
+//
+// 所有上传命令共用同一套重试逻辑：错误按 `ErrorClass` 分为瞬时错误、认证错误和永久错误，
+// 只有瞬时错误会重试。重试间隔为带抖动的指数退避，服务端返回 Retry-After 时优先按其等待。
+// 各图床只需要把 HTTP 临时故障（408 / 429 / 5xx）转换为网络错误（见 `reject_transient`）。
+
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+
+use crate::error::{AppError, ErrorClass};
+
+/// 默认最多尝试次数（含第一次）
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// 允许的最大尝试次数，避免配置错误导致长时间重试
+pub const MAX_ATTEMPTS_LIMIT: u32 = 10;
+
+/// Retry-After 的等待上限，超过时按上限等待
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// 重试策略
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 最多尝试次数（含第一次），1 表示不重试
+    pub max_attempts: u32,
+    /// 第一次重试前的基础等待时间，之后每次翻倍
+    pub base_delay: Duration,
+    /// 退避等待的上限
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 指定最多尝试次数（限制在 1..=MAX_ATTEMPTS_LIMIT）
+    pub fn with_max_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.clamp(1, MAX_ATTEMPTS_LIMIT),
+            ..Self::default()
+        }
+    }
+
+    /// 第 `attempt` 次失败后的退避时间（从 1 开始）
+    ///
+    /// 使用等量抖动：在 [d/2, d] 内随机，d 为指数退避值，避免多个任务同时重试
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        let half = exp / 2;
+        let jitter_ms = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter_ms)
+    }
+
+    /// 下次重试前的等待时间：优先使用服务端的 Retry-After
+    pub fn delay_for(&self, attempt: u32, error: &AppError) -> Duration {
+        match error.retry_after() {
+            Some(retry_after) => retry_after.min(MAX_RETRY_AFTER),
+            None => self.backoff(attempt),
+        }
+    }
+
+    /// 执行操作，瞬时错误时按策略重试
+    ///
+    /// `op` 每次调用都需要重新构造请求（请求体不能复用）
+    pub async fn run<T, F, Fut>(&self, label: &str, mut op: F) -> Result<T, AppError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Ok(value) => return Ok(value),
+                Err(e) if e.class() == ErrorClass::Transient && attempt < self.max_attempts => {
+                    let delay = self.delay_for(attempt, &e);
+                    println!(
+                        "[Retry] {} 第 {}/{} 次尝试失败，{}ms 后重试: {}",
+                        label,
+                        attempt,
+                        self.max_attempts,
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    if attempt > 1 {
+                        eprintln!("[Retry] {} 共尝试 {} 次后失败: {}", label, attempt, e);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// 内层重试用尽后的错误：瞬时错误转换为上传错误，外层的 `UploadContext::run_limited` 不再整体重试
+///
+/// 用于自行重试单个分片的实现，避免分片失败后外层从头初始化整个上传
+pub fn exhausted(service: &str, error: AppError) -> AppError {
+    match error {
+        AppError::Network { message, .. } => AppError::upload(service, format!("多次重试后仍然失败: {}", message)),
+        other => other,
+    }
+}
+
+/// 是否为服务端临时故障
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+/// 解析 Retry-After 响应头（秒数或 HTTP 日期）
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait_ms = at.timestamp_millis() - chrono::Utc::now().timestamp_millis();
+    Some(Duration::from_millis(wait_ms.max(0) as u64))
+}
+
+/// 服务端临时故障（408 / 429 / 5xx）转换为可重试的网络错误，其他响应原样返回
+///
+/// 适用于由调用方自行解析响应体中业务错误的接口
+pub async fn reject_transient(service: &str, response: Response) -> Result<Response, AppError> {
+    let status = response.status();
+    if !is_transient_status(status) {
+        return Ok(response);
+    }
+
+    let retry_after = parse_retry_after(response.headers());
+    let body = response.text().await.unwrap_or_default();
+    let message = if status == StatusCode::TOO_MANY_REQUESTS {
+        format!("{} 请求过于频繁 (HTTP {}): {}", service, status, body)
+    } else {
+        format!("{} 服务暂时不可用 (HTTP {}): {}", service, status, body)
+    };
+    Err(AppError::network_retry_after(message, retry_after))
+}
+
+/// 把非 2xx 响应转换为分类后的错误
+///
+/// 临时故障为网络错误（可重试），401 / 403 为认证错误，其他为上传错误
+pub async fn status_error(service: &str, context: &str, response: Response) -> AppError {
+    let response = match reject_transient(service, response).await {
+        Ok(response) => response,
+        Err(e) => return e,
+    };
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        AppError::auth(format!("{} {} (HTTP {}): {}", service, context, status, body))
+    } else {
+        AppError::upload(service, format!("{} (HTTP {}): {}", context, status, body))
+    }
+}
@@ -0,0 +1,326 @@
+// src-tauri/src/uploader/watermark.rs
+// 上传前添加水印（文字或 Logo 图片）
+//
+// 水印按方案保存（`config.watermark.profiles`），当前选中的方案（`activeId`）对所有上传生效，
+// 未选中任何方案时不加水印；宽或高小于阈值的图片（如表情、图标）跳过。
+// 文字使用 ab_glyph 渲染，未指定字体时依次尝试常见的系统字体（优先支持中文的字体）。
+
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::Deserialize;
+
+use crate::error::AppError;
+
+/// 未指定字体时依次尝试的系统字体
+const SYSTEM_FONTS: &[&str] = &[
+    // Windows
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    // macOS
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/Helvetica.ttc",
+    // Linux
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+/// 水印类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatermarkKind {
+    #[default]
+    Text,
+    Image,
+}
+
+/// 水印位置（平铺时忽略）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatermarkPosition {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+}
+
+/// 水印方案
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatermarkProfile {
+    pub id: String,
+    pub name: String,
+    pub kind: WatermarkKind,
+    /// 文字内容（可以多行）
+    pub text: String,
+    /// 字体文件路径（TTF / OTF / TTC），为空时使用系统字体
+    pub font_path: Option<String>,
+    /// 字号（像素）
+    pub font_size: f32,
+    /// 文字颜色（#RRGGBB）
+    pub color: String,
+    /// Logo 图片路径（建议使用带透明通道的 PNG）
+    pub image_path: Option<String>,
+    /// Logo 宽度占图片宽度的比例
+    pub image_scale: f32,
+    /// 不透明度（0-1）
+    pub opacity: f32,
+    pub position: WatermarkPosition,
+    /// 与图片边缘的距离（像素）
+    pub margin: u32,
+    /// 平铺整张图片
+    pub tile: bool,
+    /// 平铺时水印之间的间距（像素）
+    pub tile_spacing: u32,
+    /// 宽度小于该值的图片不加水印
+    pub min_width: u32,
+    /// 高度小于该值的图片不加水印
+    pub min_height: u32,
+}
+
+impl Default for WatermarkProfile {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            kind: WatermarkKind::Text,
+            text: String::new(),
+            font_path: None,
+            font_size: 24.0,
+            color: "#FFFFFF".to_string(),
+            image_path: None,
+            image_scale: 0.2,
+            opacity: 0.5,
+            position: WatermarkPosition::BottomRight,
+            margin: 16,
+            tile: false,
+            tile_spacing: 120,
+            min_width: 0,
+            min_height: 0,
+        }
+    }
+}
+
+impl WatermarkProfile {
+    /// 是否需要给该尺寸的图片加水印（内容为空的方案不生效）
+    pub fn applies_to(&self, width: u32, height: u32) -> bool {
+        let has_content = match self.kind {
+            WatermarkKind::Text => !self.text.trim().is_empty(),
+            WatermarkKind::Image => self.image_path.as_deref().is_some_and(|p| !p.is_empty()),
+        };
+        has_content && self.opacity > 0.0 && width >= self.min_width && height >= self.min_height
+    }
+}
+
+/// 水印配置（`config.watermark`）
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatermarkConfig {
+    pub profiles: Vec<WatermarkProfile>,
+    /// 当前使用的方案，为空时不加水印
+    pub active_id: Option<String>,
+}
+
+impl WatermarkConfig {
+    /// 当前使用的方案
+    pub fn into_active(self) -> Option<WatermarkProfile> {
+        let active_id = self.active_id?;
+        self.profiles.into_iter().find(|p| p.id == active_id)
+    }
+}
+
+/// 按方案给图片加水印
+pub fn apply(image: DynamicImage, profile: &WatermarkProfile) -> Result<DynamicImage, AppError> {
+    let stamp = match profile.kind {
+        WatermarkKind::Text => render_text(profile)?,
+        WatermarkKind::Image => load_logo(profile, image.width())?,
+    };
+
+    let had_alpha = image.color().has_alpha();
+    let mut canvas = image.to_rgba8();
+    let opacity = profile.opacity.clamp(0.0, 1.0);
+
+    let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+    let (stamp_width, stamp_height) = (stamp.width() as i64, stamp.height() as i64);
+
+    if profile.tile {
+        // 交错平铺：奇数行错开半个间隔
+        let step_x = stamp_width + profile.tile_spacing as i64;
+        let step_y = stamp_height + profile.tile_spacing as i64;
+        let mut y = 0;
+        let mut row = 0;
+        while y < height {
+            let mut x = if row % 2 == 1 { -step_x / 2 } else { 0 };
+            while x < width {
+                blend(&mut canvas, &stamp, x, y, opacity);
+                x += step_x;
+            }
+            y += step_y;
+            row += 1;
+        }
+    } else {
+        let margin = profile.margin as i64;
+        let left = margin;
+        let center_x = (width - stamp_width) / 2;
+        let right = width - stamp_width - margin;
+        let top = margin;
+        let center_y = (height - stamp_height) / 2;
+        let bottom = height - stamp_height - margin;
+
+        let (x, y) = match profile.position {
+            WatermarkPosition::TopLeft => (left, top),
+            WatermarkPosition::Top => (center_x, top),
+            WatermarkPosition::TopRight => (right, top),
+            WatermarkPosition::Left => (left, center_y),
+            WatermarkPosition::Center => (center_x, center_y),
+            WatermarkPosition::Right => (right, center_y),
+            WatermarkPosition::BottomLeft => (left, bottom),
+            WatermarkPosition::Bottom => (center_x, bottom),
+            WatermarkPosition::BottomRight => (right, bottom),
+        };
+        blend(&mut canvas, &stamp, x, y, opacity);
+    }
+
+    let canvas = DynamicImage::ImageRgba8(canvas);
+    Ok(if had_alpha {
+        canvas
+    } else {
+        DynamicImage::ImageRgb8(canvas.to_rgb8())
+    })
+}
+
+/// 把水印按不透明度叠加到 (x, y)，超出图片的部分裁掉
+fn blend(canvas: &mut RgbaImage, stamp: &RgbaImage, x: i64, y: i64, opacity: f32) {
+    let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+    for (sx, sy, src) in stamp.enumerate_pixels() {
+        let (dx, dy) = (x + sx as i64, y + sy as i64);
+        if dx < 0 || dy < 0 || dx >= width || dy >= height {
+            continue;
+        }
+
+        let alpha = src[3] as f32 / 255.0 * opacity;
+        if alpha <= 0.0 {
+            continue;
+        }
+        let dst = canvas.get_pixel_mut(dx as u32, dy as u32);
+        for i in 0..3 {
+            dst[i] = (dst[i] as f32 * (1.0 - alpha) + src[i] as f32 * alpha).round() as u8;
+        }
+        dst[3] = dst[3].max((alpha * 255.0).round() as u8);
+    }
+}
+
+/// 把文字渲染为透明背景的图片
+fn render_text(profile: &WatermarkProfile) -> Result<RgbaImage, AppError> {
+    let font = load_font(profile.font_path.as_deref())?;
+    let scale = PxScale::from(profile.font_size.max(1.0));
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+    let [r, g, b] = parse_color(&profile.color);
+
+    // 逐行排版，记录每个字形的位置
+    let mut glyphs = Vec::new();
+    let mut text_width: f32 = 0.0;
+    let mut lines = 0;
+    for (index, line) in profile.text.lines().enumerate() {
+        let mut caret = point(0.0, scaled.ascent() + index as f32 * line_height);
+        let mut previous: Option<GlyphId> = None;
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                caret.x += scaled.kern(previous, id);
+            }
+            glyphs.push(id.with_scale_and_position(scale, caret));
+            caret.x += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        text_width = text_width.max(caret.x);
+        lines = index + 1;
+    }
+
+    let width = text_width.ceil() as u32 + 2;
+    let height = (lines as f32 * line_height).ceil() as u32 + 2;
+    let mut stamp = RgbaImage::new(width.max(1), height.max(1));
+
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|x, y, coverage| {
+            let px = bounds.min.x as i64 + x as i64;
+            let py = bounds.min.y as i64 + y as i64;
+            if px < 0 || py < 0 || px >= width as i64 || py >= height as i64 {
+                return;
+            }
+            let pixel = stamp.get_pixel_mut(px as u32, py as u32);
+            let alpha = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+            *pixel = Rgba([r, g, b, pixel[3].max(alpha)]);
+        });
+    }
+
+    Ok(stamp)
+}
+
+/// 读取 Logo 并按比例缩放到图片宽度
+fn load_logo(profile: &WatermarkProfile, image_width: u32) -> Result<RgbaImage, AppError> {
+    let path = profile
+        .image_path
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| AppError::config("水印方案未指定 Logo 图片"))?;
+    let logo = image::open(path)
+        .map_err(|e| AppError::file_io(format!("无法读取水印图片 {}: {}", path, e)))?
+        .to_rgba8();
+
+    let target_width = ((image_width as f32 * profile.image_scale.clamp(0.01, 1.0)).round() as u32).max(1);
+    if logo.width() == target_width {
+        return Ok(logo);
+    }
+    let target_height =
+        ((logo.height() as u64 * target_width as u64) / logo.width().max(1) as u64).max(1) as u32;
+    Ok(image::imageops::resize(&logo, target_width, target_height, FilterType::Lanczos3))
+}
+
+/// 读取字体：指定了路径时只使用该字体，否则依次尝试系统字体
+fn load_font(font_path: Option<&str>) -> Result<FontVec, AppError> {
+    let font_path = font_path.filter(|p| !p.is_empty());
+    let candidates = match font_path {
+        Some(path) => vec![path],
+        None => SYSTEM_FONTS.to_vec(),
+    };
+
+    for path in candidates {
+        if let Ok(data) = std::fs::read(path) {
+            // TTC 字体集合使用第一个字体
+            return FontVec::try_from_vec_and_index(data, 0)
+                .map_err(|e| AppError::validation(format!("字体文件无效 {}: {}", path, e)));
+        }
+    }
+
+    Err(match font_path {
+        Some(path) => AppError::file_io(format!("无法读取字体文件: {}", path)),
+        None => AppError::config("未找到可用的系统字体，请在水印设置中指定字体文件"),
+    })
+}
+
+/// 解析 #RRGGBB 颜色，格式错误时使用白色
+fn parse_color(color: &str) -> [u8; 3] {
+    let hex = color.trim().trim_start_matches('#');
+    let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => [r, g, b],
+        _ => [255, 255, 255],
+    }
+}
@@ -0,0 +1,458 @@
+// src-tauri/src/watch_folder.rs
+// 监听文件夹自动上传
+//
+// 用户配置一个或多个文件夹（`config.watchFolders`），文件夹中新增或修改的图片在停止写入一段时间后
+// （防抖，等待导出完成）自动上传到图床，上传后可以移动到“已上传”子文件夹、写入上传历史。
+// 整个流程只依赖 AppHandle，窗口隐藏到托盘时也能工作；保存配置后立即生效。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::error::AppError;
+use crate::history::HistoryStore;
+use crate::queue::store::now_millis;
+use crate::secure_config;
+use crate::uploader::orchestrator::{upload_to_services, MultiUploadOutput, MultiUploadRequest, DEFAULT_MAX_CONCURRENCY};
+use crate::uploader::registry::UploaderRegistry;
+use crate::uploader::retry::RetryPolicy;
+use crate::HttpClient;
+
+/// 默认防抖时间：文件最后一次变化后等待多久再上传
+const DEFAULT_DEBOUNCE_MS: u64 = 2000;
+
+/// 防抖时间下限
+const MIN_DEBOUNCE_MS: u64 = 200;
+
+/// 检查待上传文件的间隔
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 记录已上传文件指纹的数量上限
+const UPLOADED_MEMO_LIMIT: usize = 4096;
+
+/// 未配置文件名规则时匹配的图片类型
+const DEFAULT_PATTERNS: &[&str] = &["*.png", "*.jpg", "*.jpeg", "*.gif", "*.webp", "*.bmp"];
+
+/// 监听文件夹配置（`config.watchFolders`）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchSettings {
+    pub enabled: bool,
+    pub folders: Vec<WatchFolderConfig>,
+    /// 文件最后一次变化后等待多久再上传（毫秒）
+    pub debounce_ms: u64,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folders: Vec::new(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+        }
+    }
+}
+
+/// 单个监听文件夹
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchFolderConfig {
+    pub path: String,
+    /// 文件名匹配规则（glob，不区分大小写），为空时匹配常见图片格式
+    pub patterns: Vec<String>,
+    /// 是否包含子文件夹
+    pub recursive: bool,
+    /// 目标图床，为空时使用当前启用的图床
+    pub services: Vec<String>,
+    /// 上传成功后移动到的文件夹（相对路径相对于监听文件夹），为空时保留原位
+    pub move_to: Option<String>,
+    /// 是否写入上传历史
+    pub record_history: bool,
+}
+
+impl Default for WatchFolderConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            patterns: Vec::new(),
+            recursive: false,
+            services: Vec::new(),
+            move_to: None,
+            record_history: true,
+        }
+    }
+}
+
+/// `watch-folder://uploaded` 事件载荷
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatchUploadedEvent<'a> {
+    /// 上传时的文件路径
+    file_path: &'a str,
+    /// 移动后的文件路径（未移动时与 file_path 相同）
+    final_path: &'a str,
+    output: &'a MultiUploadOutput,
+}
+
+/// `watch-folder://failed` 事件载荷
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatchFailedEvent<'a> {
+    file_path: &'a str,
+    error: &'a AppError,
+}
+
+/// 已开始监听的文件夹
+struct WatchedFolder {
+    root: PathBuf,
+    config: WatchFolderConfig,
+    patterns: Vec<glob::Pattern>,
+    /// 上传后移动到的目录（其中的文件不再上传）
+    done_dir: Option<PathBuf>,
+}
+
+impl WatchedFolder {
+    fn matches(&self, path: &Path) -> bool {
+        if !path.starts_with(&self.root) {
+            return false;
+        }
+        if !self.config.recursive && path.parent() != Some(self.root.as_path()) {
+            return false;
+        }
+        if self.done_dir.as_ref().is_some_and(|dir| path.starts_with(dir)) {
+            return false;
+        }
+
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        // 隐藏文件多为编辑器或同步工具的临时文件
+        if file_name.starts_with('.') {
+            return false;
+        }
+
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        self.patterns.iter().any(|p| p.matches_with(file_name, options))
+    }
+}
+
+/// 正在运行的监听
+struct RunningWatcher {
+    settings: WatchSettings,
+    /// 释放时停止接收文件系统事件
+    _watcher: RecommendedWatcher,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// 文件夹监听（作为 Tauri 全局状态注册）
+#[derive(Default)]
+pub struct FolderWatcher {
+    running: Mutex<Option<RunningWatcher>>,
+}
+
+impl FolderWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 应用用户配置中的 `watchFolders`，格式错误时保持当前状态
+    pub fn apply_config(&self, app: &AppHandle, user_config: &serde_json::Value) {
+        match secure_config::section::<WatchSettings>(user_config, "watchFolders") {
+            Ok(settings) => self.apply(app, settings),
+            Err(e) => eprintln!("[WatchFolder] watchFolders 配置格式错误: {}", e),
+        }
+    }
+
+    /// 按配置启动、重启或停止监听
+    fn apply(&self, app: &AppHandle, settings: WatchSettings) {
+        let mut running = self.running.lock().unwrap_or_else(|p| p.into_inner());
+        if running.as_ref().is_some_and(|r| r.settings == settings) {
+            return;
+        }
+
+        if let Some(watcher) = running.take() {
+            let _ = watcher.shutdown.send(());
+            println!("[WatchFolder] 已停止监听");
+        }
+
+        if !settings.enabled || settings.folders.is_empty() {
+            return;
+        }
+
+        match start(app.clone(), settings) {
+            Ok(watcher) => *running = Some(watcher),
+            Err(e) => eprintln!("[WatchFolder] 启动失败: {}", e),
+        }
+    }
+}
+
+fn start(app: AppHandle, settings: WatchSettings) -> Result<RunningWatcher, AppError> {
+    let (events, receiver) = mpsc::unbounded_channel::<PathBuf>();
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    let _ = events.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[WatchFolder] 文件监听出错: {}", e),
+        }
+    })
+    .map_err(|e| AppError::external(format!("创建文件监听失败: {}", e)))?;
+
+    let mut folders = Vec::with_capacity(settings.folders.len());
+    for config in &settings.folders {
+        // 部分平台的事件路径是规范化后的绝对路径（如 macOS 的 /private/var），监听目录也要规范化
+        let root = match std::fs::canonicalize(&config.path) {
+            Ok(root) if root.is_dir() => root,
+            _ => {
+                eprintln!("[WatchFolder] 文件夹不存在，已跳过: {}", config.path);
+                continue;
+            }
+        };
+
+        let mode = if config.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        if let Err(e) = watcher.watch(&root, mode) {
+            eprintln!("[WatchFolder] 无法监听 {}: {}", root.display(), e);
+            continue;
+        }
+
+        let patterns = if config.patterns.is_empty() {
+            DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect()
+        } else {
+            config.patterns.clone()
+        };
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| match glob::Pattern::new(pattern) {
+                Ok(p) => Some(p),
+                Err(e) => {
+                    eprintln!("[WatchFolder] 无效的文件名规则 {}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        let done_dir = config
+            .move_to
+            .as_deref()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(|dir| root.join(dir));
+
+        println!("[WatchFolder] 正在监听: {}", root.display());
+        folders.push(WatchedFolder {
+            root,
+            config: config.clone(),
+            patterns,
+            done_dir,
+        });
+    }
+
+    if folders.is_empty() {
+        return Err(AppError::validation("没有可以监听的文件夹"));
+    }
+
+    let debounce = Duration::from_millis(settings.debounce_ms.max(MIN_DEBOUNCE_MS));
+    let (shutdown, signal) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(run(app, folders, debounce, receiver, signal));
+
+    Ok(RunningWatcher {
+        settings,
+        _watcher: watcher,
+        shutdown,
+    })
+}
+
+/// 文件指纹（大小 + 修改时间），用于忽略只改了属性、内容未变的事件
+type Fingerprint = (u64, Option<SystemTime>);
+
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let metadata = std::fs::metadata(path).ok()?;
+    metadata
+        .is_file()
+        .then(|| (metadata.len(), metadata.modified().ok()))
+}
+
+/// 防抖并逐个上传：文件在 `debounce` 时间内没有新的变化才视为写入完成
+async fn run(
+    app: AppHandle,
+    folders: Vec<WatchedFolder>,
+    debounce: Duration,
+    mut receiver: mpsc::UnboundedReceiver<PathBuf>,
+    mut signal: oneshot::Receiver<()>,
+) {
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    let mut uploaded: HashMap<PathBuf, Fingerprint> = HashMap::new();
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = &mut signal => break,
+            path = receiver.recv() => match path {
+                Some(path) => {
+                    pending.insert(path, Instant::now());
+                }
+                None => break,
+            },
+            _ = tick.tick() => {
+                let ready: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, changed_at)| changed_at.elapsed() >= debounce)
+                    .map(|(path, _)| path.clone())
+                    .collect();
+
+                for path in ready {
+                    pending.remove(&path);
+                    process(&app, &folders, &mut uploaded, path).await;
+                }
+            }
+        }
+    }
+}
+
+async fn process(
+    app: &AppHandle,
+    folders: &[WatchedFolder],
+    uploaded: &mut HashMap<PathBuf, Fingerprint>,
+    path: PathBuf,
+) {
+    // 文件已被删除或移走
+    let Ok(path) = std::fs::canonicalize(&path) else {
+        return;
+    };
+    let Some(folder) = folders.iter().find(|folder| folder.matches(&path)) else {
+        return;
+    };
+    let Some(fingerprint) = fingerprint(&path) else {
+        return;
+    };
+    if uploaded.get(&path) == Some(&fingerprint) {
+        return;
+    }
+
+    let file_path = path.to_string_lossy().into_owned();
+    println!("[WatchFolder] 检测到图片: {}", file_path);
+
+    match upload_file(app, folder, &file_path).await {
+        Ok(()) => {
+            if uploaded.len() >= UPLOADED_MEMO_LIMIT {
+                uploaded.clear();
+            }
+            uploaded.insert(path, fingerprint);
+        }
+        Err(e) => {
+            eprintln!("[WatchFolder] {} 上传失败: {}", file_path, e);
+            let _ = app.emit(
+                "watch-folder://failed",
+                WatchFailedEvent {
+                    file_path: &file_path,
+                    error: &e,
+                },
+            );
+        }
+    }
+}
+
+async fn upload_file(app: &AppHandle, folder: &WatchedFolder, file_path: &str) -> Result<(), AppError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::config(format!("无法获取应用数据目录: {}", e)))?;
+    let user_config = secure_config::load_user_config(&app_data_dir)?;
+
+    let services = if folder.config.services.is_empty() {
+        secure_config::enabled_services(&user_config)
+    } else {
+        folder.config.services.clone()
+    };
+
+    let registry = app.state::<UploaderRegistry>();
+    let http_client = app.state::<HttpClient>().0.clone();
+    let request = MultiUploadRequest {
+        id: format!("watch-{}-{:08x}", now_millis(), rand::random::<u32>()),
+        file_path: file_path.to_string(),
+        services,
+        configs: secure_config::service_configs(&user_config),
+        max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        retry: RetryPolicy::default(),
+        force_fresh: false,
+    };
+    let output = upload_to_services(app.clone(), &registry, http_client, request).await?;
+
+    // 移动失败不影响上传结果，文件留在原处
+    let final_path = match &folder.done_dir {
+        Some(dir) => match move_into(Path::new(file_path), dir) {
+            Ok(moved) => moved.to_string_lossy().into_owned(),
+            Err(e) => {
+                eprintln!("[WatchFolder] 移动文件失败: {}", e);
+                file_path.to_string()
+            }
+        },
+        None => file_path.to_string(),
+    };
+
+    if folder.config.record_history {
+        match app.try_state::<HistoryStore>() {
+            Some(history) => {
+                if let Err(e) = history.record_upload(app, &final_path, &output, "watch-folder").await {
+                    eprintln!("[WatchFolder] {}", e);
+                }
+            }
+            None => eprintln!("[WatchFolder] 历史记录不可用，未保存: {}", final_path),
+        }
+    }
+
+    println!("[WatchFolder] 上传完成: {} -> {}", file_path, output.primary_url);
+    let _ = app.emit(
+        "watch-folder://uploaded",
+        WatchUploadedEvent {
+            file_path,
+            final_path: &final_path,
+            output: &output,
+        },
+    );
+    Ok(())
+}
+
+/// 把文件移动到目录中，重名时在文件名后追加时间戳
+fn move_into(file: &Path, dir: &Path) -> Result<PathBuf, AppError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| AppError::file_io(format!("创建文件夹失败 {}: {}", dir.display(), e)))?;
+
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| AppError::file_io(format!("无效的文件路径: {}", file.display())))?;
+    let mut target = dir.join(file_name);
+    if target.exists() {
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+        let name = match file.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}-{}.{}", stem, now_millis(), ext),
+            None => format!("{}-{}", stem, now_millis()),
+        };
+        target = dir.join(name);
+    }
+
+    // 跨磁盘时 rename 会失败，改为复制后删除
+    if std::fs::rename(file, &target).is_err() {
+        std::fs::copy(file, &target)
+            .map_err(|e| AppError::file_io(format!("移动文件失败 {}: {}", file.display(), e)))?;
+        std::fs::remove_file(file)
+            .map_err(|e| AppError::file_io(format!("删除原文件失败 {}: {}", file.display(), e)))?;
+    }
+    Ok(target)
+}
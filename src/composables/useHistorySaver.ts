@@ -1,6 +1,7 @@
 // 历史记录保存模块 - 管理上传历史的保存和更新
 
 import { basename } from '@tauri-apps/api/path';
+import { invoke } from '@tauri-apps/api/core';
 import type { HistoryItem } from '../config/types';
 import type { SingleServiceResult, MultiUploadResult } from '../core/MultiServiceUploader';
 import { historyDB } from '../services/HistoryDatabase';
@@ -57,6 +58,29 @@ async function getFileName(filePath: string): Promise<string> {
  * 2. saveHistoryItemImmediate - 立即保存（第一个成功结果）
  * 3. addResultToHistoryItem - 追加结果（后续成功结果）
  */
+/**
+ * 用本地文件生成缩略图（后台执行，失败不影响保存历史记录）
+ */
+function generateThumbnails(filePath: string, results: SingleServiceResult[]): void {
+  const urls = results
+    .filter(r => r.status === 'success' && r.result?.url)
+    .map(r => r.result!.url);
+  if (urls.length === 0) return;
+
+  invoke('generate_upload_thumbnails', { filePath, urls }).catch(error => {
+    console.warn('[历史记录] 生成缩略图失败:', error);
+  });
+}
+
+/**
+ * 计算感知哈希，用于近似重复检测（后台执行，失败不影响保存历史记录）
+ */
+function recordPerceptualHash(historyId: string, filePath: string): void {
+  invoke('record_perceptual_hash', { historyId, filePath }).catch(error => {
+    console.warn('[历史记录] 计算感知哈希失败:', error);
+  });
+}
+
 export function useHistorySaver(): UseHistorySaverReturn {
   /**
    * 保存历史记录（多图床结果）
@@ -98,6 +122,8 @@ export function useHistorySaver(): UseHistorySaverReturn {
 
       invalidateCache();
       clearImageMetadataCache(filePath);
+      generateThumbnails(filePath, successfulResults);
+      recordPerceptualHash(newItem.id, filePath);
 
       return newItem.id;
     } catch (error) {
@@ -140,6 +166,8 @@ export function useHistorySaver(): UseHistorySaverReturn {
     invalidateCache();
     emitHistoryUpdated([historyId]);
     clearImageMetadataCache(filePath);
+    generateThumbnails(filePath, [firstResult]);
+    recordPerceptualHash(historyId, filePath);
   }
 
   /**
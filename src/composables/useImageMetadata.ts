@@ -1,7 +1,7 @@
 // 图片元信息处理模块 - 获取和缓存图片的宽高、大小等元信息
 
 import { invoke } from '@tauri-apps/api/core';
-import type { ImageMetadata } from '../config/types';
+import type { ExtendedImageMetadata, ImageMetadata } from '../config/types';
 import { Semaphore } from '../utils/semaphore';
 
 // ==================== 常量 ====================
@@ -52,6 +52,22 @@ export async function getImageMetadata(filePath: string): Promise<ImageMetadata>
   }
 }
 
+/**
+ * 获取图片扩展元信息（EXIF 拍摄时间、相机、位深、动图帧数等）
+ * 需要读取 EXIF 并遍历文件结构，比 getImageMetadata 慢，只在需要时调用，不写入缓存
+ * @param filePath 图片文件路径
+ * @returns 扩展元信息，读取失败时返回 null
+ */
+export async function getExtendedImageMetadata(filePath: string): Promise<ExtendedImageMetadata | null> {
+  try {
+    const metadata = await invoke<ImageMetadata>('get_image_metadata', { filePath, extended: true });
+    return metadata.extended ?? null;
+  } catch (error) {
+    console.error('[元信息] 获取扩展元信息失败:', error);
+    return null;
+  }
+}
+
 /**
  * 清理图片元信息缓存
  * @param filePath 可选，指定要清理的文件路径；不传则清理全部
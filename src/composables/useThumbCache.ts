@@ -7,6 +7,7 @@ import type { HistoryItem } from '../config/types';
 import { getActivePrefix } from '../config/types';
 import { useConfigManager } from './useConfig';
 import { useHistoryManager } from './useHistory';
+import { localThumbnailUrl, isLocalThumbnailEnabled, THUMBNAIL_SIZE_SMALL, THUMBNAIL_SIZE_MEDIUM } from '../utils/thumbnail';
 
 // 缓存上限
 const THUMB_CACHE_MAX_SIZE = 500;
@@ -204,6 +205,19 @@ export function getThumbnailCandidates(
       }
     }
 
+    // 本地缩略图缓存优先（未命中时后端从图床缩略图生成，之后离线也能显示）
+    if (isLocalThumbnailEnabled(config)) {
+      const first = item.results.find((r: any) => r.serviceId === item.primaryService && r.status === 'success' && r.result?.url)
+        || item.results.find((r: any) => r.status === 'success' && r.result?.url);
+      if (first) {
+        candidates.unshift(localThumbnailUrl(
+          first.result.url,
+          THUMBNAIL_SIZE_SMALL,
+          generateThumbnailUrl(first.serviceId, first.result.url, first.result.fileKey, config)
+        ));
+      }
+    }
+
     // 添加其他成功上传的图床
     item.results.forEach((r: any) => {
       if (r.status === 'success' && r.result?.url && r.serviceId !== item.primaryService) {
@@ -324,12 +338,18 @@ function getMediumImageUrl(item: HistoryItem, config: ReturnType<typeof useConfi
   if (!result?.result?.url) return '';
 
   // 使用中等尺寸缩略图生成函数
-  return generateMediumThumbnailUrl(
+  const mediumUrl = generateMediumThumbnailUrl(
     result.serviceId,
     result.result.url,
     result.result.fileKey,
     config
   );
+
+  // 本地缩略图缓存：未命中时由后端下载中等尺寸图生成
+  if (isLocalThumbnailEnabled(config)) {
+    return localThumbnailUrl(result.result.url, THUMBNAIL_SIZE_MEDIUM, mediumUrl);
+  }
+  return mediumUrl;
 }
 
 /**
@@ -387,6 +407,11 @@ export function useThumbCache() {
     clearThumbCache
   );
 
+  watch(
+    () => configManager.config.value?.thumbnails?.enabled,
+    clearThumbCache
+  );
+
   return {
     /**
      * 获取缩略图 URL
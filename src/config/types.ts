@@ -35,8 +35,39 @@ export interface ImageMetadata {
   aspect_ratio: number;
   /** 文件大小（字节） */
   file_size: number;
-  /** 图片格式（jpg, png, webp, gif, bmp 等） */
+  /** 图片格式（jpg, png, webp, gif, bmp 等），按文件内容识别 */
   format: string;
+  /** 扩展元数据，只在请求时返回（getExtendedImageMetadata） */
+  extended?: ExtendedImageMetadata;
+}
+
+/**
+ * 图片扩展元数据（EXIF 和技术信息）
+ * 由 get_image_metadata 命令在 extended = true 时返回，无法读取的字段为 null
+ */
+export interface ExtendedImageMetadata {
+  /** 拍摄时间（ISO 8601，记录了时区时带偏移） */
+  captured_at: string | null;
+  /** 相机厂商 */
+  camera_make: string | null;
+  /** 相机型号 */
+  camera_model: string | null;
+  /** 镜头型号 */
+  lens_model: string | null;
+  /** EXIF 方向（1-8，1 为正常方向） */
+  orientation: number | null;
+  /** 色彩空间 */
+  color_space: 'srgb' | 'adobe-rgb' | 'uncalibrated' | 'gray' | 'cmyk' | null;
+  /** 是否嵌入了 ICC 配置 */
+  has_icc_profile: boolean;
+  /** 每个通道的位深 */
+  bit_depth: number | null;
+  /** 是否有透明通道 */
+  has_alpha: boolean;
+  /** 帧数（静态图片为 1） */
+  frame_count: number;
+  /** 动图播放一遍的总时长（毫秒），静态图片为 null */
+  duration_ms: number | null;
 }
 
 /**
@@ -149,6 +180,8 @@ export interface NamiServiceConfig extends BaseServiceConfig {
   cookie: string;
   /** Auth-Token（从 Cookie 中提取的 JWT Token） */
   authToken: string;
+  /** 分片大小（MB，5-512），不设置时默认 8MB */
+  partSizeMb?: number;
 }
 
 /**
@@ -534,6 +567,250 @@ export interface UserConfig {
 
   /** 默认历史记录视图模式 */
   defaultHistoryViewMode?: 'table' | 'grid';
+
+  /** 按图床覆盖后端默认的限流预算（未配置的图床使用内置默认值） */
+  rateLimits?: Partial<Record<ServiceType, RateLimitConfig>>;
+
+  /** PicGo 兼容的本地上传服务（供 Typora、Obsidian 等调用），默认关闭 */
+  picgoServer?: PicGoServerConfig;
+
+  /** 监听文件夹自动上传，默认关闭 */
+  watchFolders?: WatchFoldersConfig;
+
+  /** 剪贴板监听自动上传，默认关闭 */
+  clipboardWatcher?: ClipboardWatcherConfig;
+
+  /** 上传前的图片压缩与格式转换，默认关闭 */
+  imageProcessing?: ImageProcessingConfig;
+
+  /** 上传前清理 EXIF / GPS 等元数据，默认开启 */
+  metadataStripping?: MetadataStrippingConfig;
+
+  /** 上传前添加水印，未选中方案时不加水印 */
+  watermark?: WatermarkConfig;
+
+  /** 本地缩略图缓存（历史记录画廊），默认开启 */
+  thumbnails?: ThumbnailConfig;
+}
+
+/**
+ * 单个图床的限流预算
+ */
+export interface RateLimitConfig {
+  /** 每分钟最多发起的上传请求数，0 表示不限制 */
+  requestsPerMinute: number;
+  /** 同时进行的上传数上限 */
+  maxConcurrent: number;
+}
+
+/**
+ * PicGo 兼容的本地上传服务配置
+ *
+ * 开启后后端监听 `POST http://127.0.0.1:{port}/upload`，按 enabledServices 上传并返回链接
+ */
+export interface PicGoServerConfig {
+  /** 是否启用 */
+  enabled: boolean;
+  /** 监听端口，默认 36677（与 PicGo 相同） */
+  port?: number;
+  /** 访问令牌（请求需携带 `?key=` 或 `Authorization: Bearer`），为空时不校验 */
+  token?: string;
+}
+
+/**
+ * 监听文件夹自动上传配置
+ *
+ * 文件夹中新增或修改的图片在停止写入 debounceMs 后由后端自动上传
+ */
+export interface WatchFoldersConfig {
+  /** 是否启用 */
+  enabled: boolean;
+  /** 监听的文件夹 */
+  folders: WatchFolderConfig[];
+  /** 文件最后一次变化后等待多久再上传（毫秒），默认 2000 */
+  debounceMs?: number;
+}
+
+/**
+ * 单个监听文件夹
+ */
+export interface WatchFolderConfig {
+  /** 文件夹绝对路径 */
+  path: string;
+  /** 文件名匹配规则（glob，不区分大小写），为空时匹配常见图片格式 */
+  patterns?: string[];
+  /** 是否包含子文件夹 */
+  recursive?: boolean;
+  /** 目标图床，为空时使用当前启用的图床 */
+  services?: ServiceType[];
+  /** 上传成功后移动到的文件夹（相对路径相对于监听文件夹），为空时保留原位 */
+  moveTo?: string;
+  /** 是否写入上传历史，默认 true */
+  recordHistory?: boolean;
+}
+
+/**
+ * 剪贴板监听自动上传配置
+ *
+ * 剪贴板中出现新图片时由后端自动上传，并把剪贴板内容替换为链接
+ */
+export interface ClipboardWatcherConfig {
+  /** 是否启用 */
+  enabled: boolean;
+  /** 替换到剪贴板的链接格式，默认 url */
+  linkFormat?: 'url' | 'markdown' | 'html' | 'bbcode';
+  /** 目标图床，为空时使用当前启用的图床 */
+  services?: ServiceType[];
+  /** 是否写入上传历史，默认 true */
+  recordHistory?: boolean;
+  /** 检查间隔（毫秒），默认 1000 */
+  intervalMs?: number;
+}
+
+/**
+ * 本地缩略图缓存配置
+ *
+ * 上传完成时用本地文件生成 WebP 缩略图，其他图片在显示时按需下载生成；
+ * 缓存在应用缓存目录，超过上限时淘汰最久未使用的缩略图
+ */
+export interface ThumbnailConfig {
+  /** 是否启用 */
+  enabled: boolean;
+  /** 生成的尺寸（最长边像素），默认 [150, 400] */
+  sizes?: number[];
+  /** WebP 编码质量（1-100），默认 75 */
+  quality?: number;
+  /** 缓存大小上限（MB），默认 200 */
+  maxCacheMb?: number;
+}
+
+/**
+ * 上传前的图片压缩与格式转换配置
+ *
+ * 由后端在上传前处理（原文件不变），处理前后的大小记录在上传结果的 `metadata.processing` 中
+ */
+export interface ImageProcessingConfig {
+  /** 是否启用 */
+  enabled: boolean;
+  /** 输出格式，默认 original（保持原格式） */
+  outputFormat?: 'original' | 'jpeg' | 'webp';
+  /** 输出格式为 original 时把 PNG 转为 WebP */
+  pngToWebp?: boolean;
+  /** JPEG / WebP 编码质量（1-100），默认 85；100 时 WebP 使用无损编码 */
+  quality?: number;
+  /** 最长边像素上限，超过时等比缩小；0 或不填表示不限制 */
+  maxDimension?: number;
+  /** 超过图床的大小限制或格式不被接受时自动转换（默认 true，不受 enabled 影响） */
+  fitToLimits?: boolean;
+  /** 按 EXIF 方向旋转像素，避免清理元数据的图床上图片横躺（默认 true，不受 enabled 影响） */
+  autoOrient?: boolean;
+}
+
+/**
+ * 元数据清理配置
+ *
+ * 上传前删除 EXIF / XMP / IPTC 等数据块（GPS 坐标、设备序列号等），不重新编码像素
+ */
+export interface MetadataStrippingConfig {
+  /** 是否启用，默认 true */
+  enabled?: boolean;
+  /** 按图床覆盖 enabled，如 `{ r2: false }` 让私有存储桶保留元数据 */
+  services?: Partial<Record<ServiceType, boolean>>;
+}
+
+/**
+ * 水印方案
+ *
+ * 文字水印或 Logo 图片，由后端在上传前添加（原文件不变）
+ */
+export interface WatermarkProfile {
+  id: string;
+  name: string;
+  /** 水印类型，默认 text */
+  kind?: 'text' | 'image';
+  /** 文字内容（可以多行） */
+  text?: string;
+  /** 字体文件路径（TTF / OTF / TTC），为空时使用系统字体 */
+  fontPath?: string;
+  /** 字号（像素），默认 24 */
+  fontSize?: number;
+  /** 文字颜色（#RRGGBB），默认 #FFFFFF */
+  color?: string;
+  /** Logo 图片路径（建议使用带透明通道的 PNG） */
+  imagePath?: string;
+  /** Logo 宽度占图片宽度的比例，默认 0.2 */
+  imageScale?: number;
+  /** 不透明度（0-1），默认 0.5 */
+  opacity?: number;
+  /** 位置（平铺时忽略），默认 bottomRight */
+  position?: 'topLeft' | 'top' | 'topRight' | 'left' | 'center' | 'right' | 'bottomLeft' | 'bottom' | 'bottomRight';
+  /** 与图片边缘的距离（像素），默认 16 */
+  margin?: number;
+  /** 平铺整张图片 */
+  tile?: boolean;
+  /** 平铺时水印之间的间距（像素），默认 120 */
+  tileSpacing?: number;
+  /** 宽度小于该值的图片不加水印 */
+  minWidth?: number;
+  /** 高度小于该值的图片不加水印 */
+  minHeight?: number;
+}
+
+/**
+ * 水印配置（多方案，与 WebDAV 配置相同的组织方式）
+ */
+export interface WatermarkConfig {
+  profiles: WatermarkProfile[];
+  /** 当前使用的方案 ID，为 null 时不加水印 */
+  activeId: string | null;
+}
+
+/**
+ * 上传前处理的结果摘要（后端上传命令结果中的 processing）
+ */
+export interface ProcessingSummary {
+  originalSize: number;
+  processedSize: number;
+  originalFormat: string;
+  format: string;
+  originalWidth: number;
+  originalHeight: number;
+  width: number;
+  height: number;
+  /** 是否重新编码（重新编码的输出不含元数据） */
+  reencoded: boolean;
+  /** 是否添加了水印 */
+  watermarked: boolean;
+  /** 是否为满足图床的大小 / 格式限制做了调整 */
+  fitted: boolean;
+  /** 是否按 EXIF 方向旋转了像素 */
+  oriented: boolean;
+  /** 是否在不重新编码的情况下删除了元数据 */
+  metadataStripped: boolean;
+}
+
+/**
+ * 近似重复的历史记录（后端 find_near_duplicates / find_duplicate_clusters 返回）
+ */
+export interface SimilarHistoryItem {
+  historyId: string;
+  fileName: string;
+  filePath: string | null;
+  primaryService: ServiceType;
+  /** 主力图床链接 */
+  url: string;
+  timestamp: number;
+  /** 十六进制的感知哈希（dHash） */
+  phash: string;
+  /** 汉明距离，0 表示哈希完全相同；分组中为与最早一条的距离 */
+  distance: number;
+}
+
+/**
+ * 一组近似重复的历史记录（按上传时间排序）
+ */
+export interface DuplicateCluster {
+  items: SimilarHistoryItem[];
 }
 
 /**
@@ -117,7 +117,10 @@ export abstract class BaseS3StorageManager implements IStorageManager {
       region: this.getRegion(),
       bucket: this.getBucket(),
       key: remotePath,
-      publicDomain: this.getPublicDomain()
+      publicDomain: this.getPublicDomain(),
+      serviceId: this.serviceId,
+      // 存储管理器按指定路径上传，不能返回其他路径下的缓存结果
+      forceUpload: true
     });
 
     return (result as any).url;
@@ -16,7 +16,8 @@ export type AppErrorType =
   | 'SERVICE_UNAVAILABLE'
   | 'VALIDATION'
   | 'WEBDAV'
-  | 'STORAGE';
+  | 'STORAGE'
+  | 'CANCELLED';
 
 /**
  * 基础错误数据结构（只有 message）
@@ -57,7 +58,8 @@ export type AppError =
   | { type: 'SERVICE_UNAVAILABLE'; data: ServiceUnavailableErrorData }
   | { type: 'VALIDATION'; data: SimpleErrorData }
   | { type: 'WEBDAV'; data: SimpleErrorData }
-  | { type: 'STORAGE'; data: SimpleErrorData };
+  | { type: 'STORAGE'; data: SimpleErrorData }
+  | { type: 'CANCELLED'; data: SimpleErrorData };
 
 /**
  * 检查错误是否为 AppError 结构
@@ -84,6 +86,7 @@ export function isAppError(error: unknown): error is AppError {
     'VALIDATION',
     'WEBDAV',
     'STORAGE',
+    'CANCELLED',
   ];
 
   return validTypes.includes(obj.type as AppErrorType);
@@ -144,6 +147,13 @@ export function isNetworkError(error: unknown): boolean {
   return isAppError(error) && error.type === 'NETWORK';
 }
 
+/**
+ * 检查是否为用户取消
+ */
+export function isCancelledError(error: unknown): boolean {
+  return isAppError(error) && error.type === 'CANCELLED';
+}
+
 /**
  * 检查是否为上传错误
  */
@@ -8,7 +8,8 @@ import {
   ValidationResult,
   UploadOptions,
   ConnectionTestResult,
-  ProgressCallback
+  ProgressCallback,
+  RustUploadResult
 } from './types';
 import { getErrorMessage, isAuthError } from '../../types/errors';
 
@@ -23,6 +24,9 @@ interface ProgressEvent {
   step?: string;         // 可选：当前步骤描述（如"获取Token中..."）
   step_index?: number;   // 可选：当前步骤索引（从1开始）
   total_steps?: number;  // 可选：总步骤数
+  speed?: number;        // 可选：发送速度（字节/秒）
+  eta?: number;          // 可选：预计剩余时间（秒）
+  queue_wait?: number;   // 可选：在后端限流队列中等待的时间（毫秒）
 }
 
 /**
@@ -218,6 +222,16 @@ export abstract class BaseUploader implements IUploader {
     }
   }
 
+  /**
+   * 辅助方法：把 Rust 命令结果中的图片处理摘要写入 metadata.processing（随历史记录保存）
+   */
+  protected withProcessing(result: UploadResult, rustResult: RustUploadResult): UploadResult {
+    if (!rustResult.processing) {
+      return result;
+    }
+    return { ...result, metadata: { ...result.metadata, processing: rustResult.processing } };
+  }
+
   /**
    * 测试连接（默认实现，子类可覆盖）
    * 默认返回未实现
@@ -1,5 +1,7 @@
 // 共享类型定义
 
+import type { ProcessingSummary } from '../../config/types';
+
 /**
  * 上传结果接口
  * 所有上传器返回的标准化结果格式
@@ -27,6 +29,14 @@ export interface UploadResult {
   metadata?: Record<string, any>;
 }
 
+/**
+ * Rust 上传命令结果的公共字段
+ */
+export interface RustUploadResult {
+  /** 后端在上传前处理了图片（压缩、格式转换、水印或元数据清理）时的处理摘要 */
+  processing?: ProcessingSummary;
+}
+
 /**
  * 配置验证结果
  */
@@ -57,6 +67,9 @@ export interface UploadOptions {
 
   /** 最大重试次数 */
   maxRetries?: number;
+
+  /** 跳过去重缓存，强制重新上传（默认相同内容直接返回上次的结果） */
+  forceUpload?: boolean;
 }
 
 /**
@@ -1,13 +1,13 @@
 // 哔哩哔哩图床上传器实现
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { BilibiliServiceConfig } from '../../config/types';
 
 /**
  * Rust 返回的哔哩哔哩上传结果
  */
-interface BilibiliRustResult {
+interface BilibiliRustResult extends RustUploadResult {
   url: string;
   size: number;
 }
@@ -70,18 +70,18 @@ export class BilibiliUploader extends BaseUploader {
       // 调用基类的 Rust 上传方法
       const rustResult = await this.uploadViaRust(
         filePath,
-        { bilibiliCookie: config.cookie },
+        { bilibiliCookie: config.cookie, forceUpload: options.forceUpload },
         onProgress
       ) as BilibiliRustResult;
 
       this.log('info', '哔哩哔哩上传成功', { url: rustResult.url });
 
-      return {
+      return this.withProcessing({
         serviceId: 'bilibili',
         fileKey: rustResult.url,
         url: rustResult.url,
         size: rustResult.size
-      };
+      }, rustResult);
     } catch (error) {
       this.log('error', '哔哩哔哩上传失败', error);
       throw error;
@@ -1,13 +1,13 @@
 // 超星图床上传器实现
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { ChaoxingServiceConfig } from '../../config/types';
 
 /**
  * Rust 返回的超星上传结果
  */
-interface ChaoxingRustResult {
+interface ChaoxingRustResult extends RustUploadResult {
   url: string;
   size: number;
 }
@@ -70,18 +70,18 @@ export class ChaoxingUploader extends BaseUploader {
       // 调用基类的 Rust 上传方法
       const rustResult = await this.uploadViaRust(
         filePath,
-        { chaoxingCookie: config.cookie },
+        { chaoxingCookie: config.cookie, forceUpload: options.forceUpload },
         onProgress
       ) as ChaoxingRustResult;
 
       this.log('info', '超星上传成功', { url: rustResult.url });
 
-      return {
+      return this.withProcessing({
         serviceId: 'chaoxing',
         fileKey: rustResult.url,
         url: rustResult.url,
         size: rustResult.size
-      };
+      }, rustResult);
     } catch (error) {
       this.log('error', '超星上传失败', error);
       throw error;
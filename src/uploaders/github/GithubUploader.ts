@@ -1,9 +1,9 @@
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { transformGithubUrl, parseGithubRawUrl } from '../../utils/githubCdn';
 import type { GithubServiceConfig } from '../../config/types';
 
-interface GithubRustResult {
+interface GithubRustResult extends RustUploadResult {
   url: string;
   sha?: string;
   remotePath?: string;
@@ -61,7 +61,8 @@ export class GithubUploader extends BaseUploader {
         owner: config.owner,
         repo: config.repo,
         branch: config.branch || 'main',
-        path: config.path || 'images/'
+        path: config.path || 'images/',
+        forceUpload: options.forceUpload
       },
       onProgress
     ) as GithubRustResult;
@@ -71,7 +72,7 @@ export class GithubUploader extends BaseUploader {
 
     this.log('info', 'GitHub 上传成功', { rawUrl: rustResult.url, finalUrl });
 
-    return {
+    return this.withProcessing({
       serviceId: 'github',
       fileKey: rustResult.sha || rustResult.remotePath || rustResult.url,
       url: finalUrl,
@@ -80,7 +81,7 @@ export class GithubUploader extends BaseUploader {
         remotePath: rustResult.remotePath,
         rawUrl: rustResult.url
       }
-    };
+    }, rustResult);
   }
 
   private applyUrlTransform(rawUrl: string, config: GithubServiceConfig): string {
@@ -1,7 +1,7 @@
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 
-interface ImgurRustResult {
+interface ImgurRustResult extends RustUploadResult {
   url: string;
   deleteHash?: string;
 }
@@ -36,21 +36,22 @@ export class ImgurUploader extends BaseUploader {
       filePath,
       {
         imgurClientId: options.config.clientId,
-        imgurClientSecret: options.config.clientSecret
+        imgurClientSecret: options.config.clientSecret,
+        forceUpload: options.forceUpload
       },
       onProgress
     ) as ImgurRustResult;
 
     this.log('info', 'Imgur 上传成功', { url: rustResult.url });
 
-    return {
+    return this.withProcessing({
       serviceId: 'imgur',
       fileKey: rustResult.deleteHash || rustResult.url,
       url: rustResult.url,
       metadata: {
         deleteHash: rustResult.deleteHash
       }
-    };
+    }, rustResult);
   }
 
   getPublicUrl(result: UploadResult): string {
@@ -1,11 +1,11 @@
 import { JDRateLimiter } from './JDRateLimiter';
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 
 /**
  * Rust 返回的京东上传结果
  */
-interface JDRustResult {
+interface JDRustResult extends RustUploadResult {
   url: string;
   size: number;
 }
@@ -38,7 +38,7 @@ export class JDUploader extends BaseUploader {
    */
   async upload(
     filePath: string,
-    _options: UploadOptions,
+    options: UploadOptions,
     onProgress?: ProgressCallback
   ): Promise<UploadResult> {
     this.log('info', '开始上传到京东', { filePath });
@@ -59,18 +59,18 @@ export class JDUploader extends BaseUploader {
 
         const rustResult = await this.uploadViaRust(
           filePath,
-          {},
+          { forceUpload: options.forceUpload },
           onProgress
         ) as JDRustResult;
 
         this.log('info', '京东上传成功', { url: rustResult.url });
 
-        return {
+        return this.withProcessing({
           serviceId: 'jd',
           fileKey: rustResult.url,
           url: rustResult.url,
           size: rustResult.size
-        };
+        }, rustResult);
 
       } catch (error: any) {
         lastError = error;
@@ -1,7 +1,7 @@
 // 纳米图床上传器实现
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { NamiServiceConfig } from '../../config/types';
 import { invoke } from '@tauri-apps/api/core';
 
@@ -16,7 +16,7 @@ interface NamiDynamicHeaders {
 /**
  * Rust 返回的纳米上传结果
  */
-interface NamiRustResult {
+interface NamiRustResult extends RustUploadResult {
   url: string;
   size: number;
   instant: boolean;  // 是否秒传
@@ -181,7 +181,9 @@ export class NamiUploader extends BaseUploader {
         filePath,
         {
           cookie: config.cookie,
-          authToken: config.authToken
+          authToken: config.authToken,
+          partSizeMb: config.partSizeMb,
+          forceUpload: options.forceUpload
         },
         onProgress
       ) as NamiRustResult;
@@ -191,12 +193,12 @@ export class NamiUploader extends BaseUploader {
         instant: rustResult.instant
       });
 
-      return {
+      return this.withProcessing({
         serviceId: 'nami',
         fileKey: rustResult.url,
         url: rustResult.url,
         size: rustResult.size
-      };
+      }, rustResult);
     } catch (error: any) {
       this.log('error', '纳米图床上传失败', error);
 
@@ -1,13 +1,13 @@
 // 牛客图床上传器实现
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { NowcoderServiceConfig } from '../../config/types';
 
 /**
  * Rust 返回的牛客上传结果
  */
-interface NowcoderRustResult {
+interface NowcoderRustResult extends RustUploadResult {
   url: string;
   size: number;
 }
@@ -62,18 +62,18 @@ export class NowcoderUploader extends BaseUploader {
       // 调用基类的 Rust 上传方法
       const rustResult = await this.uploadViaRust(
         filePath,
-        { nowcoderCookie: config.cookie },
+        { nowcoderCookie: config.cookie, forceUpload: options.forceUpload },
         onProgress
       ) as NowcoderRustResult;
 
       this.log('info', '牛客图床上传成功', { url: rustResult.url });
 
-      return {
+      return this.withProcessing({
         serviceId: 'nowcoder',
         fileKey: rustResult.url,
         url: rustResult.url,
         size: rustResult.size
-      };
+      }, rustResult);
     } catch (error: any) {
       this.log('error', '牛客图床上传失败', error);
       throw new Error(`牛客图床上传失败: ${error.message || error.toString()}`);
@@ -4,9 +4,9 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 
-interface QiyuRustResult {
+interface QiyuRustResult extends RustUploadResult {
   url: string;
   size: number;
 }
@@ -41,22 +41,22 @@ export class QiyuUploader extends BaseUploader {
 
   async upload(
     filePath: string,
-    _options: UploadOptions,
+    options: UploadOptions,
     onProgress?: ProgressCallback
   ): Promise<UploadResult> {
     // Token 现在由后端自动获取，不再需要传递
     const rustResult = await this.uploadViaRust(
       filePath,
-      {}, // 无需额外参数
+      { forceUpload: options.forceUpload },
       onProgress
     ) as QiyuRustResult;
 
-    return {
+    return this.withProcessing({
       serviceId: 'qiyu',
       fileKey: rustResult.url,
       url: rustResult.url,
       size: rustResult.size
-    };
+    }, rustResult);
   }
 
   getPublicUrl(result: UploadResult): string {
@@ -2,9 +2,9 @@
 // 支持：腾讯云 COS、阿里云 OSS、七牛云、又拍云
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 
-interface S3RustResult {
+interface S3RustResult extends RustUploadResult {
   url: string;
   key: string;
 }
@@ -83,21 +83,23 @@ export abstract class BaseS3Uploader extends BaseUploader {
         region: this.getRegion(config),
         bucket: this.getBucket(config),
         key,
-        publicDomain: this.getPublicDomain(config)
+        publicDomain: this.getPublicDomain(config),
+        serviceId: this.serviceId,
+        forceUpload: options.forceUpload
       },
       onProgress
     ) as S3RustResult;
 
     this.log('info', `${this.serviceName} 上传成功`, { url: rustResult.url });
 
-    return {
+    return this.withProcessing({
       serviceId: this.serviceId,
       fileKey: rustResult.key,
       url: rustResult.url,
       metadata: {
         key: rustResult.key
       }
-    };
+    }, rustResult);
   }
 
   getPublicUrl(result: UploadResult): string {
@@ -1,7 +1,7 @@
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 
-interface SmmsRustResult {
+interface SmmsRustResult extends RustUploadResult {
   url: string;
   delete?: string;
   hash?: string;
@@ -35,20 +35,20 @@ export class SmmsUploader extends BaseUploader {
 
     const rustResult = await this.uploadViaRust(
       filePath,
-      { smmsToken: options.config.token },
+      { smmsToken: options.config.token, forceUpload: options.forceUpload },
       onProgress
     ) as SmmsRustResult;
 
     this.log('info', 'SM.MS 上传成功', { url: rustResult.url });
 
-    return {
+    return this.withProcessing({
       serviceId: 'smms',
       fileKey: rustResult.hash || rustResult.url,
       url: rustResult.url,
       metadata: {
         deleteUrl: rustResult.delete
       }
-    };
+    }, rustResult);
   }
 
   getPublicUrl(result: UploadResult): string {
@@ -1,14 +1,14 @@
 // 微博图床上传器实现
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { WeiboServiceConfig } from '../../config/types';
 import { convertToWeiboError } from './WeiboError';
 
 /**
  * Rust 返回的微博上传结果
  */
-interface WeiboRustResult {
+interface WeiboRustResult extends RustUploadResult {
   pid: string;
   width: number;
   height: number;
@@ -64,7 +64,7 @@ export class WeiboUploader extends BaseUploader {
       // 调用基类的 Rust 上传方法
       const rustResult = await this.uploadViaRust(
         filePath,
-        { weiboCookie: config.cookie },
+        { weiboCookie: config.cookie, forceUpload: options.forceUpload },
         onProgress
       ) as WeiboRustResult;
 
@@ -75,7 +75,7 @@ export class WeiboUploader extends BaseUploader {
 
       this.log('info', '微博上传成功', { pid, url });
 
-      return {
+      return this.withProcessing({
         serviceId: 'weibo',
         fileKey: pid,
         url: url,
@@ -86,7 +86,7 @@ export class WeiboUploader extends BaseUploader {
           hashName: hashName,
           pid: pid
         }
-      };
+      }, rustResult);
     } catch (error) {
       this.log('error', '微博上传失败', error);
       throw convertToWeiboError(error);
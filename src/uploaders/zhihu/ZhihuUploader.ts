@@ -1,13 +1,13 @@
 // 知乎图床上传器实现
 
 import { BaseUploader } from '../base/BaseUploader';
-import { UploadResult, ValidationResult, UploadOptions, ProgressCallback } from '../base/types';
+import { UploadResult, ValidationResult, UploadOptions, ProgressCallback, RustUploadResult } from '../base/types';
 import { ZhihuServiceConfig } from '../../config/types';
 
 /**
  * Rust 返回的知乎上传结果
  */
-interface ZhihuRustResult {
+interface ZhihuRustResult extends RustUploadResult {
   url: string;
   size: number;
 }
@@ -62,18 +62,18 @@ export class ZhihuUploader extends BaseUploader {
       // 调用基类的 Rust 上传方法
       const rustResult = await this.uploadViaRust(
         filePath,
-        { zhihuCookie: config.cookie },
+        { zhihuCookie: config.cookie, forceUpload: options.forceUpload },
         onProgress
       ) as ZhihuRustResult;
 
       this.log('info', '知乎图床上传成功', { url: rustResult.url });
 
-      return {
+      return this.withProcessing({
         serviceId: 'zhihu',
         fileKey: rustResult.url,
         url: rustResult.url,
         size: rustResult.size
-      };
+      }, rustResult);
     } catch (error: any) {
       this.log('error', '知乎图床上传失败', error);
       throw new Error(`知乎图床上传失败: ${error.message || error.toString()}`);
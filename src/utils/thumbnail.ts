@@ -0,0 +1,36 @@
+/**
+ * 本地缩略图缓存（thumb:// 协议）
+ * 缩略图由后端生成并缓存到应用缓存目录，见 src-tauri/src/thumbnail.rs
+ */
+
+import { convertFileSrc } from '@tauri-apps/api/core';
+
+/** 列表缩略图尺寸 */
+export const THUMBNAIL_SIZE_SMALL = 150;
+/** 悬浮预览和时间线视图的缩略图尺寸 */
+export const THUMBNAIL_SIZE_MEDIUM = 400;
+
+// 协议地址前缀（macOS/Linux 为 thumb://localhost/，Windows 为 http://thumb.localhost/）
+let protocolBase: string | undefined;
+
+/**
+ * 生成本地缩略图地址
+ * @param url 图片链接（缓存按链接区分）
+ * @param size 最长边像素，后端按配置的尺寸取整
+ * @param source 缓存未命中时下载的地址（如图床提供的缩略图），默认下载原图
+ */
+export function localThumbnailUrl(url: string, size: number, source?: string): string {
+  protocolBase ??= convertFileSrc('', 'thumb');
+  let thumbUrl = `${protocolBase}${size}?url=${encodeURIComponent(url)}`;
+  if (source && source !== url) {
+    thumbUrl += `&src=${encodeURIComponent(source)}`;
+  }
+  return thumbUrl;
+}
+
+/**
+ * 是否使用本地缩略图缓存（默认开启）
+ */
+export function isLocalThumbnailEnabled(config: any): boolean {
+  return config?.thumbnails?.enabled !== false;
+}